@@ -0,0 +1,132 @@
+//! Runtime DMA channel allocation
+//!
+//! The VideoCore firmware keeps some of the channels for itself, the
+//! remaining ones are reported by the mailbox "get DMA channels" property.
+
+use crate::dma::{Channel, Dma4Channel, Parts};
+use crate::mailbox::{self, GetDmaChannelsRepr, Mailbox, RespMsg};
+use bcm2711::dma::{EnableRegister, IntStatusRegister, DMA4_CHANNEL_START, NUM_CHANNELS};
+
+const NUM_DMA4_CHANNELS: usize = NUM_CHANNELS - DMA4_CHANNEL_START;
+
+/// Channel capability requested from an [`Allocator`](struct.Allocator.html)
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Capability {
+    /// A standard channel, supports 2D mode and 30-bit transfer lengths
+    TwoD,
+    /// A LITE channel, linear mode only with 16-bit transfer lengths
+    Lite,
+    /// Either of the above, LITE channels are handed out first
+    Any,
+}
+
+/// Hands out the channels that are not reserved by the firmware
+///
+/// Channels are enabled in the global enable register when taken.
+pub struct Allocator {
+    channels: [Option<Channel>; DMA4_CHANNEL_START],
+    dma4_channels: [Option<Dma4Channel>; NUM_DMA4_CHANNELS],
+    pub int_status: IntStatusRegister,
+    enable: EnableRegister,
+}
+
+impl Allocator {
+    /// Bit N of `mask` is set when channel N may be used by the ARM
+    pub fn new(parts: Parts, mask: u16) -> Self {
+        let usable = |index: usize| mask & (1 << index) != 0;
+        let ch = |c: Channel| if usable(c.index()) { Some(c) } else { None };
+        let ch4 = |c: Dma4Channel| if usable(c.index()) { Some(c) } else { None };
+
+        Allocator {
+            channels: [
+                ch(parts.ch0),
+                ch(parts.ch1),
+                ch(parts.ch2),
+                ch(parts.ch3),
+                ch(parts.ch4),
+                ch(parts.ch5),
+                ch(parts.ch6),
+                ch(parts.ch7),
+                ch(parts.ch8),
+                ch(parts.ch9),
+                ch(parts.ch10),
+            ],
+            dma4_channels: [
+                ch4(parts.ch11),
+                ch4(parts.ch12),
+                ch4(parts.ch13),
+                ch4(parts.ch14),
+            ],
+            int_status: parts.int_status,
+            enable: parts.enable,
+        }
+    }
+
+    /// Query the firmware for the free channels
+    pub fn from_mailbox(parts: Parts, mbox: &mut Mailbox) -> mailbox::Result<Self> {
+        let resp = mbox.call(mailbox::Channel::Prop, &GetDmaChannelsRepr::default())?;
+
+        if let RespMsg::GetDmaChannels(repr) = resp {
+            Ok(Allocator::new(parts, repr.mask()))
+        } else {
+            Err(mailbox::Error::Malformed)
+        }
+    }
+
+    /// Bitmask of the channels currently available
+    pub fn available(&self) -> u16 {
+        let standard = self
+            .channels
+            .iter()
+            .flatten()
+            .fold(0, |mask, c| mask | (1 << c.index()));
+        self.dma4_channels
+            .iter()
+            .flatten()
+            .fold(standard, |mask, c| mask | (1 << c.index()))
+    }
+
+    pub fn take(&mut self, capability: Capability) -> Option<Channel> {
+        let index = match capability {
+            Capability::TwoD => self.find(false),
+            Capability::Lite => self.find(true),
+            Capability::Any => self.find(true).or_else(|| self.find(false)),
+        }?;
+
+        self.enable_channel(index);
+        self.channels[index].take()
+    }
+
+    /// Take a 40-bit DMA4 channel
+    pub fn take_dma4(&mut self) -> Option<Dma4Channel> {
+        let index = self.dma4_channels.iter().flatten().next()?.index();
+
+        self.enable_channel(index);
+        self.dma4_channels[index - DMA4_CHANNEL_START].take()
+    }
+
+    /// Return a channel to the pool
+    pub fn release(&mut self, channel: Channel) {
+        let index = channel.index();
+        self.channels[index] = Some(channel);
+    }
+
+    /// Return a DMA4 channel to the pool
+    pub fn release_dma4(&mut self, channel: Dma4Channel) {
+        let index = channel.index() - DMA4_CHANNEL_START;
+        self.dma4_channels[index] = Some(channel);
+    }
+
+    fn find(&self, lite: bool) -> Option<usize> {
+        self.channels
+            .iter()
+            .flatten()
+            .find(|c| c.is_lite() == lite)
+            .map(|c| c.index())
+    }
+
+    fn enable_channel(&mut self, index: usize) {
+        let en = self.enable.enable.read();
+        self.enable.enable.write(en | (1 << index));
+    }
+}
@@ -0,0 +1,158 @@
+//! Linked control block chains
+//!
+//! Control blocks are linked via their `next` field so a single channel
+//! start can cover several regions (scatter-gather).
+//!
+//! ```rust,ignore
+//! static mut DCB_MEM: [dma::ControlBlock; 2] =
+//!     [dma::ControlBlock::new(), dma::ControlBlock::new()];
+//!
+//! let mut builder = dma::ChainBuilder::new(unsafe { &mut DCB_MEM });
+//! builder.copy(&src_a, &mut dest_a)?;
+//! builder.copy(&src_b, &mut dest_b)?;
+//! let chain = builder.build();
+//!
+//! dma_chan.start_chain(&chain);
+//! dma_chan.wait_chain(&chain)?;
+//! ```
+
+use crate::dma::transfer::check_buffers;
+use crate::dma::{maintain_buffers, ControlBlock, Error, TransferLength, TransferWidth};
+use core::marker::PhantomData;
+use core::mem;
+
+/// Builds a chain of control blocks in caller provided control block memory
+///
+/// The buffers are borrowed for the lifetime of the resulting `Chain`, their
+/// cache maintenance is done as each block is pushed.
+pub struct ChainBuilder<'dcb, 'buf, T> {
+    dcbs: &'dcb mut [ControlBlock],
+    len: usize,
+    dest_cached: bool,
+    _buffers: PhantomData<&'buf mut [T]>,
+}
+
+impl<'dcb, 'buf, T> ChainBuilder<'dcb, 'buf, T> {
+    pub fn new(dcbs: &'dcb mut [ControlBlock]) -> Self {
+        ChainBuilder {
+            dcbs,
+            len: 0,
+            dest_cached: false,
+            _buffers: PhantomData,
+        }
+    }
+
+    /// Number of blocks pushed so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append a block, `config` sets up the block (which was initialized to
+    /// zero). The `next` field is managed by the builder.
+    ///
+    /// `src_cached`/`dest_cached` select which side gets cache maintenance,
+    /// like the fields of `TransferResources`. Cached destinations are also
+    /// invalidated by `Channel::wait_chain`.
+    ///
+    /// The memory read or written by the block must be within the buffers,
+    /// a side paced by a DREQ is a peripheral and isn't checked (pass an
+    /// empty slice for it).
+    pub fn push<F>(
+        &mut self,
+        src_buffer: &'buf [T],
+        src_cached: bool,
+        dest_buffer: &'buf mut [T],
+        dest_cached: bool,
+        config: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: FnOnce(&mut ControlBlock),
+    {
+        if self.len >= self.dcbs.len() {
+            return Err(Error::ChainFull);
+        }
+
+        let dcb = &mut self.dcbs[self.len];
+        dcb.init();
+        config(dcb);
+        dcb.next = 0;
+
+        check_buffers(
+            dcb,
+            (src_buffer.as_ptr() as usize, mem::size_of_val(src_buffer)),
+            (dest_buffer.as_ptr() as usize, mem::size_of_val(dest_buffer)),
+        );
+
+        maintain_buffers(dcb, src_cached, dest_cached);
+
+        if self.len != 0 {
+            let paddr = self.dcbs[self.len].as_paddr() as u32;
+            self.dcbs[self.len - 1].set_next(paddr);
+        }
+
+        self.len += 1;
+        self.dest_cached |= dest_cached;
+
+        Ok(self)
+    }
+
+    /// Append a linear copy of `src` into `dest`, both in cached memory
+    ///
+    /// Copies the smaller of the two buffers.
+    pub fn copy(&mut self, src: &'buf [T], dest: &'buf mut [T]) -> Result<&mut Self, Error> {
+        let len = mem::size_of_val(src).min(mem::size_of_val(dest));
+        let src_paddr = src.as_ptr() as u32;
+        let dest_paddr = dest.as_ptr() as u32;
+
+        self.push(src, true, dest, true, |dcb| {
+            dcb.set_length(TransferLength::ModeLinear(len as u32));
+            dcb.set_src(src_paddr);
+            dcb.set_src_width(TransferWidth::Bits32);
+            dcb.info.set_src_inc(true);
+            dcb.set_dest(dest_paddr);
+            dcb.set_dest_width(TransferWidth::Bits32);
+            dcb.info.set_dest_inc(true);
+            dcb.info.set_wait_resp(true);
+        })
+    }
+
+    pub fn build(self) -> Chain<'dcb, 'buf, T> {
+        let dcbs: &'dcb [ControlBlock] = self.dcbs;
+        Chain {
+            dcbs: &dcbs[..self.len],
+            dest_cached: self.dest_cached,
+            _buffers: PhantomData,
+        }
+    }
+}
+
+/// A chain of linked control blocks, started with `Channel::start_chain`
+pub struct Chain<'dcb, 'buf, T> {
+    dcbs: &'dcb [ControlBlock],
+    /// Some block has a cached destination
+    dest_cached: bool,
+    _buffers: PhantomData<&'buf mut [T]>,
+}
+
+impl<'dcb, 'buf, T> Chain<'dcb, 'buf, T> {
+    pub fn control_blocks(&self) -> &[ControlBlock] {
+        self.dcbs
+    }
+
+    /// Drops any lines of the destinations speculatively loaded while the
+    /// DMA was running
+    pub(crate) fn invalidate_destinations(&self) {
+        if !self.dest_cached {
+            return;
+        }
+        for dcb in self.dcbs.iter() {
+            if !dcb.info.dest_dreq() && !dcb.info.dest_ignore() {
+                maintain_buffers(dcb, false, true);
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! DMA control block
 
 use crate::cache::bus_address_bits;
+use crate::dma::dreq::{peripheral_bus_address, Dreq};
 use bitfield::bitfield;
 use core::fmt;
 use static_assertions::{assert_eq_size, const_assert_eq};
@@ -14,6 +15,8 @@ pub const TRANSFER_LENGTH_MAX_LITE: u32 = 0xFFFF;
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TransferLength {
     ModeLinear(u32),
+    /// Bytes per row and the number of rows minus one, rows are separated by
+    /// the source/destination strides in `StrideWord`
     Mode2D(u16, u16),
 }
 
@@ -185,6 +188,30 @@ impl ControlBlock {
         self.dest = dest | bus_address_bits::ALIAS_4_L2_COHERENT;
     }
 
+    /// Read from a peripheral register, paced by `dreq`.
+    /// The source address doesn't increment.
+    ///
+    /// NOTE: the ARM physical address of the register will be translated to
+    /// a peripheral bus address for the DMA engine
+    pub fn set_src_periph(&mut self, paddr: usize, dreq: Dreq) {
+        self.src = peripheral_bus_address(paddr);
+        self.info.set_src_inc(false);
+        self.info.set_src_dreq(true);
+        self.info.set_periph_map(dreq.into());
+    }
+
+    /// Write to a peripheral register, paced by `dreq`.
+    /// The destination address doesn't increment.
+    ///
+    /// NOTE: the ARM physical address of the register will be translated to
+    /// a peripheral bus address for the DMA engine
+    pub fn set_dest_periph(&mut self, paddr: usize, dreq: Dreq) {
+        self.dest = peripheral_bus_address(paddr);
+        self.info.set_dest_inc(false);
+        self.info.set_dest_dreq(true);
+        self.info.set_periph_map(dreq.into());
+    }
+
     pub fn set_length(&mut self, length: TransferLength) {
         match length {
             TransferLength::ModeLinear(l) => self.length.0 = l,
@@ -0,0 +1,248 @@
+//! DMA4 (40-bit) control block
+//!
+//! DMA4 channels address memory with 40-bit ARM physical addresses instead
+//! of going through the 1 GB VideoCore bus alias.
+
+use crate::dma::control_block::{TransferLength, TxfrLenWord};
+use crate::dma::dreq::{dma4_peripheral_bus_address, Dreq};
+use bitfield::bitfield;
+use core::fmt;
+use static_assertions::{assert_eq_size, const_assert_eq};
+
+pub const DMA4_CONTROL_BLOCK_SIZE: usize = 8 * 4;
+
+/// Largest 40-bit address
+pub const DMA4_ADDRESS_MAX: u64 = 0xFF_FFFF_FFFF;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dma4TransferWidth {
+    Bits32,
+    Bits64,
+    Bits128,
+    Bits256,
+}
+
+/// 8 words (256 bits or 32 bytes) in length and must start at a 256-bit aligned
+/// address
+#[derive(Debug)]
+#[repr(C, align(32))]
+pub struct Dma4ControlBlock {
+    /// Transfer info
+    pub info: Dma4TxfrInfoWord,
+    /// Source address bits 31:0
+    pub src: u32,
+    /// Source address bits 39:32, width, increment and stride
+    pub src_info: Dma4AddrInfoWord,
+    /// Destination address bits 31:0
+    pub dest: u32,
+    /// Destination address bits 39:32, width, increment and stride
+    pub dest_info: Dma4AddrInfoWord,
+    /// Transfer length
+    pub length: TxfrLenWord,
+    /// Next control block address, shifted right by 5
+    pub next: u32,
+    #[doc(hidden)]
+    _reserved_0: u32,
+}
+
+assert_eq_size!(Dma4ControlBlock, [u32; 8]);
+const_assert_eq!(DMA4_CONTROL_BLOCK_SIZE, 32);
+
+impl fmt::Display for Dma4ControlBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "DMA4 DCB {{ info: 0x{:X}, src: 0x{:X}, src_info: 0x{:X}, dest: 0x{:X}, dest_info: 0x{:X}, length: 0x{:X}, next: 0x{:X} }}",
+            self.info.0,
+            self.src,
+            self.src_info.0,
+            self.dest,
+            self.dest_info.0,
+            self.length.0,
+            self.next,
+        )
+    }
+}
+
+bitfield! {
+    #[repr(C)]
+    pub struct Dma4TxfrInfoWord(u32);
+    impl Debug;
+    u32;
+    /// Interrupt enable
+    pub int_en, set_int_en : 0;
+    /// 2D mode
+    pub td_mode, set_td_mode : 1;
+    /// Wait for write response
+    pub wait_resp, set_wait_resp : 2;
+    /// Wait for read response
+    pub wait_rd_resp, set_wait_rd_resp : 3;
+    /// Peripheral mapping
+    pub periph_map, set_periph_map : 13, 9;
+    /// Control source reads with DREQ
+    pub src_dreq, set_src_dreq : 14;
+    /// Control destination writes with DREQ
+    pub dest_dreq, set_dest_dreq : 15;
+    /// Wait cycles after each source read
+    pub src_waits, set_src_waits : 23, 16;
+    /// Wait cycles after each destination write
+    pub dest_waits, set_dest_waits : 31, 24;
+}
+
+bitfield! {
+    #[repr(C)]
+    pub struct Dma4AddrInfoWord(u32);
+    impl Debug;
+    u32;
+    /// Address bits 39:32
+    pub addr_high, set_addr_high : 7, 0;
+    /// Burst transfer length
+    pub burst_len, set_burst_len : 11, 8;
+    /// Address increment
+    pub inc, set_inc : 12;
+    /// Transfer width
+    /// 0 = 32-bit, 1 = 64-bit, 2 = 128-bit, 3 = 256-bit
+    pub size, set_size : 14, 13;
+    /// Ignore reads/writes
+    pub ignore, set_ignore : 15;
+    /// Signed 2D stride
+    pub stride, set_stride : 31, 16;
+}
+
+impl Default for Dma4ControlBlock {
+    fn default() -> Self {
+        Dma4ControlBlock::new()
+    }
+}
+
+impl Dma4ControlBlock {
+    pub const fn new() -> Self {
+        Dma4ControlBlock {
+            info: Dma4TxfrInfoWord(0),
+            src: 0,
+            src_info: Dma4AddrInfoWord(0),
+            dest: 0,
+            dest_info: Dma4AddrInfoWord(0),
+            length: TxfrLenWord(0),
+            next: 0,
+            _reserved_0: 0,
+        }
+    }
+
+    pub fn init(&mut self) {
+        *self = Dma4ControlBlock::new();
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const Self {
+        self as *const _
+    }
+
+    #[inline]
+    pub fn as_paddr(&self) -> usize {
+        self.as_ptr() as usize
+    }
+
+    /// 40-bit source address
+    pub fn src(&self) -> u64 {
+        u64::from(self.src) | (u64::from(self.src_info.addr_high()) << 32)
+    }
+
+    /// NOTE: DMA4 uses physical addresses directly, there's no bus alias
+    pub fn set_src(&mut self, src: u64) {
+        assert!(src <= DMA4_ADDRESS_MAX);
+        self.src = src as u32;
+        self.src_info.set_addr_high((src >> 32) as u32);
+    }
+
+    /// 40-bit destination address
+    pub fn dest(&self) -> u64 {
+        u64::from(self.dest) | (u64::from(self.dest_info.addr_high()) << 32)
+    }
+
+    /// NOTE: DMA4 uses physical addresses directly, there's no bus alias
+    pub fn set_dest(&mut self, dest: u64) {
+        assert!(dest <= DMA4_ADDRESS_MAX);
+        self.dest = dest as u32;
+        self.dest_info.set_addr_high((dest >> 32) as u32);
+    }
+
+    /// Read from a peripheral register, paced by `dreq`.
+    /// The source address doesn't increment.
+    ///
+    /// NOTE: the ARM physical address of the register will be translated to
+    /// a peripheral bus address for the DMA engine
+    pub fn set_src_periph(&mut self, paddr: usize, dreq: Dreq) {
+        self.set_src(dma4_peripheral_bus_address(paddr));
+        self.src_info.set_inc(false);
+        self.info.set_src_dreq(true);
+        self.info.set_periph_map(dreq.into());
+    }
+
+    /// Write to a peripheral register, paced by `dreq`.
+    /// The destination address doesn't increment.
+    ///
+    /// NOTE: the ARM physical address of the register will be translated to
+    /// a peripheral bus address for the DMA engine
+    pub fn set_dest_periph(&mut self, paddr: usize, dreq: Dreq) {
+        self.set_dest(dma4_peripheral_bus_address(paddr));
+        self.dest_info.set_inc(false);
+        self.info.set_dest_dreq(true);
+        self.info.set_periph_map(dreq.into());
+    }
+
+    pub fn set_length(&mut self, length: TransferLength) {
+        match length {
+            TransferLength::ModeLinear(l) => self.length.0 = l,
+            TransferLength::Mode2D(x, y) => {
+                self.length.set_xlen(x.into());
+                self.length.set_ylen(y.into());
+                self.info.set_td_mode(true);
+            }
+        }
+    }
+
+    pub fn src_width(&self) -> Dma4TransferWidth {
+        self.src_info.size().into()
+    }
+
+    pub fn set_src_width(&mut self, width: Dma4TransferWidth) {
+        self.src_info.set_size(width.into());
+    }
+
+    pub fn dest_width(&self) -> Dma4TransferWidth {
+        self.dest_info.size().into()
+    }
+
+    pub fn set_dest_width(&mut self, width: Dma4TransferWidth) {
+        self.dest_info.set_size(width.into());
+    }
+
+    /// NOTE: the physical address is shifted right by 5 for the DMA engine
+    pub fn set_next(&mut self, next_dcb_paddr: u64) {
+        assert_eq!(next_dcb_paddr & 0x1F, 0);
+        self.next = (next_dcb_paddr >> 5) as u32;
+    }
+}
+
+impl From<Dma4TransferWidth> for u32 {
+    fn from(w: Dma4TransferWidth) -> u32 {
+        match w {
+            Dma4TransferWidth::Bits32 => 0,
+            Dma4TransferWidth::Bits64 => 1,
+            Dma4TransferWidth::Bits128 => 2,
+            Dma4TransferWidth::Bits256 => 3,
+        }
+    }
+}
+
+impl From<u32> for Dma4TransferWidth {
+    fn from(size: u32) -> Dma4TransferWidth {
+        match size & 0b11 {
+            0 => Dma4TransferWidth::Bits32,
+            1 => Dma4TransferWidth::Bits64,
+            2 => Dma4TransferWidth::Bits128,
+            _ => Dma4TransferWidth::Bits256,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Peripheral DMA requests (DREQ) and peripheral bus addresses
+
+/// ARM physical address of the main peripherals
+const PERIPHERAL_PADDR: usize = 0xFE00_0000;
+
+/// Size of the main peripheral window
+const PERIPHERAL_SIZE: usize = 0x0180_0000;
+
+/// Legacy bus address of the main peripherals, as seen by the DMA engines
+const PERIPHERAL_BUS_ADDR: u32 = 0x7E00_0000;
+
+/// Peripheral mapping (PERMAP) numbers, selects which DREQ paces a transfer
+// TODO - DREQs 23:31
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Dreq {
+    /// Permanently asserted, the transfer isn't paced
+    AlwaysOn = 0,
+    /// Shared with DSI0
+    Pwm1 = 1,
+    PcmTx = 2,
+    PcmRx = 3,
+    Smi = 4,
+    Pwm0 = 5,
+    Spi0Tx = 6,
+    Spi0Rx = 7,
+    BscSpiSlaveTx = 8,
+    BscSpiSlaveRx = 9,
+    Emmc = 11,
+    Uart0Tx = 12,
+    SdHost = 13,
+    Uart0Rx = 14,
+    Dsi1 = 15,
+    Spi1Tx = 16,
+    Hdmi = 17,
+    Spi1Rx = 18,
+    /// Shared with SPI4 Tx
+    Uart3Tx = 19,
+    /// Shared with SPI4 Rx
+    Uart3Rx = 20,
+    /// Shared with SPI5 Tx
+    Uart5Tx = 21,
+    /// Shared with SPI5 Rx
+    Uart5Rx = 22,
+}
+
+impl From<Dreq> for u32 {
+    fn from(dreq: Dreq) -> u32 {
+        dreq as _
+    }
+}
+
+/// Translates the ARM physical address of a peripheral register to the bus
+/// address the DMA engines use
+pub fn peripheral_bus_address(paddr: usize) -> u32 {
+    assert!(
+        paddr >= PERIPHERAL_PADDR && paddr < (PERIPHERAL_PADDR + PERIPHERAL_SIZE),
+        "Not a peripheral register address"
+    );
+    PERIPHERAL_BUS_ADDR + (paddr - PERIPHERAL_PADDR) as u32
+}
+
+/// Offset of the legacy peripheral bus addresses in the 40-bit DMA4 address
+/// space
+const DMA4_PERIPHERAL_OFFSET: u64 = 0x4_0000_0000;
+
+/// Like `peripheral_bus_address`, for the DMA4 engines
+pub fn dma4_peripheral_bus_address(paddr: usize) -> u64 {
+    DMA4_PERIPHERAL_OFFSET | u64::from(peripheral_bus_address(paddr))
+}
@@ -0,0 +1,92 @@
+//! DMA completion and error interrupts
+//!
+//! A channel raises its interrupt when a control block with `int_en` set
+//! completes. Register a callback for the channel, then register
+//! `dma_irq_handler` with the `gic` for the channel's interrupt:
+//!
+//! ```ignore
+//! fn on_dma(channel: usize, event: dma::Event) {
+//!     // ...
+//! }
+//!
+//! dma_chan.listen(on_dma);
+//! gic.register(dma_chan.interrupt(), dma::dma_irq_handler)?;
+//! gic.enable(dma_chan.interrupt())?;
+//! ```
+//!
+//! Channels 7 and 8, and channels 9 and 10 share an interrupt.
+
+use crate::dma::{Channel, Dma4Channel};
+use crate::gic;
+use bcm2711::dma::{IntStatusRegister, CHANNEL_STRIDE, DMA4_CHANNEL_START, NUM_CHANNELS};
+use bcm2711::gic::irq;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Event {
+    /// A control block with `int_en` set completed
+    Complete,
+    /// The channel has error flags set
+    Error,
+}
+
+/// Called from `dma_irq_handler` with the channel number, IRQs are masked
+pub type Callback = fn(usize, Event);
+
+static mut CALLBACKS: [Option<Callback>; NUM_CHANNELS] = [None; NUM_CHANNELS];
+
+pub(crate) fn set_callback(index: usize, callback: Option<Callback>) {
+    gic::free(|| unsafe {
+        CALLBACKS[index] = callback;
+    });
+}
+
+pub(crate) fn interrupt(index: usize) -> u32 {
+    match index {
+        0 => irq::DMA0,
+        1 => irq::DMA1,
+        2 => irq::DMA2,
+        3 => irq::DMA3,
+        4 => irq::DMA4,
+        5 => irq::DMA5,
+        6 => irq::DMA6,
+        7 | 8 => irq::DMA7_8,
+        9 | 10 => irq::DMA9_10,
+        11 => irq::DMA11,
+        12 => irq::DMA12,
+        13 => irq::DMA13,
+        14 => irq::DMA14,
+        _ => unreachable!(),
+    }
+}
+
+/// DMA IRQ handler, to be registered with the `gic` for each channel
+/// interrupt in use
+///
+/// Clears the interrupt of every pending channel and calls its callback.
+pub fn dma_irq_handler() {
+    let int_status = IntStatusRegister::new();
+    let pending = int_status.int_status.read();
+
+    for index in (0..NUM_CHANNELS).filter(|i| pending & (1 << i) != 0) {
+        let offset = index * CHANNEL_STRIDE;
+        let errors = if index < DMA4_CHANNEL_START {
+            let mut chan = Channel::new(index, offset);
+            chan.clear_interrupt();
+            chan.error_flags()
+        } else {
+            let mut chan = Dma4Channel::new(index, offset);
+            chan.clear_interrupt();
+            chan.errors()
+        };
+
+        let event = if errors {
+            Event::Error
+        } else {
+            Event::Complete
+        };
+
+        if let Some(callback) = unsafe { CALLBACKS[index] } {
+            callback(index, event);
+        }
+    }
+}
@@ -1,13 +1,13 @@
 //! DMA
+//!
+//! Channels 0:6 are standard (2D capable), 7:10 are LITE and 11:14 are
+//! DMA4 (40-bit) channels. Some of them are used by the VideoCore firmware,
+//! use an [`Allocator`](struct.Allocator.html) to only hand out the
+//! channels the firmware reports as free.
 
 // TODO
-// - generate all the channels via macros
-// - following https://github.com/rust-embedded/embedded-hal/issues/37#issuecomment-377823801
-// - https://github.com/stm32-rs/stm32f7xx-hal/blob/master/src/dma.rs
-// - https://github.com/stm32-rs/stm32l0xx-hal/pull/14
 // - fix the sync/fences/barriers
 // - allow for cached/uncached in transfer config, enable device/IO mem
-// - assumes we're using cached memory, mem2mem style
 
 use crate::cache::{self, bus_address_bits};
 use bcm2711::dma::*;
@@ -15,12 +15,42 @@ use core::mem;
 use core::sync::atomic::{compiler_fence, Ordering};
 use cortex_a::{asm, barrier};
 
+mod allocator;
+mod chain;
 mod control_block;
+mod dma4_control_block;
+mod dreq;
+mod interrupt;
+mod transfer;
 
+pub use crate::dma::allocator::{Allocator, Capability};
+pub use crate::dma::chain::{Chain, ChainBuilder};
 pub use crate::dma::control_block::{
     ControlBlock, StrideWord, TransferLength, TransferWidth, TxfrInfoWord, TxfrLenWord,
     CONTROL_BLOCK_SIZE, TRANSFER_LENGTH_MAX, TRANSFER_LENGTH_MAX_LITE,
 };
+pub use crate::dma::dma4_control_block::{
+    Dma4AddrInfoWord, Dma4ControlBlock, Dma4TransferWidth, Dma4TxfrInfoWord, DMA4_ADDRESS_MAX,
+    DMA4_CONTROL_BLOCK_SIZE,
+};
+pub use crate::dma::dreq::{dma4_peripheral_bus_address, peripheral_bus_address, Dreq};
+pub use crate::dma::interrupt::{dma_irq_handler, Callback, Event};
+pub use crate::dma::transfer::{ReadBuffer, Transfer, TransferParts, WriteBuffer};
+
+/// Number of polls `Channel::abort` waits for outstanding writes to drain
+const ABORT_TIMEOUT: usize = 10_000;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// `wait_timeout` gave up, the transfer was aborted
+    Timeout,
+    /// Outstanding AXI writes didn't drain while aborting
+    OutstandingWrites,
+    /// Ran out of control blocks while building a chain
+    ChainFull,
+    /// The channel flagged a read, FIFO or AXI error during the transfer
+    ChannelError,
+}
 
 pub trait DmaExt {
     type Parts;
@@ -31,7 +61,19 @@ pub trait DmaExt {
 pub struct Parts {
     pub ch0: Channel,
     pub ch1: Channel,
-    // TODO 2..15
+    pub ch2: Channel,
+    pub ch3: Channel,
+    pub ch4: Channel,
+    pub ch5: Channel,
+    pub ch6: Channel,
+    pub ch7: Channel,
+    pub ch8: Channel,
+    pub ch9: Channel,
+    pub ch10: Channel,
+    pub ch11: Dma4Channel,
+    pub ch12: Dma4Channel,
+    pub ch13: Dma4Channel,
+    pub ch14: Dma4Channel,
     pub int_status: IntStatusRegister,
     pub enable: EnableRegister,
 }
@@ -41,12 +83,21 @@ impl DmaExt for DMA {
 
     fn split(self) -> Self::Parts {
         Parts {
-            ch0: Channel {
-                dma: DMA::new().as_channel(CHANNEL0_OFFSET),
-            },
-            ch1: Channel {
-                dma: DMA::new().as_channel(CHANNEL1_OFFSET),
-            },
+            ch0: Channel::new(0, CHANNEL0_OFFSET),
+            ch1: Channel::new(1, CHANNEL1_OFFSET),
+            ch2: Channel::new(2, CHANNEL2_OFFSET),
+            ch3: Channel::new(3, CHANNEL3_OFFSET),
+            ch4: Channel::new(4, CHANNEL4_OFFSET),
+            ch5: Channel::new(5, CHANNEL5_OFFSET),
+            ch6: Channel::new(6, CHANNEL6_OFFSET),
+            ch7: Channel::new(7, CHANNEL7_OFFSET),
+            ch8: Channel::new(8, CHANNEL8_OFFSET),
+            ch9: Channel::new(9, CHANNEL9_OFFSET),
+            ch10: Channel::new(10, CHANNEL10_OFFSET),
+            ch11: Dma4Channel::new(11, CHANNEL11_OFFSET),
+            ch12: Dma4Channel::new(12, CHANNEL12_OFFSET),
+            ch13: Dma4Channel::new(13, CHANNEL13_OFFSET),
+            ch14: Dma4Channel::new(14, CHANNEL14_OFFSET),
             int_status: IntStatusRegister::new(),
             enable: EnableRegister::new(),
         }
@@ -61,11 +112,100 @@ pub struct TransferResources<'dcb, 'src, 'dst, T> {
     pub dest_buffer: &'dst mut [T],
 }
 
+pub struct Dma4TransferResources<'dcb, 'src, 'dst, T> {
+    pub src_cached: bool,
+    pub dest_cached: bool,
+    pub dcb: &'dcb Dma4ControlBlock,
+    pub src_buffer: &'src [T],
+    pub dest_buffer: &'dst mut [T],
+}
+
+/// Clean and invalidate the cached source/destination buffers of a control
+/// block
+pub(crate) fn maintain_buffers(dcb: &ControlBlock, src_cached: bool, dest_cached: bool) {
+    let td_mode = dcb.info.td_mode();
+
+    if src_cached {
+        maintain_range(
+            (dcb.src & !bus_address_bits::ALIAS_4_L2_COHERENT) as usize,
+            &dcb.length,
+            td_mode,
+            dcb.stride.src_stride() as u16 as i16,
+        );
+    }
+
+    if dest_cached {
+        maintain_range(
+            (dcb.dest & !bus_address_bits::ALIAS_4_L2_COHERENT) as usize,
+            &dcb.length,
+            td_mode,
+            dcb.stride.dest_stride() as u16 as i16,
+        );
+    }
+}
+
+/// DMA4 flavor of `maintain_buffers`, the addresses are physical addresses
+pub(crate) fn maintain_dma4_buffers(dcb: &Dma4ControlBlock, src_cached: bool, dest_cached: bool) {
+    let td_mode = dcb.info.td_mode();
+
+    if src_cached {
+        maintain_range(
+            dcb.src() as usize,
+            &dcb.length,
+            td_mode,
+            dcb.src_info.stride() as u16 as i16,
+        );
+    }
+
+    if dest_cached {
+        maintain_range(
+            dcb.dest() as usize,
+            &dcb.length,
+            td_mode,
+            dcb.dest_info.stride() as u16 as i16,
+        );
+    }
+}
+
+fn maintain_range(addr: usize, length: &TxfrLenWord, td_mode: bool, stride: i16) {
+    if td_mode {
+        // YLENGTH + 1 rows of XLENGTH bytes, the signed stride is added to
+        // the address after each row
+        let row_len = length.xlen() as usize;
+        let rows = length.ylen() as usize + 1;
+        let row_step = row_len as isize + stride as isize;
+
+        for row in 0..rows {
+            let row_addr = (addr as isize + (row as isize * row_step)) as usize;
+            unsafe {
+                cache::clean_and_invalidate_data_cache_range(row_addr, row_len);
+            }
+        }
+    } else {
+        unsafe {
+            cache::clean_and_invalidate_data_cache_range(addr, length.0 as _);
+        }
+    }
+}
+
 pub struct Channel {
     dma: DMA,
+    index: usize,
 }
 
 impl Channel {
+    fn new(index: usize, offset: usize) -> Self {
+        Channel {
+            dma: DMA::new().as_channel(offset),
+            index,
+        }
+    }
+
+    /// Channel number, 0:10
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     pub fn is_lite(&self) -> bool {
         self.dma.debug.is_set(Debug::Lite::Read)
     }
@@ -78,18 +218,81 @@ impl Channel {
         self.dma.cs.is_set(ControlStatus::Active::Read)
     }
 
-    pub fn abort(&mut self) {
-        // TODO
-        // https://github.com/torvalds/linux/blob/master/drivers/dma/bcm2835-dma.c#L412
-        unimplemented!();
+    /// Stop the channel without losing its state, the transfer continues
+    /// from where it left off after `resume`
+    pub fn pause(&mut self) {
+        self.dma.cs.modify(ControlStatus::Active::Clear);
+    }
+
+    pub fn resume(&mut self) {
+        self.dma.cs.modify(ControlStatus::Active::Set);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.dma.cs.is_set(ControlStatus::Paused::Read)
+    }
+
+    /// Calls `callback` from `dma_irq_handler` when a control block with
+    /// `int_en` set completes, the interrupt must also be enabled in the
+    /// `gic`
+    pub fn listen(&mut self, callback: Callback) {
+        interrupt::set_callback(self.index, Some(callback));
+    }
+
+    pub fn unlisten(&mut self) {
+        interrupt::set_callback(self.index, None);
+    }
+
+    /// The interrupt ID to register `dma_irq_handler` for
+    pub fn interrupt(&self) -> u32 {
+        interrupt::interrupt(self.index)
+    }
+
+    fn clear_interrupt(&mut self) {
+        // Write 1 to clear
+        self.dma.cs.modify(ControlStatus::Int::Set);
+    }
+
+    /// Cancel the current transfer, follows the Linux bcm2835-dma driver:
+    /// pause, let the outstanding AXI writes drain and then reset the channel.
+    ///
+    /// The channel is reset even when the writes don't drain, a stuck
+    /// peripheral can fail to signal the write responses.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        // A zero control block address means the channel is idle,
+        // the Active bit isn't a reliable indicator
+        if self.dma.dcb_addr.read() == 0 {
+            return Ok(());
+        }
+
+        self.pause();
+
+        let mut timeout = ABORT_TIMEOUT;
+        while self
+            .dma
+            .cs
+            .is_set(ControlStatus::WaitingForOutstandingWrites::Read)
+            && timeout != 0
+        {
+            asm::nop();
+            timeout -= 1;
+        }
+
+        self.reset();
+
+        if timeout == 0 {
+            Err(Error::OutstandingWrites)
+        } else {
+            Ok(())
+        }
     }
 
     pub fn reset(&mut self) {
-        // TODO - abort first?
         self.dma.cs.modify(ControlStatus::Reset::Set);
         while self.dma.cs.is_set(ControlStatus::Reset::Read) == true {}
     }
 
+    /// NOTE: returns early if the channel is paused
     pub fn wait(&mut self) {
         unsafe { barrier::dsb(barrier::SY) };
 
@@ -100,28 +303,26 @@ impl Channel {
         compiler_fence(Ordering::SeqCst);
     }
 
-    pub fn start<'dcb, 'src, 'dst, T>(&mut self, res: &TransferResources<'dcb, 'src, 'dst, T>) {
-        assert_eq!(
-            res.dcb.as_paddr() & 0x1F,
-            0,
-            "Control block address must be 256 bit aligned"
-        );
-        assert_ne!(res.dcb.src, 0, "Source address is NULL");
-        assert_ne!(res.dcb.dest, 0, "Destination address is NULL");
+    /// Like `wait` but gives up after `spins` polls, the transfer is aborted
+    /// and `Error::Timeout` is returned
+    pub fn wait_timeout(&mut self, spins: usize) -> Result<(), Error> {
+        unsafe { barrier::dsb(barrier::SY) };
 
-        if self.is_lite() {
-            assert_eq!(
-                res.dcb.info.td_mode(),
-                false,
-                "LITE channel doesn't support 2D mode"
-            );
-            assert!(res.dcb.length.0 <= TRANSFER_LENGTH_MAX_LITE);
-        } else {
-            if !res.dcb.info.td_mode() {
-                assert!(res.dcb.length.0 <= TRANSFER_LENGTH_MAX);
+        for _ in 0..spins {
+            if !self.dma.cs.is_set(ControlStatus::Active::Read) {
+                compiler_fence(Ordering::SeqCst);
+                return Ok(());
             }
+            asm::nop();
         }
 
+        self.abort()?;
+        Err(Error::Timeout)
+    }
+
+    pub fn start<'dcb, 'src, 'dst, T>(&mut self, res: &TransferResources<'dcb, 'src, 'dst, T>) {
+        self.check_control_block(res.dcb);
+
         compiler_fence(Ordering::Release);
 
         unsafe {
@@ -131,48 +332,110 @@ impl Channel {
             );
         }
 
-        if res.src_cached {
-            // TODO - need to handle 2d mode
-            assert_eq!(
-                res.dcb.info.td_mode(),
-                false,
-                "2D mode caching not supported yet"
+        maintain_buffers(res.dcb, res.src_cached, res.dest_cached);
+
+        if !res.src_cached && !res.dest_cached {
+            unsafe { barrier::dsb(barrier::SY) };
+        }
+
+        self.launch(res.dcb);
+    }
+
+    /// Start a chain of control blocks, the buffers were already maintained
+    /// as the blocks were pushed
+    pub fn start_chain<'dcb, 'buf, T>(&mut self, chain: &Chain<'dcb, 'buf, T>) {
+        let dcbs = chain.control_blocks();
+        assert!(!dcbs.is_empty(), "Control block chain is empty");
+
+        for dcb in dcbs.iter() {
+            self.check_control_block(dcb);
+        }
+
+        compiler_fence(Ordering::Release);
+
+        unsafe {
+            cache::clean_and_invalidate_data_cache_range(
+                dcbs[0].as_paddr(),
+                mem::size_of_val(dcbs),
             );
-            unsafe {
-                cache::clean_and_invalidate_data_cache_range(
-                    (res.dcb.src & !bus_address_bits::ALIAS_4_L2_COHERENT) as usize,
-                    res.dcb.length.0 as _,
-                );
-            }
+            barrier::dsb(barrier::SY);
+        }
+
+        self.launch(&dcbs[0]);
+    }
+
+    /// Blocks until a chain started with `start_chain` is done,
+    /// `Error::ChannelError` if the channel flagged an error.
+    ///
+    /// Cached destinations are invalidated once the chain completes.
+    pub fn wait_chain<'dcb, 'buf, T>(&mut self, chain: &Chain<'dcb, 'buf, T>) -> Result<(), Error> {
+        self.wait();
+        unsafe { barrier::dsb(barrier::SY) };
+
+        chain.invalidate_destinations();
+
+        if self.errors() {
+            Err(Error::ChannelError)
+        } else {
+            Ok(())
         }
+    }
+
+    fn check_control_block(&self, dcb: &ControlBlock) {
+        assert_eq!(
+            dcb.as_paddr() & 0x1F,
+            0,
+            "Control block address must be 256 bit aligned"
+        );
+        assert_ne!(dcb.src, 0, "Source address is NULL");
+        assert_ne!(dcb.dest, 0, "Destination address is NULL");
 
-        if res.dest_cached {
-            // TODO - need to handle 2d mode
+        if self.is_lite() {
             assert_eq!(
-                res.dcb.info.td_mode(),
+                dcb.info.td_mode(),
                 false,
-                "2D mode caching not supported yet"
+                "LITE channel doesn't support 2D mode"
             );
-            unsafe {
-                cache::clean_and_invalidate_data_cache_range(
-                    (res.dcb.dest & !bus_address_bits::ALIAS_4_L2_COHERENT) as usize,
-                    res.dcb.length.0 as _,
-                );
+            assert!(dcb.length.0 <= TRANSFER_LENGTH_MAX_LITE);
+        } else {
+            if !dcb.info.td_mode() {
+                assert!(dcb.length.0 <= TRANSFER_LENGTH_MAX);
             }
         }
+    }
 
-        if !res.src_cached && !res.dest_cached {
-            unsafe { barrier::dsb(barrier::SY) };
-        }
-
+    fn launch(&mut self, dcb: &ControlBlock) {
         self.dma
             .dcb_addr
-            .write(res.dcb.as_paddr() as u32 | bus_address_bits::ALIAS_4_L2_COHERENT);
+            .write(dcb.as_paddr() as u32 | bus_address_bits::ALIAS_4_L2_COHERENT);
 
         self.dma.cs.modify(ControlStatus::Active::Set);
     }
 
     pub fn errors(&self) -> bool {
+        if self.error_flags() {
+            return true;
+        }
+
+        if self
+            .dma
+            .debug
+            .get_field(Debug::OutstandingWrites::Read)
+            .unwrap()
+            .val()
+            != 0
+        {
+            return true;
+        }
+
+        false
+    }
+
+    /// Error bits only, without the outstanding writes check.
+    ///
+    /// Without `wait_resp` the completion interrupt can fire while writes
+    /// are still outstanding.
+    pub(crate) fn error_flags(&self) -> bool {
         if self.dma.cs.is_set(ControlStatus::Error::Read) {
             return true;
         }
@@ -189,14 +452,193 @@ impl Channel {
             return true;
         }
 
-        if self
-            .dma
+        false
+    }
+}
+
+/// A DMA4 (40-bit) channel
+pub struct Dma4Channel {
+    dma: DMA4,
+    index: usize,
+}
+
+impl Dma4Channel {
+    fn new(index: usize, offset: usize) -> Self {
+        Dma4Channel {
+            dma: DMA4::new().as_channel(offset),
+            index,
+        }
+    }
+
+    /// Channel number, 11:14
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn id(&self) -> u8 {
+        self.dma
             .debug
-            .get_field(Debug::OutstandingWrites::Read)
+            .get_field(Dma4Debug::DmaId::Read)
             .unwrap()
-            .val()
-            != 0
+            .val() as _
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.dma.cs.is_set(Dma4ControlStatus::Active::Read)
+    }
+
+    /// Calls `callback` from `dma_irq_handler` when a control block with
+    /// `int_en` set completes or the channel hits an error, the interrupt
+    /// must also be enabled in the `gic`
+    pub fn listen(&mut self, callback: Callback) {
+        interrupt::set_callback(self.index, Some(callback));
+        self.dma.debug.modify(Dma4Debug::IntOnError::Set);
+    }
+
+    pub fn unlisten(&mut self) {
+        self.dma.debug.modify(Dma4Debug::IntOnError::Clear);
+        interrupt::set_callback(self.index, None);
+    }
+
+    /// The interrupt ID to register `dma_irq_handler` for
+    pub fn interrupt(&self) -> u32 {
+        interrupt::interrupt(self.index)
+    }
+
+    fn clear_interrupt(&mut self) {
+        // Write 1 to clear
+        self.dma.cs.modify(Dma4ControlStatus::Int::Set);
+    }
+
+    /// Stop the channel without losing its state, the transfer continues
+    /// from where it left off after `resume`
+    pub fn pause(&mut self) {
+        self.dma.cs.modify(Dma4ControlStatus::Active::Clear);
+    }
+
+    pub fn resume(&mut self) {
+        self.dma.cs.modify(Dma4ControlStatus::Active::Set);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.dma.cs.is_set(Dma4ControlStatus::ReadPaused::Read)
+            || self.dma.cs.is_set(Dma4ControlStatus::WritePaused::Read)
+    }
+
+    /// Cancel the current transfer, follows the Linux bcm2835-dma driver:
+    /// halt, let the outstanding AXI transactions drain and then reset the
+    /// channel.
+    ///
+    /// The channel is reset even when the transactions don't drain.
+    pub fn abort(&mut self) -> Result<(), Error> {
+        if self.dma.dcb_addr.read() == 0 {
+            return Ok(());
+        }
+
+        self.dma.cs.modify(Dma4ControlStatus::Halt::Set);
+
+        let mut timeout = ABORT_TIMEOUT;
+        while self
+            .dma
+            .cs
+            .is_set(Dma4ControlStatus::OutstandingTransactions::Read)
+            && timeout != 0
         {
+            asm::nop();
+            timeout -= 1;
+        }
+
+        self.pause();
+        self.reset();
+
+        if timeout == 0 {
+            Err(Error::OutstandingWrites)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.dma.debug.modify(Dma4Debug::Reset::Set);
+        while self.dma.cs.is_set(Dma4ControlStatus::Active::Read) {}
+    }
+
+    /// NOTE: returns early if the channel is paused
+    pub fn wait(&mut self) {
+        unsafe { barrier::dsb(barrier::SY) };
+
+        while self.dma.cs.is_set(Dma4ControlStatus::Active::Read) {
+            asm::nop();
+        }
+
+        compiler_fence(Ordering::SeqCst);
+    }
+
+    /// Like `wait` but gives up after `spins` polls, the transfer is aborted
+    /// and `Error::Timeout` is returned
+    pub fn wait_timeout(&mut self, spins: usize) -> Result<(), Error> {
+        unsafe { barrier::dsb(barrier::SY) };
+
+        for _ in 0..spins {
+            if !self.dma.cs.is_set(Dma4ControlStatus::Active::Read) {
+                compiler_fence(Ordering::SeqCst);
+                return Ok(());
+            }
+            asm::nop();
+        }
+
+        self.abort()?;
+        Err(Error::Timeout)
+    }
+
+    pub fn start<'dcb, 'src, 'dst, T>(&mut self, res: &Dma4TransferResources<'dcb, 'src, 'dst, T>) {
+        assert_eq!(
+            res.dcb.as_paddr() & 0x1F,
+            0,
+            "Control block address must be 256 bit aligned"
+        );
+        assert_ne!(res.dcb.src(), 0, "Source address is NULL");
+        assert_ne!(res.dcb.dest(), 0, "Destination address is NULL");
+        if !res.dcb.info.td_mode() {
+            assert!(res.dcb.length.0 <= TRANSFER_LENGTH_MAX);
+        }
+
+        compiler_fence(Ordering::Release);
+
+        unsafe {
+            cache::clean_and_invalidate_data_cache_range(
+                res.dcb.as_paddr(),
+                mem::size_of::<Dma4ControlBlock>(),
+            );
+        }
+
+        maintain_dma4_buffers(res.dcb, res.src_cached, res.dest_cached);
+
+        unsafe { barrier::dsb(barrier::SY) };
+
+        self.dma.dcb_addr.write((res.dcb.as_paddr() >> 5) as u32);
+
+        self.dma.cs.modify(Dma4ControlStatus::Active::Set);
+    }
+
+    pub fn errors(&self) -> bool {
+        if self.dma.cs.is_set(Dma4ControlStatus::Error::Read) {
+            return true;
+        }
+
+        if self.dma.debug.is_set(Dma4Debug::WriteError::Read) {
+            return true;
+        }
+
+        if self.dma.debug.is_set(Dma4Debug::FifoError::Read) {
+            return true;
+        }
+
+        if self.dma.debug.is_set(Dma4Debug::ReadError::Read) {
+            return true;
+        }
+
+        if self.dma.debug.is_set(Dma4Debug::ReadCbError::Read) {
             return true;
         }
 
@@ -0,0 +1,256 @@
+//! Owned DMA transfers
+//!
+//! A `Transfer` takes ownership of the channel, the control blocks and both
+//! buffers while the engine is running and only hands them back once the
+//! channel is idle, so a buffer can't be reused or dropped mid-transfer.
+//! The buffers must be `ReadBuffer`/`WriteBuffer`s, `'static` memory whose
+//! address doesn't change when the buffer is moved into the `Transfer`.
+//! The memory side of every control block is checked against them.
+//!
+//! ```rust,ignore
+//! static mut DCB_MEM: [dma::ControlBlock; 1] = [dma::ControlBlock::new()];
+//! static mut SRC: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+//! static mut DEST: [u32; 8] = [0; 8];
+//!
+//! let dcbs = unsafe { &mut DCB_MEM };
+//! let (src, dest) = unsafe { (&SRC[..], &mut DEST[..]) };
+//! dcbs[0].set_src(src.as_ptr() as u32);
+//! // ...
+//!
+//! let txfr = dma::Transfer::start(dma_chan, dcbs, src, true, dest, true);
+//! let (parts, res) = txfr.wait();
+//! res?;
+//! ```
+
+use crate::cache::{self, bus_address_bits};
+use crate::dma::{maintain_buffers, Channel, ControlBlock, Error};
+use core::mem;
+use core::sync::atomic::{compiler_fence, Ordering};
+use cortex_a::barrier;
+
+/// Memory the engine reads from
+///
+/// # Safety
+///
+/// `read_buffer` must return the same region for as long as `self` lives,
+/// regardless of `self` being moved, and the region must stay valid until
+/// then.
+pub unsafe trait ReadBuffer {
+    /// Address and length in bytes
+    fn read_buffer(&self) -> (usize, usize);
+}
+
+/// Memory the engine writes to
+///
+/// # Safety
+///
+/// Same as `ReadBuffer`, and nothing else may access the region while
+/// `self` lives.
+pub unsafe trait WriteBuffer {
+    /// Address and length in bytes
+    fn write_buffer(&mut self) -> (usize, usize);
+}
+
+unsafe impl<T> ReadBuffer for &'static [T] {
+    fn read_buffer(&self) -> (usize, usize) {
+        (self.as_ptr() as usize, mem::size_of_val(&**self))
+    }
+}
+
+unsafe impl<T> ReadBuffer for &'static mut [T] {
+    fn read_buffer(&self) -> (usize, usize) {
+        (self.as_ptr() as usize, mem::size_of_val(&**self))
+    }
+}
+
+unsafe impl<T> WriteBuffer for &'static mut [T] {
+    fn write_buffer(&mut self) -> (usize, usize) {
+        (self.as_mut_ptr() as usize, mem::size_of_val(&**self))
+    }
+}
+
+/// Resources handed back by a completed `Transfer`
+pub struct TransferParts<SRC, DEST> {
+    pub chan: Channel,
+    pub dcbs: &'static mut [ControlBlock],
+    pub src: SRC,
+    pub dest: DEST,
+}
+
+/// A transfer in progress
+pub struct Transfer<SRC, DEST> {
+    chan: Channel,
+    dcbs: &'static mut [ControlBlock],
+    src: SRC,
+    dest: DEST,
+    dest_cached: bool,
+}
+
+impl<SRC, DEST> Transfer<SRC, DEST>
+where
+    SRC: ReadBuffer,
+    DEST: WriteBuffer,
+{
+    /// Starts `dcbs[0]`, any further blocks must be linked to it via `next`.
+    ///
+    /// The memory read or written by every block must be within `src` and
+    /// `dest`, a side paced by a DREQ is a peripheral and isn't checked
+    /// (pass an empty slice for it).
+    ///
+    /// `src_cached`/`dest_cached` select the cache maintenance done for every
+    /// block, like the fields of `TransferResources`. Cached destinations are
+    /// also invalidated once the transfer completes.
+    pub fn start(
+        mut chan: Channel,
+        dcbs: &'static mut [ControlBlock],
+        src: SRC,
+        src_cached: bool,
+        mut dest: DEST,
+        dest_cached: bool,
+    ) -> Self {
+        assert!(!dcbs.is_empty(), "No control blocks");
+
+        let src_buf = src.read_buffer();
+        let dest_buf = dest.write_buffer();
+
+        for dcb in dcbs.iter() {
+            chan.check_control_block(dcb);
+            check_buffers(dcb, src_buf, dest_buf);
+        }
+
+        compiler_fence(Ordering::Release);
+
+        for dcb in dcbs.iter() {
+            maintain_buffers(dcb, src_cached, dest_cached);
+        }
+
+        unsafe {
+            cache::clean_and_invalidate_data_cache_range(
+                dcbs[0].as_paddr(),
+                mem::size_of_val(dcbs),
+            );
+            barrier::dsb(barrier::SY);
+        }
+
+        chan.launch(&dcbs[0]);
+
+        Transfer {
+            chan,
+            dcbs,
+            src,
+            dest,
+            dest_cached,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.chan.is_busy()
+    }
+
+    /// Returns the resources and the channel status if the transfer is done,
+    /// otherwise gives the transfer back
+    #[allow(clippy::type_complexity)]
+    pub fn poll(self) -> Result<(TransferParts<SRC, DEST>, Result<(), Error>), Self> {
+        if self.is_done() {
+            Ok(self.finish())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Blocks until the transfer is done, `Error::ChannelError` if the
+    /// channel flagged an error
+    pub fn wait(mut self) -> (TransferParts<SRC, DEST>, Result<(), Error>) {
+        self.chan.wait();
+        self.finish()
+    }
+
+    /// Cancels the transfer, the resources are returned either way
+    pub fn abort(mut self) -> (TransferParts<SRC, DEST>, Result<(), Error>) {
+        let res = self.chan.abort();
+        let (parts, status) = self.finish();
+        (parts, res.and(status))
+    }
+
+    fn finish(self) -> (TransferParts<SRC, DEST>, Result<(), Error>) {
+        unsafe { barrier::dsb(barrier::SY) };
+        compiler_fence(Ordering::Acquire);
+
+        let status = if self.chan.errors() {
+            Err(Error::ChannelError)
+        } else {
+            Ok(())
+        };
+
+        // Drop any lines speculatively loaded while the DMA was running
+        if self.dest_cached {
+            for dcb in self.dcbs.iter() {
+                maintain_buffers(dcb, false, true);
+            }
+        }
+
+        let parts = TransferParts {
+            chan: self.chan,
+            dcbs: self.dcbs,
+            src: self.src,
+            dest: self.dest,
+        };
+
+        (parts, status)
+    }
+}
+
+/// Asserts the memory read and written by `dcb` is within the `(address,
+/// length)` buffers, sides paced by a DREQ or ignored aren't checked
+pub(crate) fn check_buffers(dcb: &ControlBlock, src_buf: (usize, usize), dest_buf: (usize, usize)) {
+    if !dcb.info.src_dreq() && !dcb.info.src_ignore() {
+        let span = span(
+            dcb,
+            dcb.src,
+            dcb.info.src_inc(),
+            dcb.info.src_width(),
+            dcb.stride.src_stride() as u16 as i16,
+        );
+        assert!(contains(src_buf, span), "Source outside of the buffer");
+    }
+
+    if !dcb.info.dest_dreq() && !dcb.info.dest_ignore() {
+        let span = span(
+            dcb,
+            dcb.dest,
+            dcb.info.dest_inc(),
+            dcb.info.dest_width(),
+            dcb.stride.dest_stride() as u16 as i16,
+        );
+        assert!(
+            contains(dest_buf, span),
+            "Destination outside of the buffer"
+        );
+    }
+}
+
+/// Byte range `[start, end)` accessed by one side of a control block
+fn span(dcb: &ControlBlock, bus_addr: u32, inc: bool, wide: bool, stride: i16) -> (usize, usize) {
+    let addr = (bus_addr & !bus_address_bits::ALIAS_4_L2_COHERENT) as isize;
+
+    if !inc {
+        let width = if wide { 16 } else { 4 };
+        return (addr as usize, addr as usize + width);
+    }
+
+    if dcb.info.td_mode() {
+        // YLENGTH + 1 rows of XLENGTH bytes, the signed stride is added to
+        // the address after each row
+        let row_len = dcb.length.xlen() as isize;
+        let rows = dcb.length.ylen() as isize + 1;
+        let last = addr + (rows - 1) * (row_len + stride as isize);
+        (addr.min(last) as usize, (addr.max(last) + row_len) as usize)
+    } else {
+        (addr as usize, addr as usize + dcb.length.0 as usize)
+    }
+}
+
+fn contains(buf: (usize, usize), span: (usize, usize)) -> bool {
+    let (addr, len) = buf;
+    span.0 >= addr && span.1 <= addr + len
+}
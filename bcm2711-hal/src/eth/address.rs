@@ -5,6 +5,15 @@ pub struct EthernetAddress(pub [u8; 6]);
 
 impl EthernetAddress {
     pub const BROADCAST: EthernetAddress = EthernetAddress([0xff; 6]);
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// Group bit of the first octet is set, includes broadcast
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
 }
 
 impl From<[u8; 6]> for EthernetAddress {
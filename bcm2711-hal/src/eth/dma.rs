@@ -5,69 +5,134 @@ use bcm2711::genet::umac::*;
 use bcm2711::genet::{rx_desc, rx_dma, rx_ring, tx_desc, tx_dma, tx_ring};
 use bcm2711::genet::{
     DEFAULT_Q, DMA_DESC_WORDS, DMA_FC_THRESH_HI, DMA_FC_THRESH_LO, DMA_MAX_BURST_LENGTH,
-    NUM_DMA_DESC, QTAG_MASK, QTAG_SHIFT,
+    DMA_RING_BUF_PRIORITY_SHIFT, NUM_DMA_DESC, NUM_DMA_RINGS, Q0_PRIORITY, QTAG_MASK, QTAG_SHIFT,
 };
 use core::convert::TryInto;
 
+/// Priority fields per Tx priority register
+const PRIORITIES_PER_REG: usize = 6;
+
+/// Software state of a descriptor ring
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RingState {
+    /// First descriptor of the ring
+    start: usize,
+    /// One past the last descriptor of the ring
+    end: usize,
+    /// Next descriptor to use
+    index: usize,
+    /// Consumer (Rx) or producer (Tx) index, wraps at 16 bits like the hw
+    sw_index: u16,
+}
+
+impl RingState {
+    pub(crate) const fn new() -> Self {
+        RingState {
+            start: 0,
+            end: 0,
+            index: 0,
+            sw_index: 0,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn advance(&mut self) {
+        self.index += 1;
+        if self.index >= self.end {
+            self.index = self.start;
+        }
+        self.sw_index = self.sw_index.wrapping_add(1);
+    }
+}
+
 impl<'rx, 'tx> Eth<'rx, 'tx> {
     pub(crate) fn dma_enable(&mut self) {
-        self.dev
-            .rdma
-            .ctrl
-            .modify(rx_dma::Ctrl::Enable::Set + rx_dma::Ctrl::DefDescEnable::Set);
-        self.dev
-            .tdma
-            .ctrl
-            .modify(tx_dma::Ctrl::Enable::Set + tx_dma::Ctrl::DefDescEnable::Set);
+        let rx_rings = (1 << self.queues.rx_queues) - 1;
+        let tx_rings = (1 << self.queues.tx_queues) - 1;
+        self.dev.rdma.ctrl.modify(
+            rx_dma::Ctrl::Enable::Set
+                + rx_dma::Ctrl::RingBufEnable::Field::new(rx_rings).unwrap()
+                + rx_dma::Ctrl::DefDescEnable::Set,
+        );
+        self.dev.tdma.ctrl.modify(
+            tx_dma::Ctrl::Enable::Set
+                + tx_dma::Ctrl::RingBufEnable::Field::new(tx_rings).unwrap()
+                + tx_dma::Ctrl::DefDescEnable::Set,
+        );
     }
 
     pub(crate) fn dma_disable<D: DelayUs<u32>>(&mut self, delay: &mut D) {
-        self.dev
-            .tdma
-            .ctrl
-            .modify(tx_dma::Ctrl::Enable::Clear + tx_dma::Ctrl::DefDescEnable::Clear);
-        self.dev
-            .rdma
-            .ctrl
-            .modify(rx_dma::Ctrl::Enable::Clear + rx_dma::Ctrl::DefDescEnable::Clear);
+        self.dev.tdma.ctrl.modify(
+            tx_dma::Ctrl::Enable::Clear
+                + tx_dma::Ctrl::RingBufEnable::Field::new(0).unwrap()
+                + tx_dma::Ctrl::DefDescEnable::Clear,
+        );
+        self.dev.rdma.ctrl.modify(
+            rx_dma::Ctrl::Enable::Clear
+                + rx_dma::Ctrl::RingBufEnable::Field::new(0).unwrap()
+                + rx_dma::Ctrl::DefDescEnable::Clear,
+        );
 
         self.dev.umac.tx_flush.modify(TxFlush::Flush::Set);
         delay.delay_us(10u32);
         self.dev.umac.tx_flush.modify(TxFlush::Flush::Clear);
     }
 
-    pub(crate) fn rx_ring_init(&mut self) {
+    pub(crate) fn rx_rings_init(&mut self) {
         self.dev.rdma.burst_size.write(DMA_MAX_BURST_LENGTH as _);
 
+        let queues = self.queues.rx_queues;
+        let bds = self.queues.rx_bds_per_queue;
+        for ring in 0..queues {
+            self.rx_ring_init(ring, ring * bds, (ring + 1) * bds);
+        }
+        self.rx_ring_init(DEFAULT_Q, queues * bds, NUM_DMA_DESC);
+
+        self.dev
+            .rdma
+            .ring_cfg
+            .write(((1 << queues) - 1) | (1 << DEFAULT_Q));
+    }
+
+    fn rx_ring_init(&mut self, ring: usize, start: usize, end: usize) {
+        let start_addr: u32 = (start * DMA_DESC_WORDS).try_into().unwrap();
+        let regs = &mut self.dev.rdma.rings[ring];
+
         // Set start and end address, read and write pointers
-        self.dev.rdma.rings[DEFAULT_Q].start_addr.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].start_addr_hi.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].read_ptr.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].read_ptr_hi.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].write_ptr.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].write_ptr_hi.write(0);
-        self.dev.rdma.rings[DEFAULT_Q]
-            .end_addr
-            .write(((NUM_DMA_DESC * DMA_DESC_WORDS) - 1).try_into().unwrap());
-        self.dev.rdma.rings[DEFAULT_Q].end_addr_hi.write(0);
-
-        self.dev.rdma.rings[DEFAULT_Q].prod_index.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].cons_index.write(0);
-        self.dev.rdma.rings[DEFAULT_Q].buf_size.modify(
-            rx_ring::BufSize::Size::Field::new(NUM_DMA_DESC as _).unwrap()
+        regs.start_addr.write(start_addr);
+        regs.start_addr_hi.write(0);
+        regs.read_ptr.write(start_addr);
+        regs.read_ptr_hi.write(0);
+        regs.write_ptr.write(start_addr);
+        regs.write_ptr_hi.write(0);
+        regs.end_addr
+            .write(((end * DMA_DESC_WORDS) - 1).try_into().unwrap());
+        regs.end_addr_hi.write(0);
+
+        regs.prod_index.write(0);
+        regs.cons_index.write(0);
+        regs.mbuf_done_thresh.write(1);
+        regs.buf_size.modify(
+            rx_ring::BufSize::Size::Field::new((end - start) as _).unwrap()
                 + rx_ring::BufSize::BufferSize::Field::new(RX_BUF_LENGTH as _).unwrap(),
         );
-        self.dev.rdma.rings[DEFAULT_Q].xon_xoff_thresh.modify(
+        regs.xon_xoff_thresh.modify(
             rx_ring::XonXoffThresh::XonThresh::Field::new(DMA_FC_THRESH_HI as _).unwrap()
                 + rx_ring::XonXoffThresh::XoffThresh::Field::new(DMA_FC_THRESH_LO as _).unwrap(),
         );
 
-        self.dev.rdma.ring_cfg.write(1 << DEFAULT_Q);
+        self.rx_rings[ring] = RingState {
+            start,
+            end,
+            index: start,
+            sw_index: 0,
+        };
     }
 
     pub(crate) fn rx_descs_init(&mut self) {
-        self.c_index = 0;
-
         for desc_index in 0..NUM_DMA_DESC {
             let address: u64 = self.rx_mem[desc_index].buffer.as_ptr() as u64;
             let addr_low = (address & (core::u32::MAX as u64)) as u32;
@@ -88,71 +153,117 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         }
     }
 
-    pub(crate) fn tx_ring_init(&mut self) {
+    pub(crate) fn tx_rings_init(&mut self) {
         self.dev.tdma.burst_size.write(DMA_MAX_BURST_LENGTH as _);
 
+        let queues = self.queues.tx_queues;
+        let bds = self.queues.tx_bds_per_queue;
+        for ring in 0..queues {
+            self.tx_ring_init(ring, ring * bds, (ring + 1) * bds);
+        }
+        self.tx_ring_init(DEFAULT_Q, queues * bds, NUM_DMA_DESC);
+
+        // Strict priority, ring 0 is the highest and the default ring the
+        // lowest
+        let mut priorities = [0_u32; 3];
+        let mut set_priority = |ring: usize, priority: usize| {
+            let shift = (ring % PRIORITIES_PER_REG) * DMA_RING_BUF_PRIORITY_SHIFT;
+            priorities[ring / PRIORITIES_PER_REG] |= (priority as u32) << shift;
+        };
+        for ring in 0..queues {
+            set_priority(ring, Q0_PRIORITY + ring);
+        }
+        set_priority(DEFAULT_Q, Q0_PRIORITY + queues);
+
+        self.dev.tdma.priority_0.write(priorities[0]);
+        self.dev.tdma.priority_1.write(priorities[1]);
+        self.dev.tdma.priority_2.write(priorities[2]);
+        self.dev.tdma.arb_ctrl.modify(tx_dma::ArbCtrl::Mode::Sp);
+
+        self.dev
+            .tdma
+            .ring_cfg
+            .write(((1 << queues) - 1) | (1 << DEFAULT_Q));
+    }
+
+    fn tx_ring_init(&mut self, ring: usize, start: usize, end: usize) {
+        let start_addr: u32 = (start * DMA_DESC_WORDS).try_into().unwrap();
+        let regs = &mut self.dev.tdma.rings[ring];
+
         // Set start and end address, read and write pointers
-        self.dev.tdma.rings[DEFAULT_Q].start_addr.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].start_addr_hi.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].read_ptr.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].read_ptr_hi.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].write_ptr.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].write_ptr_hi.write(0);
-        self.dev.tdma.rings[DEFAULT_Q]
-            .end_addr
-            .write(((NUM_DMA_DESC * DMA_DESC_WORDS) - 1).try_into().unwrap());
-        self.dev.tdma.rings[DEFAULT_Q].end_addr_hi.write(0);
-
-        self.dev.tdma.rings[DEFAULT_Q].prod_index.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].cons_index.write(0);
-        self.dev.tdma.rings[DEFAULT_Q].mbuf_done_thresh.write(1);
-
-        self.dev.tdma.rings[DEFAULT_Q].flow_period.write(0);
-
-        self.dev.tdma.rings[DEFAULT_Q].buf_size.modify(
-            tx_ring::BufSize::Size::Field::new(NUM_DMA_DESC as _).unwrap()
+        regs.start_addr.write(start_addr);
+        regs.start_addr_hi.write(0);
+        regs.read_ptr.write(start_addr);
+        regs.read_ptr_hi.write(0);
+        regs.write_ptr.write(start_addr);
+        regs.write_ptr_hi.write(0);
+        regs.end_addr
+            .write(((end * DMA_DESC_WORDS) - 1).try_into().unwrap());
+        regs.end_addr_hi.write(0);
+
+        regs.prod_index.write(0);
+        regs.cons_index.write(0);
+        regs.mbuf_done_thresh.write(1);
+
+        regs.flow_period.write(0);
+
+        regs.buf_size.modify(
+            tx_ring::BufSize::Size::Field::new((end - start) as _).unwrap()
                 + tx_ring::BufSize::BufferSize::Field::new(TX_BUF_LENGTH as _).unwrap(),
         );
 
-        self.dev.tdma.ring_cfg.write(1 << DEFAULT_Q);
+        self.tx_rings[ring] = RingState {
+            start,
+            end,
+            index: start,
+            sw_index: 0,
+        };
     }
 
-    pub(crate) fn dma_recv(&mut self) -> Result<RxPacket, Error> {
-        let p_index = self.dev.rdma.rings[DEFAULT_Q]
+    pub(crate) fn dma_recv(&mut self, ring: usize) -> Result<RxPacket, Error> {
+        if ring >= NUM_DMA_RINGS || self.rx_rings[ring].size() == 0 {
+            return Err(Error::InvalidQueue);
+        }
+
+        if let Some(drop_queue) = self.drop_queue {
+            self.rx_ring_discard(drop_queue);
+        }
+
+        let p_index = self.dev.rdma.rings[ring]
             .prod_index
             .get_field(rx_ring::ProdIndex::Index::Read)
             .unwrap()
             .val();
 
         // TODO add this back in, not in the u-boot impl
-        //let discards = self.dev.rdma.rings[DEFAULT_Q]
+        //let discards = self.dev.rdma.rings[ring]
         //    .prod_index
         //    .get_field(rx_ring::ProdIndex::DiscardCnt::Read)
         //    .unwrap()
         //    .val();
         //assert_eq!(discards, 0, "TODO");
 
-        if p_index as usize == self.c_index {
+        if p_index as u16 == self.rx_rings[ring].sw_index {
             Err(Error::WouldBlock)
         } else {
-            let dma_len = self.dev.rdma.descriptors[self.rx_index]
+            let rx_index = self.rx_rings[ring].index;
+
+            let dma_len = self.dev.rdma.descriptors[rx_index]
                 .len_status
                 .get_field(rx_desc::LenStatus::Len::Read)
                 .unwrap()
                 .val() as usize;
 
-            let packet_desc_error = self.dev.rdma.descriptors[self.rx_index]
-                .len_status
-                .matches_any(
-                    rx_desc::LenStatus::RxOverflow::Read
-                        + rx_desc::LenStatus::RxCrcErr::Read
-                        + rx_desc::LenStatus::RxErr::Read,
-                );
+            let packet_desc_error = self.dev.rdma.descriptors[rx_index].len_status.matches_any(
+                rx_desc::LenStatus::RxOverflow::Read
+                    + rx_desc::LenStatus::RxCrcErr::Read
+                    + rx_desc::LenStatus::RxErr::Read,
+            );
 
-            let eop = self.dev.rdma.descriptors[self.rx_index]
+            let eop = self.dev.rdma.descriptors[rx_index]
                 .len_status
                 .is_set(rx_desc::LenStatus::Eop::Read);
-            let sop = self.dev.rdma.descriptors[self.rx_index]
+            let sop = self.dev.rdma.descriptors[rx_index]
                 .len_status
                 .is_set(rx_desc::LenStatus::Sop::Read);
 
@@ -166,13 +277,13 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
                 } else {
                     unsafe {
                         cache::clean_and_invalidate_data_cache_range(
-                            self.rx_mem[self.rx_index].as_paddr(),
+                            self.rx_mem[rx_index].as_paddr(),
                             RX_BUF_LENGTH,
                         );
                     }
 
                     let pkt = RxPacket {
-                        entry: &mut self.rx_mem[self.rx_index],
+                        entry: &mut self.rx_mem[rx_index],
                         length: dma_len,
                     };
                     Ok(pkt)
@@ -181,37 +292,66 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
 
             // Always try to update the rings, even if an error was encountered
 
-            // Tell the MAC we have consumed that last receive buffer
-            self.c_index = (self.c_index + 1) & 0xFFFF;
-            self.dev.rdma.rings[DEFAULT_Q]
+            // Forward our descriptor pointer, wrapping around if needed, and
+            // tell the MAC we have consumed that last receive buffer
+            self.rx_rings[ring].advance();
+            self.dev.rdma.rings[ring]
                 .cons_index
-                .write(self.c_index as _);
-
-            // Forward our descriptor pointer, wrapping around if needed
-            self.rx_index = self.rx_index.saturating_add(1);
-            if self.rx_index >= NUM_DMA_DESC {
-                self.rx_index = 0;
-            }
+                .write(self.rx_rings[ring].sw_index as _);
 
             result
         }
     }
 
-    //pub(crate) fn dma_send(&mut self, pkt: &[u8]) -> Result<(), Error> {
+    /// Hand every received buffer of the ring back to the hw
+    fn rx_ring_discard(&mut self, ring: usize) {
+        let p_index = self.dev.rdma.rings[ring]
+            .prod_index
+            .get_field(rx_ring::ProdIndex::Index::Read)
+            .unwrap()
+            .val() as u16;
+
+        if p_index != self.rx_rings[ring].sw_index {
+            while self.rx_rings[ring].sw_index != p_index {
+                self.rx_rings[ring].advance();
+            }
+            self.dev.rdma.rings[ring].cons_index.write(p_index as _);
+        }
+    }
+
     pub(crate) fn dma_send<F: FnOnce(&mut [u8]) -> R, R>(
         &mut self,
+        ring: usize,
         length: usize,
         f: F,
     ) -> Result<R, Error> {
+        if ring >= NUM_DMA_RINGS || self.tx_rings[ring].size() == 0 {
+            return Err(Error::InvalidQueue);
+        }
+
         if length > MAX_MTU_SIZE {
             return Err(Error::Exhausted);
         }
 
-        let r = f(&mut self.tx_mem[self.tx_index].as_mut_slice()[..length]);
+        // Descriptors between the hw consumer and our producer index are
+        // still owned by the hw
+        let c_index = self.dev.tdma.rings[ring]
+            .cons_index
+            .get_field(tx_ring::ConsIndex::Index::Read)
+            .unwrap()
+            .val() as u16;
+        let in_flight = self.tx_rings[ring].sw_index.wrapping_sub(c_index) as usize;
+        if in_flight >= self.tx_rings[ring].size() {
+            return Err(Error::WouldBlock);
+        }
+
+        let tx_index = self.tx_rings[ring].index;
+
+        let r = f(&mut self.tx_mem[tx_index].as_mut_slice()[..length]);
 
         // Pad the frame if needed
         let length = if length < MIN_MTU_SIZE {
-            for b in self.tx_mem[self.tx_index].as_mut_slice()[length..MIN_MTU_SIZE].iter_mut() {
+            for b in self.tx_mem[tx_index].as_mut_slice()[length..MIN_MTU_SIZE].iter_mut() {
                 *b = 0;
             }
             MIN_MTU_SIZE
@@ -221,72 +361,41 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
 
         unsafe {
             cache::clean_and_invalidate_data_cache_range(
-                self.tx_mem[self.tx_index].as_paddr(),
+                self.tx_mem[tx_index].as_paddr(),
                 TX_BUF_LENGTH,
             );
         }
 
-        let address = self.tx_mem[self.tx_index].as_paddr() as u64;
+        let address = self.tx_mem[tx_index].as_paddr() as u64;
         let addr_low = (address & (core::u32::MAX as u64)) as u32;
         let addr_high = ((address >> 32) & (core::u32::MAX as u64)) as u32;
 
-        let p_index = self.dev.tdma.rings[DEFAULT_Q]
-            .prod_index
-            .get_field(tx_ring::ProdIndex::Index::Read)
-            .unwrap()
-            .val();
-
-        self.dev.tdma.descriptors[self.tx_index]
-            .addr_low
-            .write(addr_low);
+        self.dev.tdma.descriptors[tx_index].addr_low.write(addr_low);
 
         // Register writes to GISB bus can take couple hundred nanoseconds
         // and are done for each packet, save these expensive writes unless
         // the platform is explicitly configured for 64-bits/LPAE.
-        self.dev.tdma.descriptors[self.tx_index]
+        self.dev.tdma.descriptors[tx_index]
             .addr_high
             .write(addr_high);
 
         // TODO - QTAG mask and shift overlap with other fields?
-        self.dev.tdma.descriptors[self.tx_index]
+        self.dev.tdma.descriptors[tx_index]
             .len_status
             .write(QTAG_MASK << QTAG_SHIFT);
-        self.dev.tdma.descriptors[self.tx_index].len_status.modify(
+        self.dev.tdma.descriptors[tx_index].len_status.modify(
             tx_desc::LenStatus::Len::Field::new(length as _).unwrap()
                 + tx_desc::LenStatus::TxAppendCrc::Set
                 + tx_desc::LenStatus::Sop::Set
                 + tx_desc::LenStatus::Eop::Set,
         );
 
-        // Increment index and start transmission
-        self.tx_index = self.tx_index.saturating_add(1);
-        if self.tx_index >= NUM_DMA_DESC {
-            self.tx_index = 0;
-        }
-
-        // Start transmisson
-        let p_index = p_index + 1;
-        self.dev.tdma.rings[DEFAULT_Q]
+        // Increment index and start transmission, completion is reported
+        // by the consumer index (and the Tx done interrupt when listening)
+        self.tx_rings[ring].advance();
+        self.dev.tdma.rings[ring]
             .prod_index
-            .write(p_index as _);
-
-        let mut tries = 100;
-        loop {
-            let c_index = self.dev.tdma.rings[DEFAULT_Q]
-                .cons_index
-                .get_field(tx_ring::ConsIndex::Index::Read)
-                .unwrap()
-                .val();
-
-            if c_index >= p_index {
-                break;
-            }
-
-            tries -= 1;
-            if tries == 0 {
-                return Err(Error::TimedOut);
-            }
-        }
+            .write(self.tx_rings[ring].sw_index as _);
 
         Ok(r)
     }
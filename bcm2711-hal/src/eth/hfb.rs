@@ -0,0 +1,284 @@
+//! Hardware Filter Block (HFB)
+//!
+//! A filter compares a pattern against the start of each received frame and
+//! steers matching frames to an Rx priority ring, frames that don't match
+//! any filter go to the default ring.
+//! Patterns are matched with nibble granularity, offsets are from the start
+//! of the frame (destination address).
+//!
+//! The hardware can only steer frames, `Action::Drop` steers them to the
+//! drop ring (see `Eth::set_drop_queue`) which the driver discards.
+//!
+//! ```ignore
+//! let queues = eth::Queues {
+//!     rx_queues: 2,
+//!     rx_bds_per_queue: 32,
+//!     ..Default::default()
+//! };
+//! let mut eth = Eth::new_with_queues(devices, &mut delay, mac, rx_mem, tx_mem, queues)?;
+//!
+//! // RTP on UDP port 5004 goes to ring 0
+//! let mut rtp = eth::Filter::new();
+//! rtp.udp_dst_port(5004)?;
+//! eth.add_filter(&rtp, eth::Action::Queue(0))?;
+//! ```
+
+use crate::eth::{Error, Eth};
+use bcm2711::genet::hfb::{
+    FILTER_SIZE, NUM_FILTERS, WORD_EVEN_BYTE_SHIFT, WORD_EVEN_MASK_SHIFT, WORD_ODD_BYTE_SHIFT,
+    WORD_ODD_MASK_SHIFT,
+};
+use bcm2711::genet::hfb_regs::{self, FLT_ENABLE_FILTERS, FLT_LEN_FILTERS};
+use bcm2711::genet::DEFAULT_Q;
+
+/// Longest pattern in bytes, limited by the 8-bit length field
+pub const MAX_PATTERN_LEN: usize = 0xFE;
+
+/// Filters per `index2rings` register, 4 bits each
+const FILTERS_PER_INDEX2RING: usize = 8;
+
+const ETHER_TYPE_OFFSET: usize = 12;
+const IPV4_VERSION_IHL_OFFSET: usize = 14;
+const IPV4_PROTOCOL_OFFSET: usize = 23;
+const UDP_SRC_PORT_OFFSET: usize = 34;
+const UDP_DST_PORT_OFFSET: usize = 36;
+
+pub const ETHER_TYPE_IPV4: u16 = 0x0800;
+pub const ETHER_TYPE_ARP: u16 = 0x0806;
+pub const IP_PROTOCOL_ICMP: u8 = 1;
+pub const IP_PROTOCOL_TCP: u8 = 6;
+pub const IP_PROTOCOL_UDP: u8 = 17;
+
+/// IPv4 without options, the port matchers rely on a fixed header length
+const IPV4_VERSION_IHL: u8 = 0x45;
+
+/// What happens to a frame matching a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    /// Steer to the Rx priority ring
+    Queue(usize),
+    /// Steer to the drop ring
+    Drop,
+}
+
+/// A pattern to match received frames against
+///
+/// The matchers can be combined, a frame must match all of them.
+#[derive(Clone)]
+pub struct Filter {
+    /// HFB words, see `bcm2711::genet::hfb`
+    words: [u32; FILTER_SIZE],
+    /// Pattern length in bytes
+    len: usize,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::new()
+    }
+}
+
+impl Filter {
+    /// An empty filter, matches every frame
+    pub fn new() -> Self {
+        Filter {
+            words: [0; FILTER_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Pattern length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Match `value` at byte `offset` of the frame
+    ///
+    /// Each byte of `mask` selects the nibbles of the corresponding `value`
+    /// byte that are compared, a nibble is compared if any of its mask bits
+    /// are set.
+    pub fn match_bytes(
+        &mut self,
+        offset: usize,
+        value: &[u8],
+        mask: &[u8],
+    ) -> Result<&mut Self, Error> {
+        let end = offset + value.len();
+        if value.len() != mask.len() || end > MAX_PATTERN_LEN {
+            return Err(Error::InvalidFilter);
+        }
+
+        for (i, (v, m)) in value.iter().zip(mask.iter()).enumerate() {
+            let pos = offset + i;
+            let nibbles =
+                (if m & 0xF0 != 0 { 0b10 } else { 0 }) | (if m & 0x0F != 0 { 0b01 } else { 0 });
+            let (byte_shift, mask_shift) = if pos & 1 == 0 {
+                (WORD_EVEN_BYTE_SHIFT, WORD_EVEN_MASK_SHIFT)
+            } else {
+                (WORD_ODD_BYTE_SHIFT, WORD_ODD_MASK_SHIFT)
+            };
+
+            let word = &mut self.words[pos / 2];
+            *word &= !((0xFF << byte_shift) | (0b11 << mask_shift));
+            *word |= (u32::from(*v & *m) << byte_shift) | (nibbles << mask_shift);
+        }
+
+        self.len = self.len.max(end);
+
+        Ok(self)
+    }
+
+    pub fn ether_type(&mut self, ether_type: u16) -> Result<&mut Self, Error> {
+        self.match_bytes(ETHER_TYPE_OFFSET, &ether_type.to_be_bytes(), &[0xFF; 2])
+    }
+
+    /// IPv4 frames with the given protocol
+    pub fn ip_protocol(&mut self, protocol: u8) -> Result<&mut Self, Error> {
+        self.ether_type(ETHER_TYPE_IPV4)?
+            .match_bytes(IPV4_PROTOCOL_OFFSET, &[protocol], &[0xFF])
+    }
+
+    /// UDP over IPv4 (without options) with the given source port
+    pub fn udp_src_port(&mut self, port: u16) -> Result<&mut Self, Error> {
+        self.udp()?
+            .match_bytes(UDP_SRC_PORT_OFFSET, &port.to_be_bytes(), &[0xFF; 2])
+    }
+
+    /// UDP over IPv4 (without options) with the given destination port
+    pub fn udp_dst_port(&mut self, port: u16) -> Result<&mut Self, Error> {
+        self.udp()?
+            .match_bytes(UDP_DST_PORT_OFFSET, &port.to_be_bytes(), &[0xFF; 2])
+    }
+
+    fn udp(&mut self) -> Result<&mut Self, Error> {
+        self.ip_protocol(IP_PROTOCOL_UDP)?.match_bytes(
+            IPV4_VERSION_IHL_OFFSET,
+            &[IPV4_VERSION_IHL],
+            &[0xFF],
+        )
+    }
+}
+
+impl<'rx, 'tx> Eth<'rx, 'tx> {
+    /// Install a filter, returns its index
+    ///
+    /// `Action::Queue` must name one of the configured Rx priority rings.
+    pub fn add_filter(&mut self, filter: &Filter, action: Action) -> Result<usize, Error> {
+        let queue = match action {
+            Action::Queue(queue) => queue,
+            Action::Drop => self.drop_queue.ok_or(Error::InvalidQueue)?,
+        };
+        if queue >= self.queues.rx_queues {
+            return Err(Error::InvalidQueue);
+        }
+        if filter.is_empty() {
+            return Err(Error::InvalidFilter);
+        }
+
+        let index = (0..NUM_FILTERS)
+            .find(|&f| !self.hfb_is_enabled(f))
+            .ok_or(Error::Exhausted)?;
+
+        for (word, value) in self.dev.hfb.filters[index]
+            .iter_mut()
+            .zip(filter.words.iter())
+        {
+            word.write(*value);
+        }
+
+        self.hfb_set_len(index, filter.len);
+        self.hfb_set_queue(index, queue);
+        self.hfb_set_enabled(index, true);
+
+        self.dev.hfb_regs.ctrl.modify(hfb_regs::Ctrl::Enable::Set);
+
+        Ok(index)
+    }
+
+    pub fn remove_filter(&mut self, index: usize) {
+        assert!(index < NUM_FILTERS);
+
+        self.hfb_set_enabled(index, false);
+        self.hfb_clear(index);
+
+        if self.dev.hfb_regs.flt_enable.iter().all(|r| r.read() == 0) {
+            self.dev.hfb_regs.ctrl.modify(hfb_regs::Ctrl::Enable::Clear);
+        }
+    }
+
+    /// Select the Rx priority ring used by `Action::Drop`
+    ///
+    /// The ring is drained and its frames discarded whenever a ring is
+    /// received from. Filters already steering to the ring keep doing so.
+    pub fn set_drop_queue(&mut self, queue: Option<usize>) -> Result<(), Error> {
+        if let Some(queue) = queue {
+            if queue >= self.queues.rx_queues {
+                return Err(Error::InvalidQueue);
+            }
+        }
+        self.drop_queue = queue;
+        Ok(())
+    }
+
+    /// Disable and clear all of the filters
+    pub(crate) fn hfb_init(&mut self) {
+        self.dev.hfb_regs.ctrl.write(0);
+        for r in self.dev.hfb_regs.flt_enable.iter_mut() {
+            r.write(0);
+        }
+        for r in self.dev.rdma.index2rings.iter_mut() {
+            r.write(0);
+        }
+        for r in self.dev.hfb_regs.flt_lens.iter_mut() {
+            r.write(0);
+        }
+        for index in 0..NUM_FILTERS {
+            self.hfb_clear(index);
+        }
+    }
+
+    fn hfb_clear(&mut self, index: usize) {
+        for word in self.dev.hfb.filters[index].iter_mut() {
+            word.write(0);
+        }
+    }
+
+    fn hfb_is_enabled(&self, index: usize) -> bool {
+        let (reg, bit) = flt_enable_position(index);
+        self.dev.hfb_regs.flt_enable[reg].read() & bit != 0
+    }
+
+    fn hfb_set_enabled(&mut self, index: usize, enabled: bool) {
+        let (reg, bit) = flt_enable_position(index);
+        let val = self.dev.hfb_regs.flt_enable[reg].read();
+        let val = if enabled { val | bit } else { val & !bit };
+        self.dev.hfb_regs.flt_enable[reg].write(val);
+    }
+
+    fn hfb_set_len(&mut self, index: usize, len: usize) {
+        let reg = (NUM_FILTERS - 1 - index) / FLT_LEN_FILTERS;
+        let shift = 8 * (index % FLT_LEN_FILTERS);
+        let val = self.dev.hfb_regs.flt_lens[reg].read() & !(0xFF << shift);
+        // In bytes, covering whole words of the pattern
+        let len = ((len + 1) & !1) as u32;
+        self.dev.hfb_regs.flt_lens[reg].write(val | (len << shift));
+    }
+
+    fn hfb_set_queue(&mut self, index: usize, queue: usize) {
+        debug_assert!(queue < DEFAULT_Q);
+        let reg = index / FILTERS_PER_INDEX2RING;
+        let shift = 4 * (index % FILTERS_PER_INDEX2RING);
+        let val = self.dev.rdma.index2rings[reg].read() & !(0xF << shift);
+        self.dev.rdma.index2rings[reg].write(val | ((queue as u32) << shift));
+    }
+}
+
+/// Register and bit of a filter's enable
+fn flt_enable_position(index: usize) -> (usize, u32) {
+    let reg = if index < FLT_ENABLE_FILTERS { 1 } else { 0 };
+    (reg, 1 << (index % FLT_ENABLE_FILTERS))
+}
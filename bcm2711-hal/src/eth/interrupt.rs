@@ -0,0 +1,156 @@
+//! GENET Rx/Tx completion interrupts
+//!
+//! INTRL2_0 reports the default ring, INTRL2_1 reports the priority rings
+//! (Tx ring N is bit N, Rx ring N is bit 16 + N).
+//! The handlers latch the events and mask their source until the events are
+//! taken with `Eth::take_events`, so a busy ring doesn't keep re-entering the
+//! handler:
+//!
+//! ```ignore
+//! gic.register(irq::GENET_0, eth::intrl2_0_irq_handler)?;
+//! gic.register(irq::GENET_1, eth::intrl2_1_irq_handler)?;
+//! gic.enable(irq::GENET_0)?;
+//! gic.enable(irq::GENET_1)?;
+//! eth.listen();
+//!
+//! let events = eth.take_events();
+//! if events.rx_ready(eth::DEFAULT_Q) {
+//!     while let Ok(pkt) = eth.recv() {
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! Drain a ring once its event is taken, frames that arrive afterwards raise
+//! a new event.
+
+use crate::eth::Eth;
+use bcm2711::genet::intrl2::INTRL2;
+use bcm2711::genet::{DEFAULT_Q, INTRL2_0, INTRL2_1};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Priority ring sources of INTRL2_1, per direction
+const RING_MASK: u32 = 0xFFFF;
+
+/// Rx rings with received frames, bit N for ring N
+static RX_PENDING: AtomicU32 = AtomicU32::new(0);
+/// Tx rings that completed descriptors, bit N for ring N
+static TX_DONE: AtomicU32 = AtomicU32::new(0);
+
+/// Rings that raised an interrupt, bit N is set for ring N, bit `DEFAULT_Q`
+/// for the default ring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Events {
+    pub rx: u32,
+    pub tx: u32,
+}
+
+impl Events {
+    pub fn is_empty(&self) -> bool {
+        self.rx == 0 && self.tx == 0
+    }
+
+    /// Rx ring `queue` has received frames
+    pub fn rx_ready(&self, queue: usize) -> bool {
+        self.rx & (1 << queue) != 0
+    }
+
+    /// Tx ring `queue` has completed descriptors
+    pub fn tx_done(&self, queue: usize) -> bool {
+        self.tx & (1 << queue) != 0
+    }
+}
+
+impl<'rx, 'tx> Eth<'rx, 'tx> {
+    /// Unmask the Rx/Tx done interrupts of the enabled rings
+    pub fn listen(&mut self) {
+        self.listening = true;
+        let (rx, tx) = self.enabled_rings();
+        self.intr_unmask(rx, tx);
+    }
+
+    pub fn unlisten(&mut self) {
+        self.listening = false;
+        let (rx, tx) = self.enabled_rings();
+        let (l2_0, l2_1) = intr_sources(rx, tx);
+        self.dev.intrl2_0.mask_set.write(l2_0);
+        self.dev.intrl2_1.mask_set.write(l2_1);
+    }
+
+    /// Take the events latched by the IRQ handlers and unmask their sources
+    pub fn take_events(&mut self) -> Events {
+        let events = Events {
+            rx: RX_PENDING.swap(0, Ordering::AcqRel),
+            tx: TX_DONE.swap(0, Ordering::AcqRel),
+        };
+
+        if self.listening {
+            self.intr_unmask(events.rx, events.tx);
+        }
+
+        events
+    }
+
+    /// Mask and clear all of the interrupt sources
+    pub(crate) fn intr_disable(&mut self) {
+        self.dev.intrl2_0.mask_set.write(0xFFFF_FFFF);
+        self.dev.intrl2_0.clear.write(0xFFFF_FFFF);
+        self.dev.intrl2_1.mask_set.write(0xFFFF_FFFF);
+        self.dev.intrl2_1.clear.write(0xFFFF_FFFF);
+
+        RX_PENDING.store(0, Ordering::Release);
+        TX_DONE.store(0, Ordering::Release);
+    }
+
+    fn intr_unmask(&mut self, rx: u32, tx: u32) {
+        let (l2_0, l2_1) = intr_sources(rx, tx);
+        self.dev.intrl2_0.mask_clear.write(l2_0);
+        self.dev.intrl2_1.mask_clear.write(l2_1);
+    }
+
+    fn enabled_rings(&self) -> (u32, u32) {
+        let rx = ((1 << self.queues.rx_queues) - 1) | (1 << DEFAULT_Q);
+        let tx = ((1 << self.queues.tx_queues) - 1) | (1 << DEFAULT_Q);
+        (rx, tx)
+    }
+}
+
+/// INTRL2_0 and INTRL2_1 bits of the given rings
+fn intr_sources(rx: u32, tx: u32) -> (u32, u32) {
+    let mut l2_0 = INTRL2(0);
+    l2_0.set_rxdma_mbdone(rx & (1 << DEFAULT_Q) != 0);
+    l2_0.set_txdma_mbdone(tx & (1 << DEFAULT_Q) != 0);
+    let l2_1 = (tx & RING_MASK) | ((rx & RING_MASK) << 16);
+    (l2_0.0, l2_1)
+}
+
+/// Default ring Rx/Tx done, `irq::GENET_0`
+pub fn intrl2_0_irq_handler() {
+    let mut intrl2 = INTRL2_0::new();
+    let status = INTRL2(intrl2.status.read() & !intrl2.mask_status.read());
+
+    let mut handled = INTRL2(0);
+    if status.rxdma_mbdone() {
+        handled.set_rxdma_mbdone(true);
+        RX_PENDING.fetch_or(1 << DEFAULT_Q, Ordering::AcqRel);
+    }
+    if status.txdma_mbdone() {
+        handled.set_txdma_mbdone(true);
+        TX_DONE.fetch_or(1 << DEFAULT_Q, Ordering::AcqRel);
+    }
+
+    intrl2.mask_set.write(handled.0);
+    intrl2.clear.write(handled.0);
+}
+
+/// Priority ring Rx/Tx done, `irq::GENET_1`
+pub fn intrl2_1_irq_handler() {
+    let mut intrl2 = INTRL2_1::new();
+    let status = intrl2.status.read() & !intrl2.mask_status.read();
+
+    TX_DONE.fetch_or(status & RING_MASK, Ordering::AcqRel);
+    RX_PENDING.fetch_or(status >> 16, Ordering::AcqRel);
+
+    intrl2.mask_set.write(status);
+    intrl2.clear.write(status);
+}
@@ -0,0 +1,73 @@
+//! Link monitoring
+//!
+//! The BCM54213PE interrupt line isn't connected, so the link is polled.
+//! Call `Eth::poll_link` periodically (e.g. every 100 ms), the UMAC is
+//! reprogrammed for the speed, duplex and flow control the PHY negotiated
+//! whenever the link comes up or changes:
+//!
+//! ```ignore
+//! match eth.poll_link()? {
+//!     Some(eth::LinkEvent::Up(status)) => info!("Link up, {} Mbps", status.speed),
+//!     Some(eth::LinkEvent::Down) => info!("Link down"),
+//!     None => (),
+//! }
+//! ```
+
+use crate::eth::mdio::{MiiBmcr, Register};
+use crate::eth::{Error, Eth, PhyStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LinkEvent {
+    /// The link came up, or the negotiated parameters of the link changed
+    Up(PhyStatus),
+    Down,
+}
+
+impl<'rx, 'tx> Eth<'rx, 'tx> {
+    /// Read the PHY status and report a link change since the last poll
+    pub fn poll_link(&mut self) -> Result<Option<LinkEvent>, Error> {
+        let status = self.phy_read_status()?;
+        let link = if status.link_status {
+            Some(status)
+        } else {
+            None
+        };
+
+        if link == self.link {
+            return Ok(None);
+        }
+
+        self.link = link;
+
+        let event = match link {
+            Some(status) => {
+                self.mii_setup(&status);
+                LinkEvent::Up(status)
+            }
+            None => {
+                self.mii_link_down();
+                LinkEvent::Down
+            }
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Link status as of the last `poll_link`, `None` when the link is down
+    pub fn link(&self) -> Option<PhyStatus> {
+        self.link
+    }
+
+    pub fn is_link_up(&self) -> bool {
+        self.link.is_some()
+    }
+
+    /// Restart autonegotiation, the link goes down until it completes
+    pub fn restart_autoneg(&mut self) -> Result<(), Error> {
+        let mut bmcr: MiiBmcr = self.mdio_read(Register::MiiBmcr)?.into();
+        bmcr.set_autoneg_enable(true);
+        bmcr.set_restart_autoneg(true);
+        self.mdio_write(Register::MiiBmcr, bmcr.0);
+        Ok(())
+    }
+}
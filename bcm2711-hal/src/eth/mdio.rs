@@ -10,6 +10,7 @@ const PHY_ID: u8 = 0x01;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Register {
+    MiiBmcr = 0x00,
     MiiBmsr = 0x01,
     MiiAdvertise = 0x04,
     MiiLpa = 0x05,
@@ -17,6 +18,21 @@ pub enum Register {
     MiiStat1000 = 0x0A,
 }
 
+bitfield! {
+    #[repr(C)]
+    pub struct MiiBmcr(u16);
+    impl Debug;
+    u16;
+    pub restart_autoneg, set_restart_autoneg : 9;
+    pub autoneg_enable, set_autoneg_enable : 12;
+}
+
+impl From<u16> for MiiBmcr {
+    fn from(val: u16) -> MiiBmcr {
+        MiiBmcr(val)
+    }
+}
+
 bitfield! {
     #[repr(C)]
     pub struct MiiBmsr(u16);
@@ -41,6 +57,7 @@ bitfield! {
     pub lpa_100_half, set_lpa_100_half : 7;
     pub lpa_100_full, set_lpa_100_full : 8;
     pub pause_cap, set_pause_cap : 10;
+    pub pause_asym, set_pause_asym : 11;
 }
 
 impl From<u16> for MiiLpa {
@@ -94,6 +111,19 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         }
     }
 
+    pub(crate) fn mdio_write(&mut self, reg: Register, data: u16) {
+        self.dev.mdio.cmd.modify(
+            Cmd::Rw::RwWrite
+                + Cmd::PhyId::Field::new(PHY_ID as _).unwrap()
+                + Cmd::Reg::Field::new(reg as _).unwrap()
+                + Cmd::Data::Field::new(data as _).unwrap(),
+        );
+
+        self.dev.mdio.cmd.modify(Cmd::StartBusy::Set);
+
+        self.mdio_wait();
+    }
+
     fn mdio_wait(&self) {
         while self
             .dev
@@ -25,12 +25,19 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
 
         self.dev.umac.cmd.modify(
             Cmd::Speed::Field::new(speed).unwrap()
-                + Cmd::RxPauseIgnore::Field::new(!status.pause as _).unwrap()
-                + Cmd::TxPauseIgnore::Field::new(!status.pause as _).unwrap()
+                + Cmd::RxPauseIgnore::Field::new(!status.rx_pause as _).unwrap()
+                + Cmd::TxPauseIgnore::Field::new(!status.tx_pause as _).unwrap()
                 + Cmd::HdEn::Field::new(!status.full_duplex as _).unwrap(),
         );
     }
 
+    pub(crate) fn mii_link_down(&mut self) {
+        self.dev
+            .ext
+            .rgmii_oob_ctrl
+            .modify(RgmiiOobCtrl::RgmiiLink::Clear);
+    }
+
     pub(crate) fn mii_config(&mut self) {
         // RGMII_NO_ID: TXC transitions at the same time as TXD
         // (requires PCB or receiver-side delay)
@@ -2,18 +2,38 @@
 //!
 //! This implementation was based on:
 //! https://github.com/u-boot/u-boot/blob/master/drivers/net/bcmgenet.c
+//!
+//! The descriptors can be split between the default ring (`DEFAULT_Q`) and up
+//! to 16 priority rings, see [`Queues`](struct.Queues.html).
+//! Rx/Tx completion can be interrupt driven, see the `interrupt` module.
+//! Received frames can be steered between the Rx rings by the hardware
+//! filters, see the `hfb` module.
+//! Link changes after `Eth::new` are picked up by `Eth::poll_link`.
 
+use crate::eth::dma::RingState;
+use crate::eth::umac::RxMode;
 use crate::hal::blocking::delay::DelayUs;
 use bcm2711::genet::*;
 
 pub use crate::eth::address::EthernetAddress;
 pub use crate::eth::descriptor::Descriptor;
+pub use crate::eth::hfb::{
+    Action, Filter, ETHER_TYPE_ARP, ETHER_TYPE_IPV4, IP_PROTOCOL_ICMP, IP_PROTOCOL_TCP,
+    IP_PROTOCOL_UDP, MAX_PATTERN_LEN,
+};
+pub use crate::eth::interrupt::{intrl2_0_irq_handler, intrl2_1_irq_handler, Events};
+pub use crate::eth::link::LinkEvent;
 pub use crate::eth::phy::Status as PhyStatus;
 pub use crate::eth::rx::RxPacket;
+pub use crate::eth::umac::MAX_MULTICAST_ADDRS;
+pub use bcm2711::genet::DEFAULT_Q;
 
 mod address;
 mod descriptor;
 mod dma;
+mod hfb;
+mod interrupt;
+mod link;
 mod mdio;
 mod mii;
 mod netif;
@@ -78,12 +98,45 @@ pub enum Error {
     Exhausted,
     Dropped,
     TimedOut,
+    InvalidQueue,
+    InvalidFilter,
+}
+
+/// Descriptor split between the priority rings and the default ring
+///
+/// Priority ring N (`0..rx_queues`) uses descriptors
+/// `N * rx_bds_per_queue..(N + 1) * rx_bds_per_queue`, the default ring gets
+/// the remaining descriptors. The same applies to Tx.
+/// Tx rings are arbitrated by strict priority, ring 0 first and the default
+/// ring last.
+///
+/// The default uses only the default ring, with all of the descriptors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Queues {
+    pub rx_queues: usize,
+    pub rx_bds_per_queue: usize,
+    pub tx_queues: usize,
+    pub tx_bds_per_queue: usize,
+}
+
+impl Queues {
+    fn is_valid(&self) -> bool {
+        let valid = |queues: usize, bds: usize| {
+            queues <= DEFAULT_Q && (queues == 0 || bds != 0) && queues * bds < NUM_DMA_DESC
+        };
+        valid(self.rx_queues, self.rx_bds_per_queue) && valid(self.tx_queues, self.tx_bds_per_queue)
+    }
 }
 
 pub struct Eth<'rx, 'tx> {
-    c_index: usize,
-    rx_index: usize,
-    tx_index: usize,
+    queues: Queues,
+    rx_rings: [RingState; NUM_DMA_RINGS],
+    tx_rings: [RingState; NUM_DMA_RINGS],
+    listening: bool,
+    drop_queue: Option<usize>,
+    mac_address: EthernetAddress,
+    rx_mode: RxMode,
+    link: Option<PhyStatus>,
     dev: Devices,
     rx_mem: &'rx mut [Descriptor],
     tx_mem: &'tx mut [Descriptor],
@@ -96,6 +149,24 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         mac_address: EthernetAddress,
         rx_mem: &'rx mut [Descriptor],
         tx_mem: &'tx mut [Descriptor],
+    ) -> Result<Self, Error> {
+        Eth::new_with_queues(
+            devices,
+            delay,
+            mac_address,
+            rx_mem,
+            tx_mem,
+            Queues::default(),
+        )
+    }
+
+    pub fn new_with_queues<D: DelayUs<u32>>(
+        devices: Devices,
+        delay: &mut D,
+        mac_address: EthernetAddress,
+        rx_mem: &'rx mut [Descriptor],
+        tx_mem: &'tx mut [Descriptor],
+        queues: Queues,
     ) -> Result<Self, Error> {
         assert_eq!(rx_mem.len(), NUM_DMA_DESC);
         assert_eq!(tx_mem.len(), NUM_DMA_DESC);
@@ -128,15 +199,27 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
             return Err(Error::HwVersionNotSupported);
         }
 
+        if !queues.is_valid() {
+            return Err(Error::InvalidQueue);
+        }
+
         let mut eth = Eth {
-            c_index: 0,
-            rx_index: 0,
-            tx_index: 0,
+            queues,
+            rx_rings: [RingState::new(); NUM_DMA_RINGS],
+            tx_rings: [RingState::new(); NUM_DMA_RINGS],
+            listening: false,
+            drop_queue: None,
+            mac_address,
+            rx_mode: RxMode::new(),
+            link: None,
             dev: devices,
             rx_mem,
             tx_mem,
         };
 
+        // Interrupts stay masked until `listen`
+        eth.intr_disable();
+
         eth.mii_config();
         eth.umac_reset(delay);
         eth.mdio_reset();
@@ -146,14 +229,15 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         eth.umac_init(delay);
 
         eth.umac_set_hw_addr(&mac_address);
-        eth.umac_set_rx_mode(&mac_address);
+        eth.umac_set_rx_mode();
 
         // Disable RX/TX DMA and flush TX queues
         eth.dma_disable(delay);
 
-        eth.rx_ring_init();
+        eth.rx_rings_init();
         eth.rx_descs_init();
-        eth.tx_ring_init();
+        eth.tx_rings_init();
+        eth.hfb_init();
 
         // Enable RX/TX DMA
         eth.dma_enable();
@@ -162,6 +246,9 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
 
         // Update MAC registers based on PHY property
         eth.mii_setup(&status);
+        if status.link_status {
+            eth.link = Some(status);
+        }
 
         // Enable Rx/Tx
         eth.netif_start();
@@ -173,11 +260,35 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         self.phy_read_status()
     }
 
+    pub fn queues(&self) -> Queues {
+        self.queues
+    }
+
+    /// Receive from the default ring
     pub fn recv(&mut self) -> Result<RxPacket, Error> {
-        self.dma_recv()
+        self.dma_recv(DEFAULT_Q)
+    }
+
+    /// Receive from Rx ring `queue`, either a priority ring or `DEFAULT_Q`
+    pub fn recv_from(&mut self, queue: usize) -> Result<RxPacket, Error> {
+        self.dma_recv(queue)
     }
 
+    /// Queue a frame on the default ring
+    ///
+    /// Returns `Error::WouldBlock` when all of the ring's descriptors are
+    /// still owned by the hardware.
     pub fn send<F: FnOnce(&mut [u8]) -> R, R>(&mut self, length: usize, f: F) -> Result<R, Error> {
-        self.dma_send(length, f)
+        self.dma_send(DEFAULT_Q, length, f)
+    }
+
+    /// Queue a frame on Tx ring `queue`, either a priority ring or `DEFAULT_Q`
+    pub fn send_to<F: FnOnce(&mut [u8]) -> R, R>(
+        &mut self,
+        queue: usize,
+        length: usize,
+        f: F,
+    ) -> Result<R, Error> {
+        self.dma_send(queue, length, f)
     }
 }
@@ -1,11 +1,17 @@
 use crate::eth::mdio::{MiiBmsr, MiiLpa, MiiStat1000, Register};
 use crate::eth::{Error, Eth};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Status {
     pub link_status: bool,
     pub speed: u16,
     pub full_duplex: bool,
+    /// Pause frames are used in both directions
     pub pause: bool,
+    /// Pause frames received from the link partner are honored
+    pub rx_pause: bool,
+    /// Pause frames are sent to the link partner
+    pub tx_pause: bool,
 }
 
 impl<'rx, 'tx> Eth<'rx, 'tx> {
@@ -17,7 +23,8 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
 
         let mut speed = 0;
         let mut full_duplex = false;
-        let mut pause = false;
+        let mut rx_pause = false;
+        let mut tx_pause = false;
 
         if bmsr.link_status() {
             // Read autonegotiation status
@@ -50,8 +57,18 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
                 full_duplex = common_adv.lpa_10_full();
             }
 
+            // Resolve flow control (802.3 annex 28B), only applies to full
+            // duplex links
             if full_duplex {
-                pause = MiiLpa::from(lpa).pause_cap();
+                let lpa = MiiLpa::from(lpa);
+                let adv = MiiLpa::from(adv);
+                if adv.pause_cap() && lpa.pause_cap() {
+                    rx_pause = true;
+                    tx_pause = true;
+                } else if adv.pause_asym() && lpa.pause_asym() {
+                    rx_pause = adv.pause_cap();
+                    tx_pause = lpa.pause_cap();
+                }
             }
         }
 
@@ -59,7 +76,9 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
             link_status,
             speed,
             full_duplex,
-            pause,
+            pause: rx_pause && tx_pause,
+            rx_pause,
+            tx_pause,
         })
     }
 }
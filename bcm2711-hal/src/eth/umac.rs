@@ -1,9 +1,31 @@
-use crate::eth::{Eth, EthernetAddress, MAX_MTU_SIZE};
+use crate::eth::{Error, Eth, EthernetAddress, MAX_MTU_SIZE};
 use crate::hal::blocking::delay::DelayUs;
 use bcm2711::genet::rbuf::*;
 use bcm2711::genet::sys::*;
 use bcm2711::genet::umac::*;
 
+/// Multicast addresses the MDF filters can hold, next to the broadcast and
+/// own address
+pub const MAX_MULTICAST_ADDRS: usize = MAX_MDF_FILTERS - 2;
+
+/// Destination addresses accepted by the UMAC
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RxMode {
+    pub(crate) promiscuous: bool,
+    pub(crate) broadcast: bool,
+    pub(crate) multicast: [Option<EthernetAddress>; MAX_MULTICAST_ADDRS],
+}
+
+impl RxMode {
+    pub(crate) const fn new() -> Self {
+        RxMode {
+            promiscuous: false,
+            broadcast: true,
+            multicast: [None; MAX_MULTICAST_ADDRS],
+        }
+    }
+}
+
 impl<'rx, 'tx> Eth<'rx, 'tx> {
     pub(crate) fn umac_reset<D: DelayUs<u32>>(&mut self, delay: &mut D) {
         // 7358a0/7552a0: bad default in RBUF_FLUSH_CTRL.umac_sw_rst
@@ -66,20 +88,40 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
         );
     }
 
-    pub(crate) fn umac_set_rx_mode(&mut self, addr: &EthernetAddress) {
+    pub(crate) fn umac_set_rx_mode(&mut self) {
+        if self.rx_mode.promiscuous {
+            self.dev.umac.cmd.modify(Cmd::Promisc::Set);
+            self.dev.umac.mdf_ctrl.write(0);
+            return;
+        }
+
         // Promiscuous mode off
         self.dev.umac.cmd.modify(Cmd::Promisc::Clear);
 
         // update MDF filter
+        let mut index = 0;
 
         // Broadcast
-        let broadcast = EthernetAddress::BROADCAST;
-        let index = 0;
-        self.set_mdf_addr(index, &broadcast);
+        if self.rx_mode.broadcast {
+            self.set_mdf_addr(index, &EthernetAddress::BROADCAST);
+            index += 1;
+        }
 
         // Own address
-        let index = 1;
-        self.set_mdf_addr(index, addr);
+        let addr = self.mac_address;
+        self.set_mdf_addr(index, &addr);
+        index += 1;
+
+        // Multicast
+        let multicast = self.rx_mode.multicast;
+        for addr in multicast.iter().flatten() {
+            self.set_mdf_addr(index, addr);
+            index += 1;
+        }
+
+        // Enable the filters in use, filter N is bit (MAX_MDF_FILTERS - N)
+        let mask = (0..index).fold(0, |mask, i| mask | (1 << (MAX_MDF_FILTERS - i)));
+        self.dev.umac.mdf_ctrl.write(mask);
     }
 
     fn set_mdf_addr(&mut self, index: usize, addr: &EthernetAddress) {
@@ -93,11 +135,71 @@ impl<'rx, 'tx> Eth<'rx, 'tx> {
                 + MdfAddr1::Addr4::Field::new(addr.0[4] as _).unwrap()
                 + MdfAddr1::Addr5::Field::new(addr.0[5] as _).unwrap(),
         );
+    }
 
-        let reg = self.dev.umac.mdf_ctrl.read();
-        self.dev
-            .umac
-            .mdf_ctrl
-            .write(reg | (1 << (MAX_MDF_FILTERS - index)));
+    pub fn mac_address(&self) -> EthernetAddress {
+        self.mac_address
+    }
+
+    /// Accept frames sent to the multicast address `addr`
+    ///
+    /// Up to `MAX_MULTICAST_ADDRS` addresses can be added, enable
+    /// promiscuous mode to receive more groups.
+    pub fn add_multicast(&mut self, addr: EthernetAddress) -> Result<(), Error> {
+        if !addr.is_multicast() || addr.is_broadcast() {
+            return Err(Error::InvalidFilter);
+        }
+
+        let multicast = &mut self.rx_mode.multicast;
+        if !multicast.contains(&Some(addr)) {
+            let slot = multicast
+                .iter_mut()
+                .find(|a| a.is_none())
+                .ok_or(Error::Exhausted)?;
+            *slot = Some(addr);
+            self.umac_set_rx_mode();
+        }
+
+        Ok(())
+    }
+
+    /// Stop accepting frames sent to the multicast address `addr`
+    pub fn remove_multicast(&mut self, addr: EthernetAddress) {
+        if let Some(slot) = self
+            .rx_mode
+            .multicast
+            .iter_mut()
+            .find(|a| **a == Some(addr))
+        {
+            *slot = None;
+            self.umac_set_rx_mode();
+        }
+    }
+
+    /// Multicast addresses currently accepted
+    pub fn multicast_addrs(&self) -> impl Iterator<Item = &EthernetAddress> {
+        self.rx_mode.multicast.iter().flatten()
+    }
+
+    /// Accept all frames, regardless of the destination address
+    pub fn set_promiscuous(&mut self, enabled: bool) {
+        self.rx_mode.promiscuous = enabled;
+        self.umac_set_rx_mode();
+    }
+
+    pub fn is_promiscuous(&self) -> bool {
+        self.rx_mode.promiscuous
+    }
+
+    /// Accept broadcast frames, on by default
+    ///
+    /// Has no effect while in promiscuous mode.
+    pub fn set_broadcast(&mut self, enabled: bool) {
+        self.rx_mode.broadcast = enabled;
+        self.umac_set_rx_mode();
+    }
+
+    pub fn is_broadcast_enabled(&self) -> bool {
+        self.rx_mode.broadcast
     }
 }
@@ -0,0 +1,298 @@
+//! GIC-400 interrupt controller and IRQ handler registry
+//!
+//! Handlers are plain functions keyed by the BCM2711 interrupt IDs in
+//! `bcm2711::gic::irq`. They're called from `dispatch`, which should be
+//! installed as the IRQ exception handler:
+//!
+//! ```ignore
+//! raspi3_boot::irq_handler!(hal::gic::dispatch);
+//! ```
+//!
+//! - Only targets core 0
+//! - All interrupts share the same priority, there's no preemption
+//! - The loader (armstub) puts all interrupts in the non-secure group
+
+use bcm2711::gic::gicc::{self, GICC};
+use bcm2711::gic::gicd::{self, GICD};
+use bcm2711::gic::{NUM_INTERRUPTS, SPECIAL_ID_START};
+use core::sync::atomic::{compiler_fence, Ordering};
+use cortex_a::barrier;
+
+/// IRQ handler, called with IRQs masked
+pub type Handler = fn();
+
+/// First shared peripheral interrupt, IDs below are banked per core
+const SPI_START: u32 = 32;
+
+/// Priority given to all interrupts, lower values are higher priority
+const DEFAULT_PRIORITY: u8 = 0xA0;
+
+/// Lowest priority mask, lets everything through
+const PRIORITY_MASK: u32 = 0xF0;
+
+/// Route to core 0
+const CPU0_TARGET: u8 = 0x01;
+
+static mut HANDLERS: [Option<Handler>; NUM_INTERRUPTS] = [None; NUM_INTERRUPTS];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// The interrupt ID isn't implemented
+    InvalidInterrupt,
+    /// A handler is already registered for the interrupt
+    AlreadyRegistered,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Trigger {
+    Level,
+    Edge,
+}
+
+/// GIC-400 abstraction
+pub struct Gic {
+    gicd: GICD,
+    gicc: GICC,
+    num_interrupts: u32,
+}
+
+impl Gic {
+    /// Initializes the distributor and core 0's CPU interface.
+    ///
+    /// All interrupts start out disabled, level-sensitive and targeted
+    /// at core 0.
+    pub fn new(mut gicd: GICD, mut gicc: GICC) -> Self {
+        gicd.control
+            .modify(gicd::Control::EnableGrp0::Clear + gicd::Control::EnableGrp1::Clear);
+
+        let lines = gicd
+            .typ
+            .get_field(gicd::Type::ItLinesNumber::Read)
+            .unwrap()
+            .val();
+        let num_interrupts = ((lines + 1) * 32).min(NUM_INTERRUPTS as u32);
+
+        for n in 0..(num_interrupts / 32) as usize {
+            gicd.clr_enable[n].write(0xFFFF_FFFF);
+            gicd.clr_pending[n].write(0xFFFF_FFFF);
+            gicd.clr_active[n].write(0xFFFF_FFFF);
+        }
+
+        let prio = u32::from_ne_bytes([DEFAULT_PRIORITY; 4]);
+        for n in 0..(num_interrupts / 4) as usize {
+            gicd.priority[n].write(prio);
+        }
+
+        // The SGI/PPI target registers are read-only
+        let targets = u32::from_ne_bytes([CPU0_TARGET; 4]);
+        for n in (SPI_START / 4) as usize..(num_interrupts / 4) as usize {
+            gicd.targets[n].write(targets);
+        }
+
+        // Level-sensitive, SGI config is read-only
+        for n in (SPI_START / 16) as usize..(num_interrupts / 16) as usize {
+            gicd.config[n].write(0);
+        }
+
+        gicd.control
+            .modify(gicd::Control::EnableGrp0::Set + gicd::Control::EnableGrp1::Set);
+
+        gicc.priority_mask
+            .modify(gicc::PriorityMask::Priority::Field::new(PRIORITY_MASK).unwrap());
+        gicc.binary_point
+            .modify(gicc::BinaryPoint::BinaryPoint::Field::new(0).unwrap());
+        gicc.control
+            .modify(gicc::Control::EnableGrp0::Set + gicc::Control::EnableGrp1::Set);
+
+        Gic {
+            gicd,
+            gicc,
+            num_interrupts,
+        }
+    }
+
+    pub fn free(mut self) -> (GICD, GICC) {
+        self.gicc
+            .control
+            .modify(gicc::Control::EnableGrp0::Clear + gicc::Control::EnableGrp1::Clear);
+        self.gicd
+            .control
+            .modify(gicd::Control::EnableGrp0::Clear + gicd::Control::EnableGrp1::Clear);
+        (self.gicd, self.gicc)
+    }
+
+    /// Number of interrupt IDs implemented, including SGIs and PPIs
+    pub fn num_interrupts(&self) -> u32 {
+        self.num_interrupts
+    }
+
+    /// Registers the handler for `irq`, the interrupt still has to be
+    /// enabled
+    pub fn register(&mut self, irq: u32, handler: Handler) -> Result<(), Error> {
+        self.check(irq)?;
+        free(|| unsafe {
+            if HANDLERS[irq as usize].is_some() {
+                Err(Error::AlreadyRegistered)
+            } else {
+                HANDLERS[irq as usize] = Some(handler);
+                Ok(())
+            }
+        })
+    }
+
+    /// Disables `irq` and removes its handler
+    pub fn unregister(&mut self, irq: u32) -> Result<(), Error> {
+        self.disable(irq)?;
+        free(|| unsafe {
+            HANDLERS[irq as usize] = None;
+        });
+        Ok(())
+    }
+
+    pub fn enable(&mut self, irq: u32) -> Result<(), Error> {
+        self.check(irq)?;
+        let (n, bit) = bit_index(irq);
+        self.gicd.set_enable[n].write(bit);
+        Ok(())
+    }
+
+    pub fn disable(&mut self, irq: u32) -> Result<(), Error> {
+        self.check(irq)?;
+        let (n, bit) = bit_index(irq);
+        self.gicd.clr_enable[n].write(bit);
+        unsafe { barrier::dsb(barrier::SY) };
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, irq: u32) -> Result<bool, Error> {
+        self.check(irq)?;
+        let (n, bit) = bit_index(irq);
+        Ok(self.gicd.set_enable[n].read() & bit != 0)
+    }
+
+    pub fn is_pending(&self, irq: u32) -> Result<bool, Error> {
+        self.check(irq)?;
+        let (n, bit) = bit_index(irq);
+        Ok(self.gicd.set_pending[n].read() & bit != 0)
+    }
+
+    pub fn clear_pending(&mut self, irq: u32) -> Result<(), Error> {
+        self.check(irq)?;
+        let (n, bit) = bit_index(irq);
+        self.gicd.clr_pending[n].write(bit);
+        Ok(())
+    }
+
+    /// Lower values are higher priority, only the upper 4 bits are
+    /// implemented
+    pub fn set_priority(&mut self, irq: u32, priority: u8) -> Result<(), Error> {
+        self.check(irq)?;
+        let n = (irq / 4) as usize;
+        let shift = (irq % 4) * 8;
+        let val = self.gicd.priority[n].read() & !(0xFF << shift);
+        self.gicd.priority[n].write(val | (u32::from(priority) << shift));
+        Ok(())
+    }
+
+    /// SGIs are always edge-triggered and the PPIs are fixed by the
+    /// implementation
+    pub fn set_trigger(&mut self, irq: u32, trigger: Trigger) -> Result<(), Error> {
+        self.check(irq)?;
+        if irq < SPI_START {
+            return Err(Error::InvalidInterrupt);
+        }
+        let n = (irq / 16) as usize;
+        let bit = 1 << (((irq % 16) * 2) + 1);
+        let val = self.gicd.config[n].read();
+        let val = match trigger {
+            Trigger::Level => val & !bit,
+            Trigger::Edge => val | bit,
+        };
+        self.gicd.config[n].write(val);
+        Ok(())
+    }
+
+    fn check(&self, irq: u32) -> Result<(), Error> {
+        if irq < self.num_interrupts {
+            Ok(())
+        } else {
+            Err(Error::InvalidInterrupt)
+        }
+    }
+}
+
+fn bit_index(irq: u32) -> (usize, u32) {
+    ((irq / 32) as usize, 1 << (irq % 32))
+}
+
+/// Acknowledges and handles all pending interrupts.
+///
+/// Interrupts without a registered handler are disabled so they
+/// don't keep firing.
+pub fn dispatch() {
+    let mut gicc = GICC::new();
+
+    loop {
+        let iar = gicc.int_ack.read();
+        let irq = iar & 0x3FF;
+        if irq >= SPECIAL_ID_START {
+            // Spurious, nothing left to handle
+            break;
+        }
+
+        compiler_fence(Ordering::SeqCst);
+
+        match unsafe { HANDLERS.get(irq as usize).and_then(|h| *h) } {
+            Some(handler) => handler(),
+            None => {
+                let mut gicd = GICD::new();
+                let (n, bit) = bit_index(irq);
+                gicd.clr_enable[n].write(bit);
+            }
+        }
+
+        compiler_fence(Ordering::SeqCst);
+
+        gicc.eoi.write(iar);
+    }
+}
+
+/// Unmasks IRQs on the current core
+///
+/// # Safety
+///
+/// Handlers can preempt code that isn't protected by `free`.
+pub unsafe fn enable_irqs() {
+    compiler_fence(Ordering::SeqCst);
+    asm!("msr DAIFClr, #2" : : : "memory" : "volatile");
+}
+
+/// Masks IRQs on the current core
+pub fn disable_irqs() {
+    unsafe {
+        asm!("msr DAIFSet, #2" : : : "memory" : "volatile");
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Executes the closure with IRQs masked, restoring the previous mask
+/// afterwards
+pub fn free<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let daif: u64;
+    unsafe {
+        asm!("mrs $0, DAIF" : "=r"(daif) : : "memory" : "volatile");
+    }
+    disable_irqs();
+
+    let r = f();
+
+    compiler_fence(Ordering::SeqCst);
+    unsafe {
+        asm!("msr DAIF, $0" : : "r"(daif) : "memory" : "volatile");
+    }
+
+    r
+}
@@ -1,15 +1,55 @@
 //! General Purpose Input / Output
 //!
 //! TODO - update this once bcm2711 docs are available
-//! - add all the pins
 //!
 //! See the pinout:
 //! https://pinout.xyz/
-
+//!
+//! Pins are distinct types (`Pin0`..`Pin57`) with a type state mode.
+//! `downgrade()` erases the pin number into a `Pin<MODE>`, which can be
+//! kept in arrays and configured from a runtime table.
+//! Pins in the `Dynamic` mode have their function and pull resistor
+//! changed at runtime, reads and writes are checked against the current
+//! function:
+//!
+//! ```ignore
+//! let mut pin = gpio.p5.downgrade().into_dynamic();
+//! pin.set_function(Function::Output);
+//! pin.set_pull(Pull::None);
+//! pin.set_high()?;
+//! ```
+//!
+//! Input pins can detect edge and level events, which raise the pin's
+//! bank interrupt.
+//! An IRQ handler doesn't own the pins, it clears the events with
+//! `take_events`:
+//!
+//! ```ignore
+//! static BUTTON: AtomicBool = AtomicBool::new(false);
+//!
+//! fn gpio_irq_handler() {
+//!     if gpio::take_events() & (1 << 17) != 0 {
+//!         BUTTON.store(true, Ordering::Release);
+//!     }
+//! }
+//!
+//! let mut button = gpio.p17.into_pull_up_input();
+//! button.listen(Event::FallingEdge);
+//! gic.register(button.interrupt(), gpio_irq_handler)?;
+//! gic.enable(button.interrupt())?;
+//!
+//! loop {
+//!     asm::wfi();
+//!     if BUTTON.swap(false, Ordering::Acquire) {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use bcm2711::gic::irq;
 use bcm2711::gpio::*;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
-use cortex_a::asm;
 use hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin};
 use void::Void;
 
@@ -58,425 +98,568 @@ pub struct Output<MODE> {
 /// Push pull output (type state)
 pub struct PushPull;
 
-pub struct Parts {
-    /// Pins
-    pub p0: Pin0<Input<Floating>>,
-    pub p1: Pin1<Input<Floating>>,
-    pub p2: Pin2<Input<Floating>>,
-    pub p3: Pin3<Input<Floating>>,
-    pub p4: Pin4<Input<Floating>>,
-    pub p5: Pin5<Input<Floating>>,
-    pub p6: Pin6<Input<Floating>>,
-    pub p7: Pin7<Input<Floating>>,
-    pub p8: Pin8<Input<Floating>>,
-    pub p9: Pin9<Input<Floating>>,
-    pub p10: Pin10<Input<Floating>>,
-    pub p11: Pin11<Input<Floating>>,
-    pub p13: Pin13<Input<Floating>>,
-    pub p14: Pin14<Input<Floating>>,
-    pub p15: Pin15<Input<Floating>>,
-    pub p17: Pin17<Input<Floating>>,
-    pub p19: Pin19<Input<Floating>>,
-    pub p20: Pin20<Input<Floating>>,
-    pub p22: Pin22<Input<Floating>>,
-    pub p27: Pin27<Input<Floating>>,
+/// Function and pull configured at runtime (type state)
+pub struct Dynamic;
+
+/// Function select
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Function {
+    Input,
+    Output,
+    AF0,
+    AF1,
+    AF2,
+    AF3,
+    AF4,
+    AF5,
 }
 
-impl GpioExt for GPIO {
-    type Parts = Parts;
+impl Function {
+    fn bits(self) -> u32 {
+        match self {
+            Function::Input => 0b000,
+            Function::Output => 0b001,
+            Function::AF0 => 0b100,
+            Function::AF1 => 0b101,
+            Function::AF2 => 0b110,
+            Function::AF3 => 0b111,
+            Function::AF4 => 0b011,
+            Function::AF5 => 0b010,
+        }
+    }
 
-    fn split(self) -> Parts {
-        // Each pin gets a copy of the GPIO vaddr
-        Parts {
-            p0: Pin0 {
-                pin: 0,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p1: Pin1 {
-                pin: 1,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p2: Pin2 {
-                pin: 2,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p3: Pin3 {
-                pin: 3,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p4: Pin4 {
-                pin: 4,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p5: Pin5 {
-                pin: 5,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p6: Pin6 {
-                pin: 6,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p7: Pin7 {
-                pin: 7,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p8: Pin8 {
-                pin: 8,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p9: Pin9 {
-                pin: 9,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p10: Pin10 {
-                pin: 10,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p11: Pin11 {
-                pin: 11,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p13: Pin13 {
-                pin: 13,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p14: Pin14 {
-                pin: 14,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p15: Pin15 {
-                pin: 15,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p17: Pin17 {
-                pin: 17,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p19: Pin19 {
-                pin: 19,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p20: Pin20 {
-                pin: 20,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p22: Pin22 {
-                pin: 22,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
-            p27: Pin27 {
-                pin: 27,
-                gpio: GPIO::new(),
-                _mode: PhantomData,
-            },
+    fn from_bits(bits: u32) -> Self {
+        match bits & 0b111 {
+            0b000 => Function::Input,
+            0b001 => Function::Output,
+            0b100 => Function::AF0,
+            0b101 => Function::AF1,
+            0b110 => Function::AF2,
+            0b111 => Function::AF3,
+            0b011 => Function::AF4,
+            _ => Function::AF5,
         }
     }
 }
 
-/// GPIO pull-up/down clock sequence wait cycles
-const WAIT_CYCLES: usize = 150;
-
-macro_rules! gpio {
-    (
-        // struct field name, register field type name
-        $GPFSELfn:ident, $GPFSELn:ident,
-        $GPPUDCLKfn:ident, $GPPUDCLKx:ident,
-        $GPLEVfn:ident, $GPLEVx:ident,
-        $GPSETfn:ident, $GPSETx:ident,
-        $GPCLRfn:ident, $GPCLRx:ident,
-        [$($PXi:ident: ($pxi:ident, $FSELi:ident, $PUDCLKi:ident, $MODE:ty),)+]
-    ) => {
-$(
-pub struct $PXi<MODE> {
-    pin: u32,
-    gpio: GPIO,
-    _mode: PhantomData<MODE>,
+/// Pull-up/down resistor
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Pull {
+    None,
+    Up,
+    Down,
 }
 
-impl<MODE> Deref for $PXi<MODE> {
-    type Target = RegisterBlock;
-    fn deref(&self) -> &Self::Target {
-        &self.gpio
+impl Pull {
+    fn bits(self) -> u32 {
+        match self {
+            Pull::None => 0b00,
+            Pull::Up => 0b01,
+            Pull::Down => 0b10,
+        }
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        match bits & 0b11 {
+            0b01 => Pull::Up,
+            0b10 => Pull::Down,
+            _ => Pull::None,
+        }
     }
 }
 
-impl<MODE> DerefMut for $PXi<MODE> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.gpio
+/// Dynamic pin error
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// The pin's current function doesn't support the operation
+    InvalidFunction,
+}
+
+/// Input event detection
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Event {
+    /// Rising edge, the input is sampled by the system clock
+    RisingEdge,
+    /// Falling edge, the input is sampled by the system clock
+    FallingEdge,
+    /// High level, the event is raised again after clearing while
+    /// the pin stays high
+    HighLevel,
+    /// Low level, the event is raised again after clearing while
+    /// the pin stays low
+    LowLevel,
+    /// Rising edge without sampling, detects very short pulses
+    AsyncRisingEdge,
+    /// Falling edge without sampling, detects very short pulses
+    AsyncFallingEdge,
+}
+
+/// Reads and clears the event status of all pins, bit `n` is GPIO `n`
+///
+/// For IRQ handlers, which don't own the pins.
+pub fn take_events() -> u64 {
+    let mut gpio = GPIO::new();
+    let eds0 = gpio.eds0.read();
+    let eds1 = gpio.eds1.read();
+    gpio.eds0.write(eds0);
+    gpio.eds1.write(eds1);
+    u64::from(eds0) | (u64::from(eds1) << 32)
+}
+
+/// The bank interrupt for GPIO `pin`
+fn bank_interrupt(pin: u32) -> u32 {
+    match pin {
+        0..=27 => irq::GPIO_BANK0,
+        28..=45 => irq::GPIO_BANK1,
+        _ => irq::GPIO_BANK2,
     }
 }
 
-impl<MODE> $PXi<MODE> {
-    /// Configures the pin to operate in AF0 mode
-    pub fn into_alternate_af0(mut self) -> $PXi<Alternate<AF0>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF0);
-        self.pud.modify(PullUpDown::PuD::Off);
-
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
-
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
+/// Reads the function of `pin`
+fn function(gpio: &RegisterBlock, pin: u32) -> Function {
+    let val = match pin / 10 {
+        0 => gpio.fun_sel0.read(),
+        1 => gpio.fun_sel1.read(),
+        2 => gpio.fun_sel2.read(),
+        3 => gpio.fun_sel3.read(),
+        4 => gpio.fun_sel4.read(),
+        _ => gpio.fun_sel5.read(),
+    };
+    Function::from_bits(val >> ((pin % 10) * 3))
+}
+
+fn set_function(gpio: &mut RegisterBlock, pin: u32, function: Function) {
+    let shift = (pin % 10) * 3;
+    let mask = 0b111 << shift;
+    let bits = function.bits() << shift;
+    match pin / 10 {
+        0 => gpio.fun_sel0.write((gpio.fun_sel0.read() & !mask) | bits),
+        1 => gpio.fun_sel1.write((gpio.fun_sel1.read() & !mask) | bits),
+        2 => gpio.fun_sel2.write((gpio.fun_sel2.read() & !mask) | bits),
+        3 => gpio.fun_sel3.write((gpio.fun_sel3.read() & !mask) | bits),
+        4 => gpio.fun_sel4.write((gpio.fun_sel4.read() & !mask) | bits),
+        _ => gpio.fun_sel5.write((gpio.fun_sel5.read() & !mask) | bits),
     }
+}
 
-    /// Configures the pin to operate in AF1 mode
-    pub fn into_alternate_af1(mut self) -> $PXi<Alternate<AF1>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF1);
-        self.pud.modify(PullUpDown::PuD::Off);
+/// Reads the pull-up/down of `pin`
+fn pull(gpio: &RegisterBlock, pin: u32) -> Pull {
+    let val = match pin / 16 {
+        0 => gpio.pup_pdn0.read(),
+        1 => gpio.pup_pdn1.read(),
+        2 => gpio.pup_pdn2.read(),
+        _ => gpio.pup_pdn3.read(),
+    };
+    Pull::from_bits(val >> ((pin % 16) * 2))
+}
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+fn set_pull(gpio: &mut RegisterBlock, pin: u32, pull: Pull) {
+    let shift = (pin % 16) * 2;
+    let mask = 0b11 << shift;
+    let bits = pull.bits() << shift;
+    match pin / 16 {
+        0 => gpio.pup_pdn0.write((gpio.pup_pdn0.read() & !mask) | bits),
+        1 => gpio.pup_pdn1.write((gpio.pup_pdn1.read() & !mask) | bits),
+        2 => gpio.pup_pdn2.write((gpio.pup_pdn2.read() & !mask) | bits),
+        _ => gpio.pup_pdn3.write((gpio.pup_pdn3.read() & !mask) | bits),
+    }
+}
+
+fn is_low(gpio: &RegisterBlock, pin: u32) -> bool {
+    let level = if pin < 32 {
+        gpio.level0.read()
+    } else {
+        gpio.level1.read()
+    };
+    level & (1 << (pin % 32)) == 0
+}
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
+fn set_level(gpio: &mut RegisterBlock, pin: u32, high: bool) {
+    let bit = 1 << (pin % 32);
+    match (pin < 32, high) {
+        (true, true) => gpio.set0.write(bit),
+        (false, true) => gpio.set1.write(bit),
+        (true, false) => gpio.clr0.write(bit),
+        (false, false) => gpio.clr1.write(bit),
     }
+}
 
-    /// Configures the pin to operate in AF2 mode
-    pub fn into_alternate_af2(mut self) -> $PXi<Alternate<AF2>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF2);
-        self.pud.modify(PullUpDown::PuD::Off);
+fn set_event(gpio: &mut RegisterBlock, pin: u32, event: Event, enable: bool) {
+    let bit = 1 << (pin % 32);
+    let update = |val: u32| if enable { val | bit } else { val & !bit };
+    match (event, pin < 32) {
+        (Event::RisingEdge, true) => gpio.ren0.write(update(gpio.ren0.read())),
+        (Event::RisingEdge, false) => gpio.ren1.write(update(gpio.ren1.read())),
+        (Event::FallingEdge, true) => gpio.fen0.write(update(gpio.fen0.read())),
+        (Event::FallingEdge, false) => gpio.fen1.write(update(gpio.fen1.read())),
+        (Event::HighLevel, true) => gpio.hen0.write(update(gpio.hen0.read())),
+        (Event::HighLevel, false) => gpio.hen1.write(update(gpio.hen1.read())),
+        (Event::LowLevel, true) => gpio.len0.write(update(gpio.len0.read())),
+        (Event::LowLevel, false) => gpio.len1.write(update(gpio.len1.read())),
+        (Event::AsyncRisingEdge, true) => gpio.aren0.write(update(gpio.aren0.read())),
+        (Event::AsyncRisingEdge, false) => gpio.aren1.write(update(gpio.aren1.read())),
+        (Event::AsyncFallingEdge, true) => gpio.afen0.write(update(gpio.afen0.read())),
+        (Event::AsyncFallingEdge, false) => gpio.afen1.write(update(gpio.afen1.read())),
+    }
+}
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+fn is_event_pending(gpio: &RegisterBlock, pin: u32) -> bool {
+    let eds = if pin < 32 {
+        gpio.eds0.read()
+    } else {
+        gpio.eds1.read()
+    };
+    eds & (1 << (pin % 32)) != 0
+}
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
+fn clear_event(gpio: &mut RegisterBlock, pin: u32) {
+    let bit = 1 << (pin % 32);
+    if pin < 32 {
+        gpio.eds0.write(bit);
+    } else {
+        gpio.eds1.write(bit);
     }
+}
 
-    /// Configures the pin to operate in AF3 mode
-    pub fn into_alternate_af3(mut self) -> $PXi<Alternate<AF3>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF3);
-        self.pud.modify(PullUpDown::PuD::Off);
+/// Methods shared by the numbered pins and the type erased `Pin`
+macro_rules! pin_impl {
+    ($PXi:ident) => {
+        impl<MODE> Deref for $PXi<MODE> {
+            type Target = RegisterBlock;
+            fn deref(&self) -> &Self::Target {
+                &self.gpio
+            }
+        }
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+        impl<MODE> DerefMut for $PXi<MODE> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.gpio
+            }
+        }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+        impl<MODE> $PXi<MODE> {
+            /// The pin number
+            pub fn pin(&self) -> u32 {
+                self.pin
+            }
+
+            /// Configures the pin to operate in AF0 mode
+            pub fn into_alternate_af0(self) -> $PXi<Alternate<AF0>> {
+                self.into_mode(Function::AF0, Pull::None)
+            }
+
+            /// Configures the pin to operate in AF1 mode
+            pub fn into_alternate_af1(self) -> $PXi<Alternate<AF1>> {
+                self.into_mode(Function::AF1, Pull::None)
+            }
+
+            /// Configures the pin to operate in AF2 mode
+            pub fn into_alternate_af2(self) -> $PXi<Alternate<AF2>> {
+                self.into_mode(Function::AF2, Pull::None)
+            }
+
+            /// Configures the pin to operate in AF3 mode
+            pub fn into_alternate_af3(self) -> $PXi<Alternate<AF3>> {
+                self.into_mode(Function::AF3, Pull::None)
+            }
+
+            /// Configures the pin to operate in AF4 mode
+            pub fn into_alternate_af4(self) -> $PXi<Alternate<AF4>> {
+                self.into_mode(Function::AF4, Pull::None)
+            }
+
+            /// Configures the pin to operate in AF5 mode
+            pub fn into_alternate_af5(self) -> $PXi<Alternate<AF5>> {
+                self.into_mode(Function::AF5, Pull::None)
+            }
+
+            /// Configures the pin to operate as a floating input pin
+            pub fn into_floating_input(self) -> $PXi<Input<Floating>> {
+                self.into_mode(Function::Input, Pull::None)
+            }
+
+            /// Configures the pin to operate as a pulled down input pin
+            pub fn into_pull_down_input(self) -> $PXi<Input<PullDown>> {
+                self.into_mode(Function::Input, Pull::Down)
+            }
+
+            /// Configures the pin to operate as a pulled up input pin
+            pub fn into_pull_up_input(self) -> $PXi<Input<PullUp>> {
+                self.into_mode(Function::Input, Pull::Up)
+            }
+
+            /// Configures the pin to operate as an push pull output pin
+            pub fn into_push_pull_output(self) -> $PXi<Output<PushPull>> {
+                self.into_mode(Function::Output, Pull::None)
+            }
+
+            /// Keeps the current configuration, the function and pull can then
+            /// be changed at runtime
+            pub fn into_dynamic(self) -> $PXi<Dynamic> {
+                $PXi {
+                    pin: self.pin,
+                    gpio: self.gpio,
+                    _mode: PhantomData,
+                }
+            }
+
+            fn into_mode<NMODE>(mut self, function: Function, pull: Pull) -> $PXi<NMODE> {
+                set_function(&mut self.gpio, self.pin, function);
+                set_pull(&mut self.gpio, self.pin, pull);
+                $PXi {
+                    pin: self.pin,
+                    gpio: self.gpio,
+                    _mode: PhantomData,
+                }
+            }
+        }
 
-    /// Configures the pin to operate in AF4 mode
-    pub fn into_alternate_af4(mut self) -> $PXi<Alternate<AF4>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF4);
-        self.pud.modify(PullUpDown::PuD::Off);
+        impl<MODE> OutputPin for $PXi<Output<MODE>> {
+            type Error = Void;
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                set_level(&mut self.gpio, self.pin, true);
+                Ok(())
+            }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                set_level(&mut self.gpio, self.pin, false);
+                Ok(())
+            }
+        }
+
+        impl<MODE> StatefulOutputPin for $PXi<Output<MODE>> {
+            fn is_set_high(&self) -> Result<bool, Self::Error> {
+                self.is_set_low().map(|b| !b)
+            }
 
-    /// Configures the pin to operate in AF5 mode
-    pub fn into_alternate_af5(mut self) -> $PXi<Alternate<AF5>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::AF5);
-        self.pud.modify(PullUpDown::PuD::Off);
+            fn is_set_low(&self) -> Result<bool, Self::Error> {
+                Ok(is_low(&self.gpio, self.pin))
+            }
+        }
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+        impl<MODE> InputPin for $PXi<Input<MODE>> {
+            type Error = Void;
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+            fn is_high(&self) -> Result<bool, Self::Error> {
+                self.is_low().map(|b| !b)
+            }
 
-    /// Configures the pin to operate as a floating input pin
-    pub fn into_floating_input(mut self) -> $PXi<Input<Floating>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::Input);
-        self.pud.modify(PullUpDown::PuD::Off);
+            fn is_low(&self) -> Result<bool, Self::Error> {
+                Ok(is_low(&self.gpio, self.pin))
+            }
+        }
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+        impl<MODE> $PXi<Input<MODE>> {
+            /// Enables detection of `event`, several events can be enabled at once
+            pub fn listen(&mut self, event: Event) {
+                set_event(&mut self.gpio, self.pin, event, true);
+            }
+
+            /// Disables detection of `event`, a pending event stays set
+            pub fn unlisten(&mut self, event: Event) {
+                set_event(&mut self.gpio, self.pin, event, false);
+            }
+
+            /// One of the enabled events was detected
+            pub fn is_event_pending(&self) -> bool {
+                is_event_pending(&self.gpio, self.pin)
+            }
+
+            pub fn clear_event(&mut self) {
+                clear_event(&mut self.gpio, self.pin);
+            }
+
+            /// The bank interrupt raised by this pin's events, to be enabled
+            /// in the `gic`
+            pub fn interrupt(&self) -> u32 {
+                bank_interrupt(self.pin)
+            }
+        }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+        impl $PXi<Dynamic> {
+            pub fn function(&self) -> Function {
+                function(&self.gpio, self.pin)
+            }
 
-    /// Configures the pin to operate as a pulled down input pin
-    pub fn into_pull_down_input(mut self) -> $PXi<Input<PullDown>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::Input);
-        self.pud.modify(PullUpDown::PuD::PullDown);
+            pub fn set_function(&mut self, function: Function) {
+                set_function(&mut self.gpio, self.pin, function);
+            }
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+            pub fn pull(&self) -> Pull {
+                pull(&self.gpio, self.pin)
+            }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+            pub fn set_pull(&mut self, pull: Pull) {
+                set_pull(&mut self.gpio, self.pin, pull);
+            }
+        }
 
-    /// Configures the pin to operate as a pulled up input pin
-    pub fn into_pull_up_input(mut self) -> $PXi<Input<PullUp>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::Input);
-        self.pud.modify(PullUpDown::PuD::PullUp);
+        /// Only valid while the function is `Function::Output`
+        impl OutputPin for $PXi<Dynamic> {
+            type Error = Error;
+
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.check_function(Function::Output)?;
+                set_level(&mut self.gpio, self.pin, true);
+                Ok(())
+            }
+
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.check_function(Function::Output)?;
+                set_level(&mut self.gpio, self.pin, false);
+                Ok(())
+            }
+        }
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+        impl StatefulOutputPin for $PXi<Dynamic> {
+            fn is_set_high(&self) -> Result<bool, Self::Error> {
+                self.is_set_low().map(|b| !b)
+            }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+            fn is_set_low(&self) -> Result<bool, Self::Error> {
+                self.check_function(Function::Output)?;
+                Ok(is_low(&self.gpio, self.pin))
+            }
+        }
 
-    /// Configures the pin to operate as an push pull output pin
-    pub fn into_push_pull_output(mut self) -> $PXi<Output<PushPull>> {
-        // Select function
-        self.$GPFSELfn.modify($GPFSELn::$FSELi::Output);
-        self.pud.modify(PullUpDown::PuD::Off);
+        /// Only valid while the function is `Function::Input`
+        impl InputPin for $PXi<Dynamic> {
+            type Error = Error;
 
-        // Enable pin
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.modify($GPPUDCLKx::$PUDCLKi::Set);
-        for _ in 0..WAIT_CYCLES { asm::nop(); }
-        self.$GPPUDCLKfn.write(0);
+            fn is_high(&self) -> Result<bool, Self::Error> {
+                self.is_low().map(|b| !b)
+            }
 
-        $PXi { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
-    }
+            fn is_low(&self) -> Result<bool, Self::Error> {
+                self.check_function(Function::Input)?;
+                Ok(is_low(&self.gpio, self.pin))
+            }
+        }
+
+        impl $PXi<Dynamic> {
+            fn check_function(&self, function: Function) -> Result<(), Error> {
+                if self.function() == function {
+                    Ok(())
+                } else {
+                    Err(Error::InvalidFunction)
+                }
+            }
+        }
+    };
 }
 
-impl<MODE> OutputPin for $PXi<Output<MODE>> {
-    type Error = Void;
+/// Type erased pin, see `downgrade()`
+pub struct Pin<MODE> {
+    pin: u32,
+    gpio: GPIO,
+    _mode: PhantomData<MODE>,
+}
 
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        let pin = 1 << self.pin;
-        Ok(self.$GPSETfn.write(pin))
-    }
+pin_impl!(Pin);
 
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        let pin = 1 << self.pin;
-        Ok(self.$GPCLRfn.write(pin))
-    }
+macro_rules! gpio {
+    ([$($PXi:ident: ($pxi:ident, $i:expr, $MODE:ty),)+]) => {
+pub struct Parts {
+    /// Pins
+    $(
+    pub $pxi: $PXi<$MODE>,
+    )+
 }
 
-impl<MODE> StatefulOutputPin for $PXi<Output<MODE>> {
-    fn is_set_high(&self) -> Result<bool, Self::Error> {
-        self.is_set_low().map(|b| !b)
-    }
+impl GpioExt for GPIO {
+    type Parts = Parts;
 
-    fn is_set_low(&self) -> Result<bool, Self::Error> {
-        Ok(self.$GPLEVfn.read() & (1 << self.pin) == 0)
+    fn split(self) -> Parts {
+        // Each pin gets a copy of the GPIO vaddr
+        Parts {
+            $(
+            $pxi: $PXi {
+                pin: $i,
+                gpio: GPIO::new(),
+                _mode: PhantomData,
+            },
+            )+
+        }
     }
 }
 
-impl<MODE> InputPin for $PXi<Input<MODE>> {
-    type Error = Void;
+$(
+pub struct $PXi<MODE> {
+    pin: u32,
+    gpio: GPIO,
+    _mode: PhantomData<MODE>,
+}
 
-    fn is_high(&self) -> Result<bool, Self::Error> {
-        self.is_low().map(|b| !b)
-    }
+pin_impl!($PXi);
 
-    fn is_low(&self) -> Result<bool, Self::Error> {
-        Ok(self.$GPLEVfn.read() & (1 << self.pin) == 0)
+impl<MODE> $PXi<MODE> {
+    /// Erases the pin number from the type
+    pub fn downgrade(self) -> Pin<MODE> {
+        Pin { pin: self.pin, gpio: self.gpio, _mode: PhantomData }
     }
 }
 )+
     }
 }
 
-gpio!(
-    fun_sel0,
-    FunSel0,
-    pud_clk0,
-    PullUpDownClock0,
-    level0,
-    PinLevel0,
-    set0,
-    Set0,
-    clr0,
-    Clear0,
-    [
-        Pin0: (p0, Pin0, Pin0, Input<Floating>),
-        Pin1: (p1, Pin1, Pin1, Input<Floating>),
-        Pin2: (p2, Pin2, Pin2, Input<Floating>),
-        Pin3: (p3, Pin3, Pin3, Input<Floating>),
-        Pin4: (p4, Pin4, Pin4, Input<Floating>),
-        Pin5: (p5, Pin5, Pin5, Input<Floating>),
-        Pin6: (p6, Pin6, Pin6, Input<Floating>),
-        Pin7: (p7, Pin7, Pin7, Input<Floating>),
-        Pin8: (p8, Pin8, Pin8, Input<Floating>),
-        Pin9: (p9, Pin9, Pin9, Input<Floating>),
-    ]
-);
-
-gpio!(
-    fun_sel1,
-    FunSel1,
-    pud_clk0,
-    PullUpDownClock0,
-    level0,
-    PinLevel0,
-    set0,
-    Set0,
-    clr0,
-    Clear0,
-    [
-        Pin10: (p10, Pin10, Pin10, Input<Floating>),
-        Pin11: (p11, Pin11, Pin11, Input<Floating>),
-        Pin13: (p13, Pin13, Pin13, Input<Floating>),
-        Pin14: (p14, Pin14, Pin14, Input<Floating>),
-        Pin15: (p15, Pin15, Pin15, Input<Floating>),
-        Pin17: (p17, Pin17, Pin17, Input<Floating>),
-        Pin19: (p19, Pin19, Pin19, Input<Floating>),
-    ]
-);
-
-gpio!(
-    fun_sel2,
-    FunSel2,
-    pud_clk0,
-    PullUpDownClock0,
-    level0,
-    PinLevel0,
-    set0,
-    Set0,
-    clr0,
-    Clear0,
-    [
-        Pin20: (p20, Pin20, Pin20, Input<Floating>),
-        Pin22: (p22, Pin22, Pin22, Input<Floating>),
-        Pin27: (p27, Pin27, Pin27, Input<Floating>),
-    ]
-);
+gpio!([
+    Pin0: (p0, 0, Input<Floating>),
+    Pin1: (p1, 1, Input<Floating>),
+    Pin2: (p2, 2, Input<Floating>),
+    Pin3: (p3, 3, Input<Floating>),
+    Pin4: (p4, 4, Input<Floating>),
+    Pin5: (p5, 5, Input<Floating>),
+    Pin6: (p6, 6, Input<Floating>),
+    Pin7: (p7, 7, Input<Floating>),
+    Pin8: (p8, 8, Input<Floating>),
+    Pin9: (p9, 9, Input<Floating>),
+    Pin10: (p10, 10, Input<Floating>),
+    Pin11: (p11, 11, Input<Floating>),
+    Pin12: (p12, 12, Input<Floating>),
+    Pin13: (p13, 13, Input<Floating>),
+    Pin14: (p14, 14, Input<Floating>),
+    Pin15: (p15, 15, Input<Floating>),
+    Pin16: (p16, 16, Input<Floating>),
+    Pin17: (p17, 17, Input<Floating>),
+    Pin18: (p18, 18, Input<Floating>),
+    Pin19: (p19, 19, Input<Floating>),
+    Pin20: (p20, 20, Input<Floating>),
+    Pin21: (p21, 21, Input<Floating>),
+    Pin22: (p22, 22, Input<Floating>),
+    Pin23: (p23, 23, Input<Floating>),
+    Pin24: (p24, 24, Input<Floating>),
+    Pin25: (p25, 25, Input<Floating>),
+    Pin26: (p26, 26, Input<Floating>),
+    Pin27: (p27, 27, Input<Floating>),
+    Pin28: (p28, 28, Input<Floating>),
+    Pin29: (p29, 29, Input<Floating>),
+    Pin30: (p30, 30, Input<Floating>),
+    Pin31: (p31, 31, Input<Floating>),
+    Pin32: (p32, 32, Input<Floating>),
+    Pin33: (p33, 33, Input<Floating>),
+    Pin34: (p34, 34, Input<Floating>),
+    Pin35: (p35, 35, Input<Floating>),
+    Pin36: (p36, 36, Input<Floating>),
+    Pin37: (p37, 37, Input<Floating>),
+    Pin38: (p38, 38, Input<Floating>),
+    Pin39: (p39, 39, Input<Floating>),
+    Pin40: (p40, 40, Input<Floating>),
+    Pin41: (p41, 41, Input<Floating>),
+    Pin42: (p42, 42, Input<Floating>),
+    Pin43: (p43, 43, Input<Floating>),
+    Pin44: (p44, 44, Input<Floating>),
+    Pin45: (p45, 45, Input<Floating>),
+    Pin46: (p46, 46, Input<Floating>),
+    Pin47: (p47, 47, Input<Floating>),
+    Pin48: (p48, 48, Input<Floating>),
+    Pin49: (p49, 49, Input<Floating>),
+    Pin50: (p50, 50, Input<Floating>),
+    Pin51: (p51, 51, Input<Floating>),
+    Pin52: (p52, 52, Input<Floating>),
+    Pin53: (p53, 53, Input<Floating>),
+    Pin54: (p54, 54, Input<Floating>),
+    Pin55: (p55, 55, Input<Floating>),
+    Pin56: (p56, 56, Input<Floating>),
+    Pin57: (p57, 57, Input<Floating>),
+]);
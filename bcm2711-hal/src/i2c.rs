@@ -0,0 +1,367 @@
+//! I2C (Broadcom Serial Controller)
+//!
+//! TODO - update this once bcm2711 docs are available
+//!
+//! - BSC2 (and BSC7) are dedicated to the HDMI interfaces and not routed to
+//!   any GPIO pins, so there's no constructor for I2C2
+//! - Only supports 7-bit addressing
+
+use crate::clocks::Clocks;
+use crate::gpio::{
+    Alternate, Pin0, Pin1, Pin10, Pin11, Pin12, Pin13, Pin2, Pin22, Pin23, Pin28, Pin29, Pin3,
+    Pin4, Pin44, Pin45, Pin5, Pin6, Pin7, Pin8, Pin9, AF0, AF1, AF2, AF5,
+};
+use crate::hal::blocking::i2c::{Read, Write, WriteRead};
+use crate::time::Hertz;
+use bcm2711::i2c0::*;
+use bcm2711::i2c1::I2C1;
+use bcm2711::i2c3::I2C3;
+use bcm2711::i2c4::I2C4;
+use bcm2711::i2c5::I2C5;
+use bcm2711::i2c6::I2C6;
+use core::ops::DerefMut;
+
+/// Depth of the Tx and Rx FIFOs
+const FIFO_SIZE: usize = 16;
+
+/// Clock stretch timeout, in milliseconds
+const CLOCK_STRETCH_TIMEOUT_MS: u32 = 35;
+
+/// I2C error
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// The slave didn't acknowledge the address or a data byte
+    Nack,
+    /// The slave held SCL low for longer than the timeout
+    ClockStretchTimeout,
+    /// Transfers are limited to 65535 bytes
+    Length,
+}
+
+pub trait Pins<I2C> {}
+pub trait PinScl<I2C> {}
+pub trait PinSda<I2C> {}
+
+impl<I2C, SCL, SDA> Pins<I2C> for (SCL, SDA)
+where
+    SCL: PinScl<I2C>,
+    SDA: PinSda<I2C>,
+{
+}
+
+macro_rules! pins {
+    ($($I2CX:ty: SCL: [$($SCL:ty),*] SDA: [$($SDA:ty),*])+) => {
+        $(
+            $(
+                impl PinScl<$I2CX> for $SCL {}
+            )*
+            $(
+                impl PinSda<$I2CX> for $SDA {}
+            )*
+        )+
+    }
+}
+
+pins! {
+    I2C0:
+        SCL: [
+            Pin1<Alternate<AF0>>,
+            Pin29<Alternate<AF0>>,
+            Pin45<Alternate<AF1>>
+        ]
+        SDA: [
+            Pin0<Alternate<AF0>>,
+            Pin28<Alternate<AF0>>,
+            Pin44<Alternate<AF1>>
+        ]
+    I2C1:
+        SCL: [
+            Pin3<Alternate<AF0>>,
+            Pin45<Alternate<AF2>>
+        ]
+        SDA: [
+            Pin2<Alternate<AF0>>,
+            Pin44<Alternate<AF2>>
+        ]
+    I2C3:
+        SCL: [
+            Pin3<Alternate<AF5>>,
+            Pin5<Alternate<AF5>>
+        ]
+        SDA: [
+            Pin2<Alternate<AF5>>,
+            Pin4<Alternate<AF5>>
+        ]
+    I2C4:
+        SCL: [
+            Pin7<Alternate<AF5>>,
+            Pin9<Alternate<AF5>>
+        ]
+        SDA: [
+            Pin6<Alternate<AF5>>,
+            Pin8<Alternate<AF5>>
+        ]
+    I2C5:
+        SCL: [
+            Pin11<Alternate<AF5>>,
+            Pin13<Alternate<AF5>>
+        ]
+        SDA: [
+            Pin10<Alternate<AF5>>,
+            Pin12<Alternate<AF5>>
+        ]
+    I2C6:
+        SCL: [
+            Pin1<Alternate<AF5>>,
+            Pin23<Alternate<AF5>>
+        ]
+        SDA: [
+            Pin0<Alternate<AF5>>,
+            Pin22<Alternate<AF5>>
+        ]
+}
+
+/// I2C master abstraction
+#[derive(Debug)]
+pub struct I2c<I2C, PINS> {
+    i2c: I2C,
+    pins: PINS,
+}
+
+macro_rules! hal {
+    ($($I2CX:ident: $i2cX:ident,)+) => {
+        $(
+            impl<PINS> I2c<$I2CX, PINS> {
+                pub fn $i2cX(i2c: $I2CX, pins: PINS, freq: Hertz, clocks: Clocks) -> Self
+                where
+                    PINS: Pins<$I2CX>,
+                {
+                    let mut i2c = I2c { i2c, pins };
+                    i2c.init(freq, clocks);
+                    i2c
+                }
+
+                pub fn free(self) -> ($I2CX, PINS) {
+                    (self.i2c, self.pins)
+                }
+            }
+        )+
+    }
+}
+
+hal! {
+    I2C0: i2c0,
+    I2C1: i2c1,
+    I2C3: i2c3,
+    I2C4: i2c4,
+    I2C5: i2c5,
+    I2C6: i2c6,
+}
+
+impl<I2C, PINS> I2c<I2C, PINS>
+where
+    I2C: DerefMut<Target = RegisterBlock>,
+{
+    fn init(&mut self, freq: Hertz, clocks: Clocks) {
+        // Disable, clear FIFOs and status
+        self.i2c.control.modify(Control::Enable::Clear);
+        self.i2c.control.modify(Control::Clear::ClearFifo);
+        self.clear_status();
+
+        // Ported from the Linux driver
+        // SCL = core_clk / CDIV, CDIV is rounded up to an even number so SCL
+        // never exceeds the requested frequency
+        let mut div = if freq.0 > 0 {
+            (clocks.core().0 + freq.0 - 1) / freq.0
+        } else {
+            0xFFFE
+        };
+        div += div % 2;
+        let div = div.max(2).min(0xFFFE);
+        self.i2c
+            .div
+            .modify(ClockDivider::Divider::Field::new(div).unwrap());
+
+        // Sample and drive SDA relative to the SCL edges
+        let fedl = (div / 16).max(1);
+        let redl = (div / 4).max(1);
+        self.i2c.del.modify(
+            DataDelay::FallingEdgeDelay::Field::new(fedl).unwrap()
+                + DataDelay::RisingEdgeDelay::Field::new(redl).unwrap(),
+        );
+
+        // Timeout is in SCL cycles
+        let scl = clocks.core().0 / div;
+        let clkt = ((CLOCK_STRETCH_TIMEOUT_MS * scl) / 1000).min(0xFFFF);
+        self.i2c
+            .clkt
+            .modify(ClockStretchTimeout::Timeout::Field::new(clkt).unwrap());
+
+        self.i2c.control.modify(Control::Enable::Set);
+    }
+
+    fn clear_status(&mut self) {
+        // Write 1 to clear
+        self.i2c
+            .status
+            .modify(Status::Done::Set + Status::Error::Set + Status::ClockStretchTimeout::Set);
+    }
+
+    fn setup(&mut self, address: u8, len: usize) -> Result<(), Error> {
+        if len > 0xFFFF {
+            return Err(Error::Length);
+        }
+        self.i2c.control.modify(Control::Clear::ClearFifo);
+        self.clear_status();
+        self.i2c
+            .sa
+            .modify(SlaveAddress::Address::Field::new(u32::from(address & 0x7F)).unwrap());
+        self.i2c
+            .dlen
+            .modify(DataLen::Len::Field::new(len as u32).unwrap());
+        Ok(())
+    }
+
+    fn start(&mut self, read: bool) {
+        let rw = if read {
+            Control::Rw::ReadTransfer
+        } else {
+            Control::Rw::WriteTransfer
+        };
+        self.i2c
+            .control
+            .modify(Control::Enable::Set + Control::StartTransfer::Set + rw);
+    }
+
+    /// Returns the transfer's error, if any, and clears the status
+    fn check_errors(&mut self) -> Result<(), Error> {
+        let res = if self.i2c.status.is_set(Status::Error::Read) {
+            Err(Error::Nack)
+        } else if self.i2c.status.is_set(Status::ClockStretchTimeout::Read) {
+            Err(Error::ClockStretchTimeout)
+        } else {
+            Ok(())
+        };
+        if res.is_err() {
+            self.i2c.control.modify(Control::Clear::ClearFifo);
+            self.clear_status();
+        }
+        res
+    }
+
+    fn is_done(&self) -> bool {
+        self.i2c.status.is_set(Status::Done::Read)
+            || self.i2c.status.is_set(Status::Error::Read)
+            || self.i2c.status.is_set(Status::ClockStretchTimeout::Read)
+    }
+
+    /// Feeds the Tx FIFO until the transfer completes
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let mut iter = bytes.iter();
+        while !self.is_done() {
+            while self.i2c.status.is_set(Status::TxData::Read) {
+                if let Some(b) = iter.next() {
+                    self.i2c.fifo.write(u32::from(*b));
+                } else {
+                    break;
+                }
+            }
+        }
+        self.check_errors()?;
+        self.clear_status();
+        Ok(())
+    }
+
+    /// Drains the Rx FIFO until the transfer completes
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut count = 0;
+        loop {
+            let done = self.is_done();
+            while count < buffer.len() && self.i2c.status.is_set(Status::RxData::Read) {
+                buffer[count] = (self.i2c.fifo.read() & 0xFF) as u8;
+                count += 1;
+            }
+            if done {
+                break;
+            }
+        }
+        self.check_errors()?;
+        self.clear_status();
+        Ok(())
+    }
+}
+
+impl<I2C, PINS> Write for I2c<I2C, PINS>
+where
+    I2C: DerefMut<Target = RegisterBlock>,
+{
+    type Error = Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.setup(address, bytes.len())?;
+
+        // Pre-fill the FIFO so the bus doesn't stall right after the address
+        let prefill = bytes.len().min(FIFO_SIZE);
+        for b in &bytes[..prefill] {
+            self.i2c.fifo.write(u32::from(*b));
+        }
+
+        self.start(false);
+        self.write_bytes(&bytes[prefill..])
+    }
+}
+
+impl<I2C, PINS> Read for I2c<I2C, PINS>
+where
+    I2C: DerefMut<Target = RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.setup(address, buffer.len())?;
+        self.start(true);
+        self.read_bytes(buffer)
+    }
+}
+
+impl<I2C, PINS> WriteRead for I2c<I2C, PINS>
+where
+    I2C: DerefMut<Target = RegisterBlock>,
+{
+    type Error = Error;
+
+    /// Uses a repeated start when the write fits in the FIFO, otherwise the
+    /// write and read are separate transfers with a stop in between
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error> {
+        if bytes.is_empty() || bytes.len() > FIFO_SIZE {
+            if !bytes.is_empty() {
+                self.write(address, bytes)?;
+            }
+            return self.read(address, buffer);
+        }
+        if buffer.len() > 0xFFFF {
+            return Err(Error::Length);
+        }
+
+        self.setup(address, bytes.len())?;
+        for b in bytes {
+            self.i2c.fifo.write(u32::from(*b));
+        }
+        self.start(false);
+
+        // Wait for the write to be underway, then queue the read.
+        // The controller issues a repeated start instead of a stop once
+        // the write completes.
+        while !self.i2c.status.is_set(Status::TransferActive::Read) {
+            if self.is_done() {
+                break;
+            }
+        }
+        self.i2c
+            .dlen
+            .modify(DataLen::Len::Field::new(buffer.len() as u32).unwrap());
+        self.start(true);
+
+        self.read_bytes(buffer)
+    }
+}
@@ -12,9 +12,12 @@ pub mod clocks;
 pub mod delay;
 pub mod dma;
 pub mod eth;
+pub mod gic;
 pub mod gpio;
+pub mod i2c;
 pub mod mailbox;
 pub mod prelude;
+pub mod pwm;
 pub mod rng;
 pub mod serial;
 pub mod spi;
@@ -6,6 +6,7 @@
 mod alloc_framebuffer;
 mod get_arm_mem;
 mod get_clock_rate;
+mod get_dma_channels;
 mod get_mac_address;
 mod get_serial_num;
 mod get_temp;
@@ -24,6 +25,9 @@ pub use self::get_arm_mem::{Repr as GetArmMemRepr, Req as GetArmMemReq, Resp as
 pub use self::get_clock_rate::{
     Repr as GetClockRateRepr, Req as GetClockRateReq, Resp as GetClockRateResp,
 };
+pub use self::get_dma_channels::{
+    Repr as GetDmaChannelsRepr, Req as GetDmaChannelsReq, Resp as GetDmaChannelsResp,
+};
 pub use self::get_mac_address::{
     Repr as GetMacAddressRepr, Req as GetMacAddressReq, Resp as GetMacAddressResp,
 };
@@ -71,6 +75,7 @@ pub enum ReqMsg {
     GetClockRate(GetClockRateRepr),
     GetMacAddress(GetMacAddressRepr),
     GetSerialNum(GetSerialNumRepr),
+    GetDmaChannels(GetDmaChannelsRepr),
     AllocFramebuffer(AllocFramebufferRepr),
 }
 
@@ -82,6 +87,7 @@ pub enum RespMsg {
     GetClockRate(GetClockRateRepr),
     GetMacAddress(GetMacAddressRepr),
     GetSerialNum(GetSerialNumRepr),
+    GetDmaChannels(GetDmaChannelsRepr),
     AllocFramebuffer(AllocFramebufferRepr),
 }
 
@@ -209,6 +215,9 @@ impl<'a, T: AsRef<[u32]> + ?Sized> TryFrom<Msg<&'a T>> for RespMsg {
                 &m,
             )?)),
             TagId::GetSerialNum => Ok(RespMsg::GetSerialNum(GetSerialNumRepr::parse_response(&m)?)),
+            TagId::GetDmaChannels => Ok(RespMsg::GetDmaChannels(
+                GetDmaChannelsRepr::parse_response(&m)?,
+            )),
             // TODO - frame buffer currently just matches on the first TagId
             TagId::SetPhySize => Ok(RespMsg::AllocFramebuffer(
                 AllocFramebufferRepr::parse_response(&m)?,
@@ -238,6 +247,7 @@ impl MsgEmitter for &ReqMsg {
             ReqMsg::GetClockRate(repr) => repr.msg_size(),
             ReqMsg::GetMacAddress(repr) => repr.msg_size(),
             ReqMsg::GetSerialNum(repr) => repr.msg_size(),
+            ReqMsg::GetDmaChannels(repr) => repr.msg_size(),
             ReqMsg::AllocFramebuffer(repr) => repr.msg_size(),
         }
     }
@@ -249,6 +259,7 @@ impl MsgEmitter for &ReqMsg {
             ReqMsg::GetVcMem(repr) => repr.emit_msg(msg),
             ReqMsg::GetMacAddress(repr) => repr.emit_msg(msg),
             ReqMsg::GetSerialNum(repr) => repr.emit_msg(msg),
+            ReqMsg::GetDmaChannels(repr) => repr.emit_msg(msg),
             ReqMsg::GetClockRate(repr) => repr.emit_msg(msg),
             ReqMsg::AllocFramebuffer(repr) => repr.emit_msg(msg),
         }
@@ -12,6 +12,7 @@ pub enum TagId {
     GetVcMem = 0x0001_0006,
     GetClockRate = 0x0003_0002,
     GetTemperature = 0x0003_0006,
+    GetDmaChannels = 0x0006_0001,
     AllocBuffer = 0x0004_0001,
     GetPitch = 0x0004_0008,
     GetPhySize = 0x0004_0003,
@@ -41,6 +42,7 @@ impl TryFrom<u32> for TagId {
             0x0001_0006 => Ok(TagId::GetVcMem),
             0x0003_0002 => Ok(TagId::GetClockRate),
             0x0003_0006 => Ok(TagId::GetTemperature),
+            0x0006_0001 => Ok(TagId::GetDmaChannels),
             0x0004_0001 => Ok(TagId::AllocBuffer),
             0x0004_0008 => Ok(TagId::GetPitch),
             0x0004_0003 => Ok(TagId::GetPhySize),
@@ -0,0 +1,438 @@
+//! PWM
+//!
+//! TODO - update this once bcm2711 docs are available
+//!
+//! - PWM0 and PWM1 have two channels each, they share the PWM clock
+//!   which is configured once with `PwmClock`
+//! - Channels use the mark-space mode, the period is `range` PWM clock
+//!   cycles and the duty cycle is `data` clock cycles
+//! - Both channels of an instance share the same period
+//! - `Pwm::with_dma` feeds the channels from the FIFO for audio-rate
+//!   sample output
+//! - PWM1 is wired to the analog audio jack on GPIO40/41
+
+use crate::dma;
+use crate::gpio::{Alternate, Pin12, Pin13, Pin18, Pin19, Pin40, Pin41, Pin45, AF0, AF5};
+use crate::hal;
+use crate::time::Hertz;
+use bcm2711::cm::{self, CM};
+use bcm2711::pwm0::*;
+use bcm2711::pwm1::PWM1;
+use core::ops::DerefMut;
+
+/// Crystal oscillator frequency
+const OSC_FREQ: u32 = 54_000_000;
+
+/// PLLD peripheral clock frequency
+const PLLD_FREQ: u32 = 750_000_000;
+
+/// Largest integer divisor of the clock generator
+const CLOCK_DIV_MAX: u32 = 0xFFF;
+
+/// FIFO level below which DREQ and panic are raised
+const DMA_THRESHOLD: u32 = 7;
+
+/// Largest transfer a single control block can do on any channel,
+/// rounded down to a whole number of samples
+const DMA_TRANSFER_MAX: usize = 0xFFFC;
+
+/// PWM error
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// The DMA channel reported an error
+    Dma,
+}
+
+/// PWM clock generator source
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ClockSource {
+    /// 54 MHz crystal oscillator
+    Oscillator,
+    /// 750 MHz PLLD
+    PllD,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Channel {
+    C1,
+    C2,
+}
+
+pub trait Pins<PWM> {
+    const C1: bool = false;
+    const C2: bool = false;
+}
+pub trait PinC1<PWM> {}
+pub trait PinC2<PWM> {}
+
+impl<PWM, CH1, CH2> Pins<PWM> for (CH1, CH2)
+where
+    CH1: PinC1<PWM>,
+    CH2: PinC2<PWM>,
+{
+    const C1: bool = true;
+    const C2: bool = true;
+}
+
+macro_rules! pins {
+    ($($PWMX:ty: C1: [$($C1:ty),*] C2: [$($C2:ty),*])+) => {
+        $(
+            $(
+                impl PinC1<$PWMX> for $C1 {}
+                impl Pins<$PWMX> for $C1 {
+                    const C1: bool = true;
+                }
+            )*
+            $(
+                impl PinC2<$PWMX> for $C2 {}
+                impl Pins<$PWMX> for $C2 {
+                    const C2: bool = true;
+                }
+            )*
+        )+
+    }
+}
+
+pins! {
+    PWM0:
+        C1: [
+            Pin12<Alternate<AF0>>,
+            Pin18<Alternate<AF5>>
+        ]
+        C2: [
+            Pin13<Alternate<AF0>>,
+            Pin19<Alternate<AF5>>,
+            Pin45<Alternate<AF0>>
+        ]
+    PWM1:
+        C1: [Pin40<Alternate<AF0>>]
+        C2: [Pin41<Alternate<AF0>>]
+}
+
+/// PWM clock, shared by PWM0 and PWM1
+pub struct PwmClock {
+    cm: CM,
+    freq: Hertz,
+}
+
+impl PwmClock {
+    /// Starts the PWM clock generator with an integer divisor, the
+    /// resulting frequency is rounded down to what the divisor allows
+    pub fn new(mut cm: CM, source: ClockSource, freq: Hertz) -> Self {
+        let (src, src_freq) = match source {
+            ClockSource::Oscillator => (cm::Control::Source::Oscillator, OSC_FREQ),
+            ClockSource::PllD => (cm::Control::Source::PllD, PLLD_FREQ),
+        };
+        let div = if freq.0 > 0 {
+            (src_freq + freq.0 - 1) / freq.0
+        } else {
+            CLOCK_DIV_MAX
+        };
+        let div = div.max(2).min(CLOCK_DIV_MAX);
+
+        Self::stop(&mut cm);
+
+        cm.pwm_div.modify(
+            cm::Divisor::Password::Password
+                + cm::Divisor::DivI::Field::new(div).unwrap()
+                + cm::Divisor::DivF::Field::new(0).unwrap(),
+        );
+        cm.pwm_ctl.modify(
+            cm::Control::Password::Password + src + cm::Control::Mash::Field::new(0).unwrap(),
+        );
+        cm.pwm_ctl
+            .modify(cm::Control::Password::Password + cm::Control::Enable::Set);
+        while !cm.pwm_ctl.is_set(cm::Control::Busy::Read) {}
+
+        PwmClock {
+            cm,
+            freq: Hertz(src_freq / div),
+        }
+    }
+
+    pub fn freq(&self) -> Hertz {
+        self.freq
+    }
+
+    /// Stops the clock generator
+    pub fn free(mut self) -> CM {
+        Self::stop(&mut self.cm);
+        self.cm
+    }
+
+    fn stop(cm: &mut CM) {
+        // The source and divisor can only be changed while the generator
+        // isn't busy
+        cm.pwm_ctl
+            .modify(cm::Control::Password::Password + cm::Control::Enable::Clear);
+        while cm.pwm_ctl.is_set(cm::Control::Busy::Read) {}
+    }
+}
+
+/// PWM abstraction
+pub struct Pwm<PWM, PINS> {
+    pwm: PWM,
+    pins: PINS,
+    clock: Hertz,
+}
+
+macro_rules! pwm {
+    ($($PWMX:ident: ($pwmX:ident, $dreq:expr),)+) => {
+        $(
+            impl<PINS> Pwm<$PWMX, PINS> {
+                /// Both channels start disabled with a 0 duty cycle
+                pub fn $pwmX(pwm: $PWMX, pins: PINS, clock: &PwmClock, period: Hertz) -> Self
+                where
+                    PINS: Pins<$PWMX>,
+                {
+                    let mut pwm = Pwm {
+                        pwm,
+                        pins,
+                        clock: clock.freq(),
+                    };
+                    pwm.init(period);
+                    pwm
+                }
+
+                /// Feeds the channels from the FIFO, using a DMA channel paced
+                /// by the PWM DREQ
+                pub fn with_dma<'a>(
+                    self,
+                    chan: dma::Channel,
+                    dcb: &'a mut dma::ControlBlock,
+                ) -> PwmDma<'a, $PWMX, PINS>
+                where
+                    PINS: Pins<$PWMX>,
+                {
+                    PwmDma::new(self, chan, dcb, $dreq)
+                }
+
+                pub fn free(mut self) -> ($PWMX, PINS) {
+                    self.pwm.ctl.write(0);
+                    (self.pwm, self.pins)
+                }
+            }
+        )+
+    }
+}
+
+pwm! {
+    PWM0: (pwm0, dma::Dreq::Pwm0),
+    PWM1: (pwm1, dma::Dreq::Pwm1),
+}
+
+impl<PWM, PINS> Pwm<PWM, PINS>
+where
+    PWM: DerefMut<Target = RegisterBlock>,
+{
+    fn init(&mut self, period: Hertz) {
+        self.pwm.ctl.write(0);
+        self.pwm.dmac.write(0);
+        self.clear_status();
+
+        self.set_range(period);
+        self.pwm.dat1.write(0);
+        self.pwm.dat2.write(0);
+        self.pwm
+            .ctl
+            .modify(Control::MarkSpace1::Set + Control::MarkSpace2::Set);
+    }
+
+    fn clear_status(&mut self) {
+        // Write 1 to clear
+        self.pwm.sta.modify(
+            Status::FifoWriteError::Set
+                + Status::FifoReadError::Set
+                + Status::Gap1::Set
+                + Status::Gap2::Set
+                + Status::BusError::Set,
+        );
+    }
+
+    /// Sets both channels' range to the number of PWM clock cycles in
+    /// `period`, returns the range
+    fn set_range(&mut self, period: Hertz) -> u32 {
+        let range = if period.0 > 0 {
+            (self.clock.0 / period.0).max(1)
+        } else {
+            u32::max_value()
+        };
+        self.pwm.rng1.write(range);
+        self.pwm.rng2.write(range);
+        range
+    }
+}
+
+impl<PWM, PINS> hal::Pwm for Pwm<PWM, PINS>
+where
+    PWM: DerefMut<Target = RegisterBlock>,
+{
+    type Channel = Channel;
+    type Time = Hertz;
+    type Duty = u32;
+
+    fn disable(&mut self, channel: Channel) {
+        match channel {
+            Channel::C1 => self.pwm.ctl.modify(Control::Enable1::Clear),
+            Channel::C2 => self.pwm.ctl.modify(Control::Enable2::Clear),
+        }
+    }
+
+    fn enable(&mut self, channel: Channel) {
+        match channel {
+            Channel::C1 => self.pwm.ctl.modify(Control::Enable1::Set),
+            Channel::C2 => self.pwm.ctl.modify(Control::Enable2::Set),
+        }
+    }
+
+    fn get_period(&self) -> Hertz {
+        Hertz(self.clock.0 / self.pwm.rng1.read().max(1))
+    }
+
+    fn get_duty(&self, channel: Channel) -> u32 {
+        match channel {
+            Channel::C1 => self.pwm.dat1.read(),
+            Channel::C2 => self.pwm.dat2.read(),
+        }
+    }
+
+    /// The duty cycle is in PWM clock cycles, up to the period's range
+    fn get_max_duty(&self) -> u32 {
+        self.pwm.rng1.read()
+    }
+
+    fn set_duty(&mut self, channel: Channel, duty: u32) {
+        let duty = duty.min(self.pwm.rng1.read());
+        match channel {
+            Channel::C1 => self.pwm.dat1.write(duty),
+            Channel::C2 => self.pwm.dat2.write(duty),
+        }
+    }
+
+    /// Changing the period doesn't rescale the duty cycles
+    fn set_period<P>(&mut self, period: P)
+    where
+        P: Into<Hertz>,
+    {
+        self.set_range(period.into());
+    }
+}
+
+/// PWM with the channels fed from the FIFO by DMA
+///
+/// Samples are duty cycles in PWM clock cycles, up to `range`.
+/// Channels with a pin are enabled, if both are the samples are
+/// interleaved: channel 1 then channel 2.
+pub struct PwmDma<'a, PWM, PINS> {
+    pwm: Pwm<PWM, PINS>,
+    chan: dma::Channel,
+    dcb: &'a mut dma::ControlBlock,
+    dreq: dma::Dreq,
+    range: u32,
+}
+
+impl<'a, PWM, PINS> PwmDma<'a, PWM, PINS>
+where
+    PWM: DerefMut<Target = RegisterBlock>,
+    PINS: Pins<PWM>,
+{
+    fn new(
+        mut pwm: Pwm<PWM, PINS>,
+        chan: dma::Channel,
+        dcb: &'a mut dma::ControlBlock,
+        dreq: dma::Dreq,
+    ) -> Self {
+        pwm.pwm.ctl.write(0);
+        pwm.clear_status();
+        pwm.pwm.ctl.modify(Control::ClearFifo::Set);
+        pwm.pwm.ctl.modify(
+            Control::MarkSpace1::Set
+                + Control::MarkSpace2::Set
+                + Control::UseFifo1::Field::new(PINS::C1 as _).unwrap()
+                + Control::UseFifo2::Field::new(PINS::C2 as _).unwrap(),
+        );
+        pwm.pwm.dmac.modify(
+            DmaConfig::DreqThreshold::Field::new(DMA_THRESHOLD).unwrap()
+                + DmaConfig::PanicThreshold::Field::new(DMA_THRESHOLD).unwrap()
+                + DmaConfig::Enable::Set,
+        );
+        pwm.pwm.ctl.modify(
+            Control::Enable1::Field::new(PINS::C1 as _).unwrap()
+                + Control::Enable2::Field::new(PINS::C2 as _).unwrap(),
+        );
+
+        let range = pwm.pwm.rng1.read();
+        PwmDma {
+            pwm,
+            chan,
+            dcb,
+            dreq,
+            range,
+        }
+    }
+
+    /// Stops the FIFO output, the channels go back to using their data
+    /// registers, disabled
+    pub fn free(mut self) -> (Pwm<PWM, PINS>, dma::Channel, &'a mut dma::ControlBlock) {
+        self.pwm.pwm.dmac.write(0);
+        self.pwm.pwm.ctl.write(0);
+        self.pwm.pwm.ctl.modify(Control::ClearFifo::Set);
+        self.pwm.clear_status();
+        self.pwm
+            .pwm
+            .ctl
+            .modify(Control::MarkSpace1::Set + Control::MarkSpace2::Set);
+        (self.pwm, self.chan, self.dcb)
+    }
+
+    /// Sets the sample rate, each sample is output for one PWM period.
+    /// Returns the new range, the largest sample value
+    pub fn set_sample_rate(&mut self, rate: Hertz) -> u32 {
+        self.range = self.pwm.set_range(rate);
+        self.range
+    }
+
+    /// Largest sample value
+    pub fn range(&self) -> u32 {
+        self.range
+    }
+
+    /// Blocks until all of the samples have been written to the FIFO
+    pub fn write(&mut self, samples: &[u32]) -> Result<(), Error> {
+        for chunk in samples.chunks(DMA_TRANSFER_MAX / 4) {
+            self.write_chunk(chunk)?;
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, samples: &[u32]) -> Result<(), Error> {
+        let len = samples.len() * 4;
+
+        // Memory -> FIFO, paced by the PWM DREQ
+        self.dcb.init();
+        self.dcb.set_src(samples.as_ptr() as u32);
+        self.dcb
+            .set_dest_periph(&self.pwm.pwm.fif1 as *const _ as usize, self.dreq);
+        self.dcb
+            .set_length(dma::TransferLength::ModeLinear(len as u32));
+        self.dcb.info.set_src_inc(true);
+        self.dcb.info.set_wait_resp(true);
+
+        // Only the memory side gets cache maintenance, the FIFO address
+        // isn't cacheable
+        self.chan.start(&dma::TransferResources {
+            src_cached: true,
+            dest_cached: false,
+            dcb: self.dcb,
+            src_buffer: samples,
+            dest_buffer: &mut [],
+        });
+        self.chan.wait();
+
+        if self.chan.errors() {
+            Err(Error::Dma)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Interrupt driven, buffered serial
+//!
+//! The IRQ handler drains the Rx FIFO into the Rx ring buffer and refills
+//! the Tx FIFO from the Tx ring buffer.
+//! Register the handler with the `gic` and enable the interrupt:
+//!
+//! ```ignore
+//! gic.register(irq::UART, serial::uart0_irq_handler)?;
+//! gic.enable(irq::UART)?;
+//! ```
+//!
+//! UART1 shares the AUX interrupt (`irq::AUX`) with SPI1 and SPI2.
+//!
+//! Receive errors are latched by the handler and reported by the next
+//! `read`, the first error wins.
+
+use crate::gic;
+use crate::hal::serial;
+use crate::serial::ring_buffer::RingBuffer;
+use crate::serial::{pl011_error, Error, Event, Serial, LSR_DATA_READY, LSR_RX_OVERRUN};
+use bcm2711::uart0::UART0;
+use bcm2711::uart1::UART1;
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
+use nb::block;
+
+/// Mini UART Tx FIFO can accept at least one byte
+const LSR_TX_EMPTY: u32 = 1 << 5;
+
+/// Ring buffers and latched errors shared with the IRQ handler
+struct State {
+    rx: RingBuffer,
+    tx: RingBuffer,
+    /// `Error` + 1, 0 when there's no error
+    error: AtomicU8,
+}
+
+impl State {
+    const fn new() -> Self {
+        State {
+            rx: RingBuffer::new(),
+            tx: RingBuffer::new(),
+            error: AtomicU8::new(0),
+        }
+    }
+
+    fn attach(&self, rx_buffer: &'static mut [u8], tx_buffer: &'static mut [u8]) {
+        gic::free(|| {
+            self.rx.attach(rx_buffer);
+            self.tx.attach(tx_buffer);
+            self.error.store(0, Ordering::Relaxed);
+        });
+    }
+
+    fn detach(&self) -> (&'static mut [u8], &'static mut [u8]) {
+        gic::free(|| (self.rx.detach(), self.tx.detach()))
+    }
+
+    fn set_error(&self, err: Error) {
+        let _ = self
+            .error
+            .compare_exchange(0, err as u8 + 1, Ordering::AcqRel, Ordering::Relaxed);
+    }
+
+    fn take_error(&self) -> Option<Error> {
+        match self.error.swap(0, Ordering::AcqRel) {
+            0 => None,
+            1 => Some(Error::Framing),
+            2 => Some(Error::Parity),
+            3 => Some(Error::Break),
+            _ => Some(Error::Overrun),
+        }
+    }
+}
+
+static UART0_STATE: State = State::new();
+static UART1_STATE: State = State::new();
+
+/// Serial with interrupt driven Rx and Tx ring buffers
+pub struct BufferedSerial<UART, PINS> {
+    serial: Serial<UART, PINS>,
+}
+
+impl<PINS> Serial<UART0, PINS> {
+    /// Moves data through the ring buffers from the UART IRQ handler,
+    /// `uart0_irq_handler`
+    pub fn into_buffered(
+        mut self,
+        rx_buffer: &'static mut [u8],
+        tx_buffer: &'static mut [u8],
+    ) -> BufferedSerial<UART0, PINS> {
+        UART0_STATE.attach(rx_buffer, tx_buffer);
+        self.listen(Event::Rxne);
+        self.listen(Event::Error);
+        BufferedSerial { serial: self }
+    }
+}
+
+impl<PINS> BufferedSerial<UART0, PINS> {
+    /// Number of received bytes waiting to be read
+    pub fn rx_len(&self) -> usize {
+        UART0_STATE.rx.len()
+    }
+
+    /// Number of bytes waiting to be transmitted
+    pub fn tx_len(&self) -> usize {
+        UART0_STATE.tx.len()
+    }
+
+    /// Returns the serial and the ring buffers, pending Tx data is
+    /// discarded
+    pub fn free(mut self) -> (Serial<UART0, PINS>, &'static mut [u8], &'static mut [u8]) {
+        self.serial.unlisten(Event::Rxne);
+        self.serial.unlisten(Event::Txe);
+        self.serial.unlisten(Event::Error);
+        let (rx_buffer, tx_buffer) = UART0_STATE.detach();
+        (self.serial, rx_buffer, tx_buffer)
+    }
+
+    fn start_tx(&mut self) {
+        gic::free(|| {
+            uart0_fill_tx(&mut self.serial.uart);
+            if !UART0_STATE.tx.is_empty() {
+                self.serial.listen(Event::Txe);
+            }
+        });
+    }
+}
+
+/// Refills the Tx FIFO from the ring buffer, masks the Tx interrupt once
+/// it's empty
+fn uart0_fill_tx(uart: &mut UART0) {
+    use bcm2711::uart0::{Flag, Int};
+    while !uart.fr.is_set(Flag::TxFull::Read) {
+        match UART0_STATE.tx.pop() {
+            Some(byte) => uart.dr.write(u32::from(byte)),
+            None => {
+                uart.imsc.modify(Int::Tx::Clear);
+                break;
+            }
+        }
+    }
+}
+
+/// UART0 IRQ handler, to be registered with the `gic`
+pub fn uart0_irq_handler() {
+    use bcm2711::uart0::Flag;
+    let mut uart = UART0::new();
+    let mis = uart.mis.read();
+
+    while !uart.fr.is_set(Flag::RxEmpty::Read) {
+        let dr = uart.dr.read();
+        let err = pl011_error(dr);
+        if let Some(err) = err {
+            UART0_STATE.set_error(err);
+        }
+
+        // On overrun the character is still valid, the one after it was lost
+        if (err.is_none() || err == Some(Error::Overrun)) && UART0_STATE.rx.push(dr as u8).is_err()
+        {
+            UART0_STATE.set_error(Error::Overrun);
+        }
+    }
+
+    uart0_fill_tx(&mut uart);
+
+    uart.icr.write(mis);
+}
+
+impl<PINS> serial::Read<u8> for BufferedSerial<UART0, PINS> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        if let Some(err) = UART0_STATE.take_error() {
+            return Err(nb::Error::Other(err));
+        }
+        UART0_STATE.rx.pop().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+impl<PINS> serial::Write<u8> for BufferedSerial<UART0, PINS> {
+    type Error = Error;
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        use bcm2711::uart0::Flag;
+        if UART0_STATE.tx.is_empty()
+            && self.serial.uart.fr.is_set(Flag::TxEmpty::Read)
+            && !self.serial.uart.fr.is_set(Flag::Busy::Read)
+        {
+            Ok(())
+        } else {
+            // Make progress even if IRQs are masked
+            self.start_tx();
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+        let res = UART0_STATE.tx.push(byte);
+        self.start_tx();
+        res.map_err(|_| nb::Error::WouldBlock)
+    }
+}
+
+impl<PINS> Serial<UART1, PINS> {
+    /// Moves data through the ring buffers from the AUX IRQ handler,
+    /// `uart1_irq_handler`
+    pub fn into_buffered(
+        mut self,
+        rx_buffer: &'static mut [u8],
+        tx_buffer: &'static mut [u8],
+    ) -> BufferedSerial<UART1, PINS> {
+        UART1_STATE.attach(rx_buffer, tx_buffer);
+        self.listen(Event::Rxne);
+        BufferedSerial { serial: self }
+    }
+}
+
+impl<PINS> BufferedSerial<UART1, PINS> {
+    /// Number of received bytes waiting to be read
+    pub fn rx_len(&self) -> usize {
+        UART1_STATE.rx.len()
+    }
+
+    /// Number of bytes waiting to be transmitted
+    pub fn tx_len(&self) -> usize {
+        UART1_STATE.tx.len()
+    }
+
+    /// Returns the serial and the ring buffers, pending Tx data is
+    /// discarded
+    pub fn free(mut self) -> (Serial<UART1, PINS>, &'static mut [u8], &'static mut [u8]) {
+        self.serial.unlisten(Event::Rxne);
+        self.serial.unlisten(Event::Txe);
+        let (rx_buffer, tx_buffer) = UART1_STATE.detach();
+        (self.serial, rx_buffer, tx_buffer)
+    }
+
+    fn start_tx(&mut self) {
+        gic::free(|| {
+            uart1_fill_tx(&mut self.serial.uart);
+            if !UART1_STATE.tx.is_empty() {
+                self.serial.listen(Event::Txe);
+            }
+        });
+    }
+}
+
+/// Refills the Tx FIFO from the ring buffer, masks the Tx interrupt once
+/// it's empty
+fn uart1_fill_tx(uart: &mut UART1) {
+    use bcm2711::uart1::IntEnable;
+    loop {
+        let lsr = uart.lsr.read();
+        if lsr & LSR_RX_OVERRUN != 0 {
+            UART1_STATE.set_error(Error::Overrun);
+        }
+        if lsr & LSR_TX_EMPTY == 0 {
+            break;
+        }
+        match UART1_STATE.tx.pop() {
+            Some(byte) => uart.io.write(u32::from(byte)),
+            None => {
+                uart.ier.modify(IntEnable::IntTx::Clear);
+                break;
+            }
+        }
+    }
+}
+
+/// UART1 IRQ handler, to be registered with the `gic`
+pub fn uart1_irq_handler() {
+    let mut uart = UART1::new();
+
+    loop {
+        let lsr = uart.lsr.read();
+        if lsr & LSR_RX_OVERRUN != 0 {
+            UART1_STATE.set_error(Error::Overrun);
+        }
+        if lsr & LSR_DATA_READY == 0 {
+            break;
+        }
+        let byte = uart.io.read() as u8;
+        if UART1_STATE.rx.push(byte).is_err() {
+            UART1_STATE.set_error(Error::Overrun);
+        }
+    }
+
+    uart1_fill_tx(&mut uart);
+}
+
+impl<PINS> serial::Read<u8> for BufferedSerial<UART1, PINS> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        if let Some(err) = UART1_STATE.take_error() {
+            return Err(nb::Error::Other(err));
+        }
+        match UART1_STATE.rx.pop() {
+            // convert carrige return to newline, same as `Serial`
+            Some(b'\r') => Ok(b'\n'),
+            Some(byte) => Ok(byte),
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+impl<PINS> serial::Write<u8> for BufferedSerial<UART1, PINS> {
+    type Error = Error;
+
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        use bcm2711::uart1::LineStatus;
+        if UART1_STATE.tx.is_empty() && self.serial.uart.lsr.is_set(LineStatus::TxIdle::Read) {
+            Ok(())
+        } else {
+            // Make progress even if IRQs are masked
+            self.start_tx();
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+        let res = UART1_STATE.tx.push(byte);
+        self.start_tx();
+        res.map_err(|_| nb::Error::WouldBlock)
+    }
+}
+
+impl<UART, PINS> fmt::Write for BufferedSerial<UART, PINS>
+where
+    BufferedSerial<UART, PINS>: serial::Write<u8>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        use crate::hal::serial::Write;
+        for b in s.bytes() {
+            // Convert '\n' to '\r\n'
+            if b as char == '\n' {
+                block!(self.write('\r' as _)).ok();
+            }
+            block!(self.write(b)).ok();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+//! Serial configuration
+
+use crate::time::Bps;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum WordLength {
+    DataBits5,
+    DataBits6,
+    DataBits7,
+    DataBits8,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Parity {
+    ParityNone,
+    ParityEven,
+    ParityOdd,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum StopBits {
+    /// 1 stop bit
+    STOP1,
+    /// 2 stop bits
+    STOP2,
+}
+
+/// The mini UART (UART1) only supports 7 or 8 data bits, no parity,
+/// 1 stop bit, always has its FIFOs enabled and has no loopback mode
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Config {
+    pub baudrate: Bps,
+    pub wordlength: WordLength,
+    pub parity: Parity,
+    pub stopbits: StopBits,
+    /// Enable the Tx and Rx FIFOs, otherwise they're 1 byte deep
+    pub fifo: bool,
+    /// Internally connect Tx to Rx
+    pub loopback: bool,
+}
+
+impl Config {
+    pub fn baudrate(mut self, baudrate: Bps) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    pub fn wordlength(mut self, wordlength: WordLength) -> Self {
+        self.wordlength = wordlength;
+        self
+    }
+
+    pub fn parity_none(mut self) -> Self {
+        self.parity = Parity::ParityNone;
+        self
+    }
+
+    pub fn parity_even(mut self) -> Self {
+        self.parity = Parity::ParityEven;
+        self
+    }
+
+    pub fn parity_odd(mut self) -> Self {
+        self.parity = Parity::ParityOdd;
+        self
+    }
+
+    pub fn stopbits(mut self, stopbits: StopBits) -> Self {
+        self.stopbits = stopbits;
+        self
+    }
+
+    pub fn fifo(mut self, enable: bool) -> Self {
+        self.fifo = enable;
+        self
+    }
+
+    pub fn loopback(mut self, enable: bool) -> Self {
+        self.loopback = enable;
+        self
+    }
+}
+
+/// 115200 8N1, FIFOs enabled
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            baudrate: Bps(115_200),
+            wordlength: WordLength::DataBits8,
+            parity: Parity::ParityNone,
+            stopbits: StopBits::STOP1,
+            fifo: true,
+            loopback: false,
+        }
+    }
+}
+
+/// The configuration isn't supported by the UART
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct InvalidConfig;
@@ -0,0 +1,576 @@
+//! Serial
+//!
+//! TODO - update this once bcm2711 docs are available
+//!
+//! There are five PL011 UARTs (UART0, UART2-5)
+//! and a mini UART (UART1).
+//!
+//! The PL011s all share the `irq::UART` interrupt.
+//!
+//! RTS/CTS hardware flow control is enabled when the pins are given as
+//! a `(TX, RX, CTS, RTS)` tuple.
+//!
+//! UART0 and UART1 can be converted into a `BufferedSerial`, which moves data
+//! between the UART and caller-provided ring buffers from the IRQ handler.
+//!
+//! See the documentation:
+//! https://www.raspberrypi.org/documentation/configuration/uart.md
+
+use crate::clocks::Clocks;
+use crate::gpio::{
+    Alternate, Pin0, Pin1, Pin10, Pin11, Pin12, Pin13, Pin14, Pin15, Pin16, Pin17, Pin2, Pin3,
+    Pin30, Pin31, Pin32, Pin33, Pin36, Pin37, Pin38, Pin39, Pin4, Pin5, Pin6, Pin7, Pin8, Pin9,
+    AF0, AF2, AF3, AF4, AF5,
+};
+use crate::hal::prelude::*;
+use crate::hal::serial;
+use crate::time::Bps;
+use bcm2711::uart0::UART0;
+use bcm2711::uart1::UART1;
+use bcm2711::uart2::UART2;
+use bcm2711::uart3::UART3;
+use bcm2711::uart4::UART4;
+use bcm2711::uart5::UART5;
+use core::fmt;
+use nb::block;
+use void::Void;
+
+pub mod config;
+
+mod buffered;
+mod ring_buffer;
+
+pub use crate::serial::buffered::{uart0_irq_handler, uart1_irq_handler, BufferedSerial};
+pub use crate::serial::config::{Config, InvalidConfig, Parity, StopBits, WordLength};
+
+/// Mini UART line status bits, the register is read once since reading
+/// clears the overrun flag
+const LSR_DATA_READY: u32 = 1 << 0;
+const LSR_RX_OVERRUN: u32 = 1 << 1;
+
+/// Serial error
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// Framing error
+    Framing,
+    /// Parity check error
+    Parity,
+    /// Break condition detected
+    Break,
+    /// Rx FIFO or buffer overrun, data was lost
+    Overrun,
+}
+
+/// Interrupt events
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Event {
+    /// New data has been received, or the Rx FIFO has data that hasn't
+    /// reached the threshold
+    Rxne,
+    /// The Tx FIFO can accept more data
+    Txe,
+    /// Framing, parity, break or overrun error, UART0 only
+    Error,
+}
+
+/// PL011 FIFO interrupt threshold
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum FifoThreshold {
+    OneEighth,
+    OneQuarter,
+    OneHalf,
+    ThreeQuarters,
+    SevenEighths,
+}
+
+pub trait Pins<UART> {
+    /// RTS/CTS hardware flow control
+    const FLOW_CONTROL: bool = false;
+}
+pub trait PinTx<UART> {}
+pub trait PinRx<UART> {}
+pub trait PinCts<UART> {}
+pub trait PinRts<UART> {}
+
+impl<UART, TX, RX> Pins<UART> for (TX, RX)
+where
+    TX: PinTx<UART>,
+    RX: PinRx<UART>,
+{
+}
+
+impl<UART, TX, RX, CTS, RTS> Pins<UART> for (TX, RX, CTS, RTS)
+where
+    TX: PinTx<UART>,
+    RX: PinRx<UART>,
+    CTS: PinCts<UART>,
+    RTS: PinRts<UART>,
+{
+    const FLOW_CONTROL: bool = true;
+}
+
+macro_rules! pins {
+    ($($UARTX:ty: TX: [$($TX:ty),*] RX: [$($RX:ty),*] CTS: [$($CTS:ty),*] RTS: [$($RTS:ty),*])+) => {
+        $(
+            $(
+                impl PinTx<$UARTX> for $TX {}
+            )*
+            $(
+                impl PinRx<$UARTX> for $RX {}
+            )*
+            $(
+                impl PinCts<$UARTX> for $CTS {}
+            )*
+            $(
+                impl PinRts<$UARTX> for $RTS {}
+            )*
+        )+
+    }
+}
+
+// GPIO30-33 are wired to the Bluetooth module
+pins! {
+    UART0:
+        TX: [
+            Pin14<Alternate<AF0>>,
+            Pin32<Alternate<AF3>>,
+            Pin36<Alternate<AF2>>
+        ]
+        RX: [
+            Pin15<Alternate<AF0>>,
+            Pin33<Alternate<AF3>>,
+            Pin37<Alternate<AF2>>
+        ]
+        CTS: [
+            Pin16<Alternate<AF3>>,
+            Pin30<Alternate<AF3>>,
+            Pin39<Alternate<AF2>>
+        ]
+        RTS: [
+            Pin17<Alternate<AF3>>,
+            Pin31<Alternate<AF3>>,
+            Pin38<Alternate<AF2>>
+        ]
+    UART1:
+        TX: [
+            Pin14<Alternate<AF5>>,
+            Pin32<Alternate<AF5>>
+        ]
+        RX: [
+            Pin15<Alternate<AF5>>,
+            Pin33<Alternate<AF5>>
+        ]
+        CTS: [
+            Pin16<Alternate<AF5>>,
+            Pin30<Alternate<AF5>>
+        ]
+        RTS: [
+            Pin17<Alternate<AF5>>,
+            Pin31<Alternate<AF5>>
+        ]
+    UART2:
+        TX: [Pin0<Alternate<AF4>>]
+        RX: [Pin1<Alternate<AF4>>]
+        CTS: [Pin2<Alternate<AF4>>]
+        RTS: [Pin3<Alternate<AF4>>]
+    UART3:
+        TX: [Pin4<Alternate<AF4>>]
+        RX: [Pin5<Alternate<AF4>>]
+        CTS: [Pin6<Alternate<AF4>>]
+        RTS: [Pin7<Alternate<AF4>>]
+    UART4:
+        TX: [Pin8<Alternate<AF4>>]
+        RX: [Pin9<Alternate<AF4>>]
+        CTS: [Pin10<Alternate<AF4>>]
+        RTS: [Pin11<Alternate<AF4>>]
+    UART5:
+        TX: [Pin12<Alternate<AF4>>]
+        RX: [Pin13<Alternate<AF4>>]
+        CTS: [Pin14<Alternate<AF4>>]
+        RTS: [Pin15<Alternate<AF4>>]
+}
+
+/// Serial abstraction
+pub struct Serial<UART, PINS> {
+    uart: UART,
+    pins: PINS,
+}
+
+/// Decodes the error bits of a PL011 data register read
+fn pl011_error(dr: u32) -> Option<Error> {
+    // A break also sets the framing error bit
+    if dr & (1 << 10) != 0 {
+        Some(Error::Break)
+    } else if dr & (1 << 8) != 0 {
+        Some(Error::Framing)
+    } else if dr & (1 << 9) != 0 {
+        Some(Error::Parity)
+    } else if dr & (1 << 11) != 0 {
+        Some(Error::Overrun)
+    } else {
+        None
+    }
+}
+
+macro_rules! pl011 {
+    ($($UARTX:ident: ($uartX:ident, $uartX_with_config:ident),)+) => {
+        $(
+            impl<PINS> Serial<$UARTX, PINS> {
+                pub fn $uartX(uart: $UARTX, pins: PINS, baud_rate: Bps, clocks: Clocks) -> Self
+                where
+                    PINS: Pins<$UARTX>,
+                {
+                    let config = Config::default().baudrate(baud_rate);
+                    Self::$uartX_with_config(uart, pins, config, clocks)
+                }
+
+                /// The PL011 supports all of the `Config` options
+                ///
+                /// RTS/CTS flow control is enabled if `PINS` includes them.
+                pub fn $uartX_with_config(
+                    mut uart: $UARTX,
+                    pins: PINS,
+                    config: Config,
+                    clocks: Clocks,
+                ) -> Self
+                where
+                    PINS: Pins<$UARTX>,
+                {
+                    use bcm2711::uart0::*;
+                    let baud_rate = config.baudrate;
+                    let brr = if baud_rate.0 > (clocks.uart().0 / 16) {
+                        (clocks.uart().0 * 8) / baud_rate.0
+                    } else {
+                        (clocks.uart().0 * 4) / baud_rate.0
+                    };
+
+                    // Turn off the UART
+                    uart.cr.modify(
+                        Control::Enable::Clear + Control::TxEnable::Clear + Control::RxEnable::Clear,
+                    );
+
+                    uart.imsc.write(0);
+                    uart.icr.modify(IntClear::All::Clear);
+                    uart.ibrd
+                        .modify(IntegerBaudRateDivisor::Ibrd::Field::new(brr >> 6).unwrap());
+                    uart.fbrd.modify(
+                        FractionalBaudRateDivisor::Fbrd::Field::new(brr & 0x3F).unwrap(),
+                    );
+
+                    // Line control must be written after the divisors
+                    let word_length = match config.wordlength {
+                        WordLength::DataBits5 => LineControl::WordLength::FiveBit,
+                        WordLength::DataBits6 => LineControl::WordLength::SixBit,
+                        WordLength::DataBits7 => LineControl::WordLength::SevenBit,
+                        WordLength::DataBits8 => LineControl::WordLength::EightBit,
+                    };
+                    let (parity_en, even_parity) = match config.parity {
+                        Parity::ParityNone => (false, false),
+                        Parity::ParityEven => (true, true),
+                        Parity::ParityOdd => (true, false),
+                    };
+                    let two_stop_bits = config.stopbits == StopBits::STOP2;
+                    uart.lcrh.modify(
+                        word_length
+                            + LineControl::ParityEnable::Field::new(parity_en as _).unwrap()
+                            + LineControl::EvenParity::Field::new(even_parity as _).unwrap()
+                            + LineControl::TwoStopBits::Field::new(two_stop_bits as _).unwrap()
+                            + LineControl::FifoEnable::Field::new(config.fifo as _).unwrap(),
+                    );
+                    uart.ifls
+                        .modify(FifoLevel::TxLevel::OneHalf + FifoLevel::RxLevel::OneHalf);
+
+                    uart.cr.modify(
+                        Control::LoopbackEnable::Field::new(config.loopback as _).unwrap()
+                            + Control::RtsEnable::Field::new(PINS::FLOW_CONTROL as _).unwrap()
+                            + Control::CtsEnable::Field::new(PINS::FLOW_CONTROL as _).unwrap(),
+                    );
+                    uart.cr.modify(
+                        Control::Enable::Set + Control::TxEnable::Set + Control::RxEnable::Set,
+                    );
+
+                    Serial { uart, pins }
+                }
+
+                /// Sets the FIFO levels at which the Rx and Tx interrupts are raised
+                pub fn set_fifo_thresholds(&mut self, rx: FifoThreshold, tx: FifoThreshold) {
+                    use bcm2711::uart0::FifoLevel;
+                    let rx = match rx {
+                        FifoThreshold::OneEighth => FifoLevel::RxLevel::OneEighth,
+                        FifoThreshold::OneQuarter => FifoLevel::RxLevel::OneQuarter,
+                        FifoThreshold::OneHalf => FifoLevel::RxLevel::OneHalf,
+                        FifoThreshold::ThreeQuarters => FifoLevel::RxLevel::ThreeQuarters,
+                        FifoThreshold::SevenEighths => FifoLevel::RxLevel::SevenEighths,
+                    };
+                    let tx = match tx {
+                        FifoThreshold::OneEighth => FifoLevel::TxLevel::OneEighth,
+                        FifoThreshold::OneQuarter => FifoLevel::TxLevel::OneQuarter,
+                        FifoThreshold::OneHalf => FifoLevel::TxLevel::OneHalf,
+                        FifoThreshold::ThreeQuarters => FifoLevel::TxLevel::ThreeQuarters,
+                        FifoThreshold::SevenEighths => FifoLevel::TxLevel::SevenEighths,
+                    };
+                    self.uart.ifls.modify(rx + tx);
+                }
+
+                /// Enables the interrupt for the given `event`, the UART interrupt
+                /// must also be enabled in the `gic`
+                pub fn listen(&mut self, event: Event) {
+                    use bcm2711::uart0::Int;
+                    match event {
+                        Event::Rxne => self.uart.imsc.modify(Int::Rx::Set + Int::RxTimeout::Set),
+                        Event::Txe => self.uart.imsc.modify(Int::Tx::Set),
+                        Event::Error => self.uart.imsc.modify(
+                            Int::FramingError::Set
+                                + Int::ParityError::Set
+                                + Int::BreakError::Set
+                                + Int::OverrunError::Set,
+                        ),
+                    }
+                }
+
+                pub fn unlisten(&mut self, event: Event) {
+                    use bcm2711::uart0::Int;
+                    match event {
+                        Event::Rxne => self
+                            .uart
+                            .imsc
+                            .modify(Int::Rx::Clear + Int::RxTimeout::Clear),
+                        Event::Txe => self.uart.imsc.modify(Int::Tx::Clear),
+                        Event::Error => self.uart.imsc.modify(
+                            Int::FramingError::Clear
+                                + Int::ParityError::Clear
+                                + Int::BreakError::Clear
+                                + Int::OverrunError::Clear,
+                        ),
+                    }
+                }
+
+                pub fn free(mut self) -> ($UARTX, PINS) {
+                    self.uart.imsc.write(0);
+                    (self.uart, self.pins)
+                }
+            }
+
+            impl<PINS> serial::Read<u8> for Serial<$UARTX, PINS> {
+                type Error = Error;
+
+                fn read(&mut self) -> nb::Result<u8, Error> {
+                    use bcm2711::uart0::Flag;
+                    if self.uart.fr.is_set(Flag::RxEmpty::Read) {
+                        return Err(nb::Error::WouldBlock);
+                    }
+
+                    // Data and its error bits are read together
+                    let dr = self.uart.dr.read();
+                    match pl011_error(dr) {
+                        Some(err) => Err(nb::Error::Other(err)),
+                        None => Ok(dr as u8),
+                    }
+                }
+            }
+
+            impl<PINS> serial::Write<u8> for Serial<$UARTX, PINS> {
+                type Error = Void;
+
+                fn flush(&mut self) -> nb::Result<(), Void> {
+                    use bcm2711::uart0::Flag;
+                    if self.uart.fr.is_set(Flag::TxEmpty::Read)
+                        && !self.uart.fr.is_set(Flag::Busy::Read)
+                    {
+                        Ok(())
+                    } else {
+                        Err(nb::Error::WouldBlock)
+                    }
+                }
+
+                fn write(&mut self, byte: u8) -> nb::Result<(), Void> {
+                    use bcm2711::uart0::{Data, Flag};
+                    if !self.uart.fr.is_set(Flag::TxFull::Read) {
+                        self.uart
+                            .dr
+                            .modify(Data::Data::Field::new(byte as _).unwrap());
+                        Ok(())
+                    } else {
+                        Err(nb::Error::WouldBlock)
+                    }
+                }
+            }
+
+            impl<PINS> fmt::Write for Serial<$UARTX, PINS> {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    for b in s.bytes() {
+                        // Convert '\n' to '\r\n'
+                        if b as char == '\n' {
+                            block!(self.write('\r' as _)).ok();
+                        }
+                        block!(self.write(b)).ok();
+                    }
+                    Ok(())
+                }
+            }
+        )+
+    }
+}
+
+pl011! {
+    UART0: (uart0, uart0_with_config),
+    UART2: (uart2, uart2_with_config),
+    UART3: (uart3, uart3_with_config),
+    UART4: (uart4, uart4_with_config),
+    UART5: (uart5, uart5_with_config),
+}
+
+impl<PINS> Serial<UART1, PINS> {
+    pub fn uart1(uart: UART1, pins: PINS, baud_rate: Bps, clocks: Clocks) -> Self
+    where
+        PINS: Pins<UART1>,
+    {
+        Self::uart1_with_config(uart, pins, Config::default().baudrate(baud_rate), clocks).unwrap()
+    }
+
+    /// The mini UART only supports 7 or 8 data bits, no parity, 1 stop bit,
+    /// and always has its FIFOs enabled
+    ///
+    /// RTS/CTS flow control is enabled if `PINS` includes them.
+    pub fn uart1_with_config(
+        mut uart: UART1,
+        pins: PINS,
+        config: Config,
+        clocks: Clocks,
+    ) -> Result<Self, InvalidConfig>
+    where
+        PINS: Pins<UART1>,
+    {
+        use bcm2711::uart1::*;
+        let data_size = match config.wordlength {
+            WordLength::DataBits7 => LineControl::DataSize::SevenBit,
+            WordLength::DataBits8 => LineControl::DataSize::EightBit,
+            _ => return Err(InvalidConfig),
+        };
+        if config.parity != Parity::ParityNone
+            || config.stopbits != StopBits::STOP1
+            || !config.fifo
+            || config.loopback
+        {
+            return Err(InvalidConfig);
+        }
+
+        // Mini UART uses 8-times oversampling
+        // baudrate_reg = ((sys_clock / baudrate) / 8) - 1
+        let brr = ((clocks.core().0 / config.baudrate.0) / 8) - 1;
+
+        uart.enable.modify(AuxEnable::MiniUartEnable::Set);
+        uart.ier
+            .modify(IntEnable::IntRx::Clear + IntEnable::IntTx::Clear);
+        uart.cntl
+            .modify(Control::RxEnable::Clear + Control::TxEnable::Clear);
+        uart.lcr.modify(data_size);
+        uart.mcr.modify(ModemControl::Rts::Clear);
+        uart.ier
+            .modify(IntEnable::IntRx::Clear + IntEnable::IntTx::Clear);
+        uart.iir.modify(IntIdentify::FifoClear::All);
+        uart.baudrate
+            .modify(Baudrate::Rate::Field::new(brr).unwrap());
+
+        uart.cntl.modify(
+            Control::RtsFlowEnable::Field::new(PINS::FLOW_CONTROL as _).unwrap()
+                + Control::CtsFlowEnable::Field::new(PINS::FLOW_CONTROL as _).unwrap(),
+        );
+        uart.cntl
+            .modify(Control::RxEnable::Set + Control::TxEnable::Set);
+
+        Ok(Serial { uart, pins })
+    }
+
+    /// Enables the interrupt for the given `event`, the AUX interrupt
+    /// must also be enabled in the `gic`
+    ///
+    /// The mini UART has no error interrupt, overruns are reported on read.
+    pub fn listen(&mut self, event: Event) {
+        use bcm2711::uart1::IntEnable;
+        match event {
+            Event::Rxne => self.uart.ier.modify(
+                IntEnable::IntRx::Set + IntEnable::IntLineStatus::Field::new(0b11).unwrap(),
+            ),
+            Event::Txe => self.uart.ier.modify(IntEnable::IntTx::Set),
+            Event::Error => (),
+        }
+    }
+
+    pub fn unlisten(&mut self, event: Event) {
+        use bcm2711::uart1::IntEnable;
+        match event {
+            Event::Rxne => self.uart.ier.modify(IntEnable::IntRx::Clear),
+            Event::Txe => self.uart.ier.modify(IntEnable::IntTx::Clear),
+            Event::Error => (),
+        }
+    }
+
+    pub fn free(mut self) -> (UART1, PINS) {
+        self.uart.ier.write(0);
+        (self.uart, self.pins)
+    }
+}
+
+impl<PINS> serial::Read<u8> for Serial<UART1, PINS> {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        use bcm2711::uart1::Data;
+        let lsr = self.uart.lsr.read();
+        if lsr & LSR_RX_OVERRUN != 0 {
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+
+        if lsr & LSR_DATA_READY != 0 {
+            let mut data = self.uart.io.get_field(Data::Data::Read).unwrap().val() as u8;
+
+            // convert carrige return to newline
+            if data == '\r' as _ {
+                data = '\n' as _;
+            }
+
+            Ok(data)
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<PINS> serial::Write<u8> for Serial<UART1, PINS> {
+    type Error = Void;
+
+    fn flush(&mut self) -> nb::Result<(), Void> {
+        use bcm2711::uart1::LineStatus;
+        if self.uart.lsr.is_set(LineStatus::TxIdle::Read) {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Void> {
+        use bcm2711::uart1::{Data, LineStatus};
+        if self.uart.lsr.is_set(LineStatus::TxEmpty::Read) {
+            self.uart
+                .io
+                .modify(Data::Data::Field::new(byte as _).unwrap());
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<PINS> core::fmt::Write for Serial<UART1, PINS> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for b in s.bytes() {
+            // Convert '\n' to '\r\n'
+            if b as char == '\n' {
+                block!(self.write('\r' as _)).ok();
+            }
+            block!(self.write(b)).ok();
+        }
+        Ok(())
+    }
+}
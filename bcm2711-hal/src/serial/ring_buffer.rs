@@ -0,0 +1,147 @@
+//! Single producer, single consumer byte ring buffer
+//!
+//! Shared between the IRQ handler and the `BufferedSerial` owner, the
+//! storage is provided by the caller.
+//! One slot is kept free to distinguish full from empty.
+
+use core::ptr;
+use core::slice;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+pub(crate) struct RingBuffer {
+    buffer: AtomicPtr<u8>,
+    size: AtomicUsize,
+    /// Next slot to write, only modified by the producer
+    head: AtomicUsize,
+    /// Next slot to read, only modified by the consumer
+    tail: AtomicUsize,
+}
+
+impl RingBuffer {
+    pub const fn new() -> Self {
+        RingBuffer {
+            buffer: AtomicPtr::new(ptr::null_mut()),
+            size: AtomicUsize::new(0),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Must not be called while the producer or consumer is active
+    pub fn attach(&self, buffer: &'static mut [u8]) {
+        self.head.store(0, Ordering::Relaxed);
+        self.tail.store(0, Ordering::Relaxed);
+        self.size.store(buffer.len(), Ordering::Relaxed);
+        self.buffer.store(buffer.as_mut_ptr(), Ordering::Release);
+    }
+
+    /// Must not be called while the producer or consumer is active
+    pub fn detach(&self) -> &'static mut [u8] {
+        let buffer = self.buffer.swap(ptr::null_mut(), Ordering::Acquire);
+        let size = self.size.swap(0, Ordering::Relaxed);
+        self.head.store(0, Ordering::Relaxed);
+        self.tail.store(0, Ordering::Relaxed);
+        if buffer.is_null() {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(buffer, size) }
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.size.load(Ordering::Relaxed).saturating_sub(1)
+    }
+
+    pub fn len(&self) -> usize {
+        let size = self.size.load(Ordering::Relaxed);
+        if size == 0 {
+            return 0;
+        }
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (head + size - tail) % size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Producer side, gives the byte back if the buffer is full
+    pub fn push(&self, byte: u8) -> Result<(), u8> {
+        let buffer = self.buffer.load(Ordering::Acquire);
+        let size = self.size.load(Ordering::Relaxed);
+        if buffer.is_null() || size < 2 {
+            return Err(byte);
+        }
+
+        let head = self.head.load(Ordering::Relaxed);
+        let next = (head + 1) % size;
+        if next == self.tail.load(Ordering::Acquire) {
+            return Err(byte);
+        }
+
+        unsafe { ptr::write_volatile(buffer.add(head), byte) };
+        self.head.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// Consumer side
+    pub fn pop(&self) -> Option<u8> {
+        let buffer = self.buffer.load(Ordering::Acquire);
+        let size = self.size.load(Ordering::Relaxed);
+        if buffer.is_null() || size == 0 {
+            return None;
+        }
+
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let byte = unsafe { ptr::read_volatile(buffer.add(tail)) };
+        self.tail.store((tail + 1) % size, Ordering::Release);
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        static mut STORAGE: [u8; 4] = [0; 4];
+        let rb = RingBuffer::new();
+        assert_eq!(rb.pop(), None);
+        assert_eq!(rb.push(1), Err(1));
+
+        rb.attach(unsafe { &mut STORAGE });
+        assert_eq!(rb.capacity(), 3);
+        assert!(rb.is_empty());
+
+        for i in 0..10 {
+            assert_eq!(rb.push(i), Ok(()));
+            assert_eq!(rb.push(i + 1), Ok(()));
+            assert_eq!(rb.len(), 2);
+            assert_eq!(rb.pop(), Some(i));
+            assert_eq!(rb.pop(), Some(i + 1));
+            assert_eq!(rb.pop(), None);
+        }
+
+        assert_eq!(rb.push(1), Ok(()));
+        assert_eq!(rb.push(2), Ok(()));
+        assert_eq!(rb.push(3), Ok(()));
+        assert!(rb.is_full());
+        assert_eq!(rb.push(4), Err(4));
+        assert_eq!(rb.pop(), Some(1));
+        assert_eq!(rb.push(4), Ok(()));
+
+        assert_eq!(rb.detach().len(), 4);
+        assert_eq!(rb.pop(), None);
+        assert_eq!(rb.len(), 0);
+    }
+}
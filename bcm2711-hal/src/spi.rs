@@ -2,22 +2,35 @@
 //!
 //! TODO - update this once bcm2711 docs are available
 //!
-//! - Only implemented for SPI0 because the other two are different
-//! - Only supports chip select 0 and 1 pins
+//! - SPI0 only supports software driven chip selects
+//! - SPI0 transfers can be DMA-backed, see `Spi::with_dma`
+//! - SPI1 and SPI2 are the auxiliary SPI masters, they have a different
+//!   register block but the same interface
 
 // TODO - add events/etc
 
+use crate::cache;
 use crate::clocks::Clocks;
-use crate::gpio::{Alternate, Pin10, Pin11, Pin9, AF0};
+use crate::dma;
+use crate::gpio::{
+    Alternate, Pin10, Pin11, Pin16, Pin17, Pin18, Pin19, Pin20, Pin21, Pin40, Pin41, Pin42, Pin43,
+    Pin44, Pin45, Pin9, AF0, AF4,
+};
 use crate::hal::spi::{self, Mode, Phase, Polarity};
 use crate::time::Hertz;
 use bcm2711::spi0::*;
+use bcm2711::spi1::{self, SPI1};
+use bcm2711::spi2::SPI2;
+use bcm2711::uart1::{AuxEnable, UART1};
+use core::ops::DerefMut;
 use nb::block;
 use typenum::consts::U0;
 
 /// SPI error
 #[derive(Debug)]
 pub enum Error {
+    /// A DMA channel reported an error
+    Dma,
     #[doc(hidden)]
     _Extensible,
 }
@@ -83,6 +96,44 @@ pins! {
         MOSI: [
             Pin10<Alternate<AF0>>
         ]
+    SPI1:
+        CS: [
+            NoCs,
+            Pin18<Alternate<AF4>>,
+            Pin17<Alternate<AF4>>,
+            Pin16<Alternate<AF4>>,
+            (Pin18<Alternate<AF4>>, Pin17<Alternate<AF4>>),
+            (Pin18<Alternate<AF4>>, Pin17<Alternate<AF4>>, Pin16<Alternate<AF4>>)
+        ]
+        SCK: [
+            Pin21<Alternate<AF4>>
+        ]
+        MISO: [
+            NoMiso,
+            Pin19<Alternate<AF4>>
+        ]
+        MOSI: [
+            Pin20<Alternate<AF4>>
+        ]
+    SPI2:
+        CS: [
+            NoCs,
+            Pin43<Alternate<AF4>>,
+            Pin44<Alternate<AF4>>,
+            Pin45<Alternate<AF4>>,
+            (Pin43<Alternate<AF4>>, Pin44<Alternate<AF4>>),
+            (Pin43<Alternate<AF4>>, Pin44<Alternate<AF4>>, Pin45<Alternate<AF4>>)
+        ]
+        SCK: [
+            Pin42<Alternate<AF4>>
+        ]
+        MISO: [
+            NoMiso,
+            Pin40<Alternate<AF4>>
+        ]
+        MOSI: [
+            Pin41<Alternate<AF4>>
+        ]
 }
 
 /// Interrupt events
@@ -251,3 +302,489 @@ impl<PINS> crate::hal::blocking::spi::Write<u8> for Spi<SPI0, PINS> {
         Ok(())
     }
 }
+
+/// SPI0 can't transfer more than DLEN bytes at once
+const DMA_TRANSFER_MAX: usize = 0xFFFF;
+
+/// Number of control blocks `SpiDma` needs, one for TX and one for RX
+pub const SPI_DMA_CONTROL_BLOCKS: usize = 2;
+
+impl<PINS> Spi<SPI0, PINS> {
+    /// Uses a pair of DMA channels for transfers, paced by the SPI TX and RX
+    /// DREQs.
+    ///
+    /// The channels must already be enabled.
+    pub fn with_dma<'a>(
+        self,
+        tx_chan: dma::Channel,
+        rx_chan: dma::Channel,
+        dcbs: &'a mut [dma::ControlBlock; SPI_DMA_CONTROL_BLOCKS],
+    ) -> SpiDma<'a, PINS> {
+        SpiDma {
+            spi: self,
+            tx_chan,
+            rx_chan,
+            dcbs,
+        }
+    }
+}
+
+/// SPI0 with DMA-backed blocking transfers
+pub struct SpiDma<'a, PINS> {
+    spi: Spi<SPI0, PINS>,
+    tx_chan: dma::Channel,
+    rx_chan: dma::Channel,
+    dcbs: &'a mut [dma::ControlBlock; SPI_DMA_CONTROL_BLOCKS],
+}
+
+impl<'a, PINS> SpiDma<'a, PINS> {
+    pub fn free(
+        self,
+    ) -> (
+        Spi<SPI0, PINS>,
+        dma::Channel,
+        dma::Channel,
+        &'a mut [dma::ControlBlock; SPI_DMA_CONTROL_BLOCKS],
+    ) {
+        (self.spi, self.tx_chan, self.rx_chan, self.dcbs)
+    }
+
+    /// Shifts out `tx` while shifting into `rx`, they must be the same length
+    /// and may be the same buffer.
+    /// A `None` `rx` discards the received data.
+    fn transfer_dma(
+        &mut self,
+        tx: *const u8,
+        rx: Option<*mut u8>,
+        len: usize,
+    ) -> Result<(), Error> {
+        let mut offset = 0;
+        while offset < len {
+            let size = (len - offset).min(DMA_TRANSFER_MAX);
+            let rx_chunk = rx.map(|p| unsafe { p.add(offset) });
+            self.transfer_chunk(unsafe { tx.add(offset) }, rx_chunk, size)?;
+            offset += size;
+        }
+        Ok(())
+    }
+
+    fn transfer_chunk(
+        &mut self,
+        tx: *const u8,
+        rx: Option<*mut u8>,
+        len: usize,
+    ) -> Result<(), Error> {
+        let fifo = &self.spi.spi.fifo as *const _ as usize;
+        let (tx_dcb, rx_dcb) = self.dcbs.split_at_mut(1);
+        let tx_dcb = &mut tx_dcb[0];
+        let rx_dcb = &mut rx_dcb[0];
+
+        // Memory -> FIFO, paced by the TX DREQ
+        tx_dcb.init();
+        tx_dcb.set_src(tx as u32);
+        tx_dcb.set_dest_periph(fifo, dma::Dreq::Spi0Tx);
+        tx_dcb.set_length(dma::TransferLength::ModeLinear(len as u32));
+        tx_dcb.info.set_src_inc(true);
+        tx_dcb.info.set_wait_resp(true);
+
+        // FIFO -> memory, paced by the RX DREQ
+        rx_dcb.init();
+        rx_dcb.set_src_periph(fifo, dma::Dreq::Spi0Rx);
+        match rx {
+            Some(rx) => {
+                rx_dcb.set_dest(rx as u32);
+                rx_dcb.info.set_dest_inc(true);
+            }
+            None => {
+                // Still drain the RX FIFO, otherwise the transfer stalls
+                rx_dcb.set_dest(tx as u32);
+                rx_dcb.info.set_dest_ignore(true);
+            }
+        }
+        rx_dcb.set_length(dma::TransferLength::ModeLinear(len as u32));
+        rx_dcb.info.set_wait_resp(true);
+
+        let tx_buffer = unsafe { core::slice::from_raw_parts(tx, len) };
+        let rx_buffer: &mut [u8] = match rx {
+            Some(rx) => unsafe { core::slice::from_raw_parts_mut(rx, len) },
+            None => &mut [],
+        };
+
+        self.spi.spi.cs.modify(
+            ControlStatus::FifoClear::ClearTxRx
+                + ControlStatus::DmaEnable::Set
+                + ControlStatus::AutoDeassert::Set,
+        );
+        self.spi
+            .spi
+            .data_len
+            .modify(DataLength::Len::Field::new(len as u32).unwrap());
+        self.spi.spi.cs.modify(ControlStatus::TransferActive::Set);
+
+        // Start RX first so nothing is missed.
+        // Only the memory side gets cache maintenance, the FIFO address
+        // isn't cacheable
+        self.rx_chan.start(&dma::TransferResources {
+            src_cached: false,
+            dest_cached: rx.is_some(),
+            dcb: rx_dcb,
+            src_buffer: &[],
+            dest_buffer: rx_buffer,
+        });
+        self.tx_chan.start(&dma::TransferResources {
+            src_cached: true,
+            dest_cached: false,
+            dcb: tx_dcb,
+            src_buffer: tx_buffer,
+            dest_buffer: &mut [],
+        });
+
+        self.tx_chan.wait();
+        self.rx_chan.wait();
+
+        self.spi.spi.cs.modify(
+            ControlStatus::TransferActive::Clear
+                + ControlStatus::DmaEnable::Clear
+                + ControlStatus::AutoDeassert::Clear,
+        );
+        self.spi
+            .spi
+            .data_len
+            .modify(DataLength::Len::Field::checked::<U0>());
+
+        // Drop any lines speculatively loaded while the DMA was running
+        if let Some(rx) = rx {
+            unsafe {
+                cache::invalidate_data_cache_range(rx as usize, len);
+            }
+        }
+
+        if self.tx_chan.errors() || self.rx_chan.errors() {
+            Err(Error::Dma)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a, PINS> crate::hal::blocking::spi::Transfer<u8> for SpiDma<'a, PINS> {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Error> {
+        let len = words.len();
+        let ptr = words.as_mut_ptr();
+        self.transfer_dma(ptr, Some(ptr), len)?;
+        Ok(words)
+    }
+}
+
+impl<'a, PINS> crate::hal::blocking::spi::Write<u8> for SpiDma<'a, PINS> {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        self.transfer_dma(words.as_ptr(), None, words.len())
+    }
+}
+
+/// Auxiliary SPI FIFO depth, in entries
+const AUX_FIFO_SIZE: usize = 4;
+
+/// Maximum shift length of an auxiliary SPI FIFO entry
+const AUX_MAX_BITS: usize = 24;
+
+/// Auxiliary SPI hardware chip selects
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ChipSelect {
+    Cs0,
+    Cs1,
+    Cs2,
+    /// Chip selects are driven by software
+    None,
+}
+
+impl ChipSelect {
+    /// Active low chip select pattern
+    fn pattern(self) -> u32 {
+        match self {
+            ChipSelect::Cs0 => 0b110,
+            ChipSelect::Cs1 => 0b101,
+            ChipSelect::Cs2 => 0b011,
+            ChipSelect::None => 0b111,
+        }
+    }
+}
+
+/// Auxiliary SPI abstraction
+///
+/// Every FIFO entry carries its own shift length (variable width mode),
+/// byte transfers are packed 3 bytes per entry.
+#[derive(Debug)]
+pub struct AuxSpi<SPI, PINS> {
+    spi: SPI,
+    pins: PINS,
+}
+
+macro_rules! aux_hal {
+    ($($SPIX:ident: ($spiX:ident, $AuxEnableX:ident),)+) => {
+        $(
+            impl<PINS> AuxSpi<$SPIX, PINS> {
+                pub fn $spiX(
+                    spi: $SPIX,
+                    pins: PINS,
+                    mode: Mode,
+                    freq: Hertz,
+                    clocks: Clocks,
+                ) -> Self
+                where
+                    PINS: Pins<$SPIX>,
+                {
+                    // AUX_ENABLES is shared with the mini UART
+                    let mut aux = UART1::new();
+                    aux.enable.modify(AuxEnable::$AuxEnableX::Set);
+
+                    let mut spi = AuxSpi { spi, pins };
+                    spi.init(mode, freq, clocks);
+                    spi
+                }
+
+                pub fn free(self) -> ($SPIX, PINS) {
+                    let mut aux = UART1::new();
+                    aux.enable.modify(AuxEnable::$AuxEnableX::Clear);
+                    (self.spi, self.pins)
+                }
+            }
+        )+
+    }
+}
+
+aux_hal! {
+    SPI1: (spi1, Spi1Enable),
+    SPI2: (spi2, Spi2Enable),
+}
+
+impl<SPI, PINS> AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    fn init(&mut self, mode: Mode, freq: Hertz, clocks: Clocks) {
+        use bcm2711::spi1::{Control0, Control1};
+
+        // Disable, hold the FIFOs in reset while configuring
+        self.spi
+            .cntl0
+            .modify(Control0::Enable::Clear + Control0::ClearFifos::Set);
+
+        // SPI clock = core_clk / (2 * (speed + 1))
+        let speed = if freq.0 > 0 {
+            let div = (clocks.core().0 + (2 * freq.0) - 1) / (2 * freq.0);
+            div.max(1).min(4096) - 1
+        } else {
+            4095
+        };
+
+        // Data is sampled on the leading edge when CPOL == CPHA, the
+        // inverted clock is accounted for by the hardware
+        let idle_high = mode.polarity == Polarity::IdleHigh;
+        let second_edge = mode.phase == Phase::CaptureOnSecondTransition;
+        let in_rising = idle_high == second_edge;
+
+        self.spi.cntl0.modify(
+            Control0::Speed::Field::new(speed).unwrap()
+                + Control0::ChipSelects::Field::new(ChipSelect::None.pattern()).unwrap()
+                + Control0::InvertClock::Field::new(idle_high as _).unwrap()
+                + Control0::InRising::Field::new(in_rising as _).unwrap()
+                + Control0::OutRising::Field::new((!in_rising) as _).unwrap()
+                + Control0::ShiftOutMsbFirst::Set
+                + Control0::VariableWidth::Set
+                + Control0::VariableCs::Clear
+                + Control0::PostInputMode::Clear
+                + Control0::DoutHoldTime::Cycles0,
+        );
+        self.spi
+            .cntl1
+            .modify(Control1::ShiftInMsbFirst::Set + Control1::KeepInput::Clear);
+        self.spi.cntl1.modify(
+            Control1::DoneIrq::Clear
+                + Control1::TxEmptyIrq::Clear
+                + Control1::CsHighTime::Field::new(0).unwrap(),
+        );
+
+        self.spi
+            .cntl0
+            .modify(Control0::ClearFifos::Clear + Control0::Enable::Set);
+    }
+
+    /// Selects which hardware chip select is asserted during transfers
+    pub fn set_chip_select(&mut self, cs: ChipSelect) {
+        use bcm2711::spi1::Control0;
+        self.spi
+            .cntl0
+            .modify(Control0::ChipSelects::Field::new(cs.pattern()).unwrap());
+    }
+
+    #[inline]
+    fn tx_entry(&mut self, bits: usize, data: u32, hold_cs: bool) -> nb::Result<(), Error> {
+        use bcm2711::spi1::Stat;
+        if self.spi.stat.is_set(Stat::TxFull::Read) {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // Left aligned at bit 23, shift length in bits [28:24]
+        let entry = (data << (AUX_MAX_BITS - bits)) | ((bits as u32) << 24);
+        if hold_cs {
+            self.spi.txhold[0].write(entry);
+        } else {
+            self.spi.io[0].write(entry);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn rx_entry(&mut self, bits: usize) -> nb::Result<u32, Error> {
+        use bcm2711::spi1::Stat;
+        if self.spi.stat.is_set(Stat::RxEmpty::Read) {
+            Err(nb::Error::WouldBlock)
+        } else {
+            // Shifted in at bit 0
+            Ok(self.spi.io[0].read() & ((1 << bits) - 1))
+        }
+    }
+
+    /// Shifts out `len` bytes from `tx`, packing up to 3 bytes per FIFO
+    /// entry, and stores the bytes received to `rx` if given.
+    ///
+    /// `tx` and `rx` may be the same buffer, an entry is always queued
+    /// before its reply is stored.
+    /// The chip select stays asserted for the whole transfer.
+    fn transfer_bytes(
+        &mut self,
+        tx: *const u8,
+        rx: Option<*mut u8>,
+        len: usize,
+    ) -> Result<(), Error> {
+        const BYTES: usize = AUX_MAX_BITS / 8;
+        let entries = (len + BYTES - 1) / BYTES;
+
+        let mut tx_entries = 0;
+        let mut rx_entries = 0;
+        while rx_entries < entries {
+            // Keep the FIFO full
+            while tx_entries < entries && (tx_entries - rx_entries) < AUX_FIFO_SIZE {
+                let start = tx_entries * BYTES;
+                let end = len.min(start + BYTES);
+                let data = (start..end).fold(0_u32, |acc, i| {
+                    (acc << 8) | u32::from(unsafe { *tx.add(i) })
+                });
+                let last = tx_entries + 1 == entries;
+                match self.tx_entry((end - start) * 8, data, !last) {
+                    Ok(()) => tx_entries += 1,
+                    Err(nb::Error::WouldBlock) => break,
+                    Err(nb::Error::Other(e)) => return Err(e),
+                }
+            }
+
+            let start = rx_entries * BYTES;
+            let end = len.min(start + BYTES);
+            let data = block!(self.rx_entry((end - start) * 8))?;
+            if let Some(rx) = rx {
+                for (i, offset) in (start..end).rev().enumerate() {
+                    unsafe { *rx.add(offset) = (data >> (8 * i)) as u8 };
+                }
+            }
+            rx_entries += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Every word is a transfer of its own, the chip select is released after
+/// each one. Use the blocking `Transfer`/`Write` impls for exchanges that
+/// need it held across several words.
+impl<SPI, PINS> spi::FullDuplex<u8> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        self.rx_entry(8).map(|w| w as u8)
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Error> {
+        self.tx_entry(8, u32::from(byte), false)
+    }
+}
+
+/// Single word transfers, see `FullDuplex<u8>`
+impl<SPI, PINS> spi::FullDuplex<u16> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u16, Error> {
+        self.rx_entry(16).map(|w| w as u16)
+    }
+
+    fn send(&mut self, word: u16) -> nb::Result<(), Error> {
+        self.tx_entry(16, u32::from(word), false)
+    }
+}
+
+impl<SPI, PINS> crate::hal::blocking::spi::Transfer<u8> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Error> {
+        let len = words.len();
+        let ptr = words.as_mut_ptr();
+        self.transfer_bytes(ptr, Some(ptr), len)?;
+        Ok(words)
+    }
+}
+
+impl<SPI, PINS> crate::hal::blocking::spi::Write<u8> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        self.transfer_bytes(words.as_ptr(), None, words.len())
+    }
+}
+
+impl<SPI, PINS> crate::hal::blocking::spi::Transfer<u16> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u16]) -> Result<&'w [u16], Error> {
+        let len = words.len();
+        for (i, word) in words.iter_mut().enumerate() {
+            block!(self.tx_entry(16, u32::from(*word), i + 1 != len))?;
+            *word = block!(self.rx_entry(16))? as u16;
+        }
+        Ok(words)
+    }
+}
+
+impl<SPI, PINS> crate::hal::blocking::spi::Write<u16> for AuxSpi<SPI, PINS>
+where
+    SPI: DerefMut<Target = spi1::RegisterBlock>,
+{
+    type Error = Error;
+
+    fn write(&mut self, words: &[u16]) -> Result<(), Error> {
+        let len = words.len();
+        for (i, word) in words.iter().enumerate() {
+            block!(self.tx_entry(16, u32::from(*word), i + 1 != len))?;
+            block!(self.rx_entry(16))?;
+        }
+        Ok(())
+    }
+}
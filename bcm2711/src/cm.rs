@@ -0,0 +1,99 @@
+//! Clock Manager
+//!
+//! Only the PWM clock is described.
+//! Every write must include the password.
+
+use crate::MMIO_BASE;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+pub const PADDR: usize = MMIO_BASE + 0x10_1000;
+
+register! {
+    Control,
+    u32,
+    RW,
+    Fields [
+        Source WIDTH(U4) OFFSET(U0) [
+            Gnd = U0,
+            /// 54 MHz crystal oscillator
+            Oscillator = U1,
+            TestDebug0 = U2,
+            TestDebug1 = U3,
+            PllA = U4,
+            PllC = U5,
+            /// 750 MHz PLLD peripheral clock
+            PllD = U6,
+            HdmiAux = U7
+        ],
+        Enable WIDTH(U1) OFFSET(U4) [],
+        /// Stop and reset the clock generator, glitches the output
+        Kill WIDTH(U1) OFFSET(U5) [],
+        /// The clock generator is running
+        Busy WIDTH(U1) OFFSET(U7) [],
+        /// Invert the generator output
+        Flip WIDTH(U1) OFFSET(U8) [],
+        /// MASH noise shaping, 0 is an integer divider
+        Mash WIDTH(U2) OFFSET(U9) [],
+        Password WIDTH(U8) OFFSET(U24) [
+            Password = U90
+        ],
+    ]
+}
+
+register! {
+    Divisor,
+    u32,
+    RW,
+    Fields [
+        /// Fractional part of the divisor
+        DivF WIDTH(U12) OFFSET(U0) [],
+        /// Integer part of the divisor
+        DivI WIDTH(U12) OFFSET(U12) [],
+        Password WIDTH(U8) OFFSET(U24) [
+            Password = U90
+        ],
+    ]
+}
+
+#[repr(C)]
+pub struct RegisterBlock {
+    __reserved_0: [u32; 40],        // 0x00
+    pub pwm_ctl: Control::Register, // 0xA0
+    pub pwm_div: Divisor::Register, // 0xA4
+}
+
+pub struct CM {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for CM {}
+
+impl CM {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        PADDR as *mut _
+    }
+}
+
+impl Deref for CM {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for CM {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
@@ -5,8 +5,16 @@ use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 
 /// Base address, each channel is offset by 0x100
+///
+/// Channels 0:6 are standard, 7:10 are LITE and 11:14 are DMA4 (40-bit)
 pub const PADDR: usize = MMIO_BASE + 0x0000_7000;
 
+/// Number of channels in the block at PADDR
+pub const NUM_CHANNELS: usize = 15;
+
+/// First DMA4 channel
+pub const DMA4_CHANNEL_START: usize = 11;
+
 /// Offset of the global interrupt status register
 pub const INT_STATUS_OFFSET: usize = 0xFE0;
 pub const INT_STATUS_PADDR: usize = PADDR + INT_STATUS_OFFSET;
@@ -15,6 +23,9 @@ pub const INT_STATUS_PADDR: usize = PADDR + INT_STATUS_OFFSET;
 pub const ENABLE_OFFSET: usize = 0xFF0;
 pub const ENABLE_PADDR: usize = PADDR + ENABLE_OFFSET;
 
+/// Offset between consecutive channels
+pub const CHANNEL_STRIDE: usize = 0x100;
+
 // TODO - make this an enum
 pub const CHANNEL0_OFFSET: usize = 0x000;
 pub const CHANNEL1_OFFSET: usize = 0x100;
@@ -209,6 +220,144 @@ register! {
     ]
 }
 
+register! {
+    /// DMA4 control and status
+    Dma4ControlStatus,
+    u32,
+    RW,
+    Fields [
+        Active WIDTH(U1) OFFSET(U0),
+        End WIDTH(U1) OFFSET(U1),
+        Int WIDTH(U1) OFFSET(U2),
+        DReq WIDTH(U1) OFFSET(U3),
+        ReadPaused WIDTH(U1) OFFSET(U4),
+        WritePaused WIDTH(U1) OFFSET(U5),
+        DReqPaused WIDTH(U1) OFFSET(U6),
+        WaitingForOutstandingWrites WIDTH(U1) OFFSET(U7),
+        Error WIDTH(U1) OFFSET(U10),
+        Qos WIDTH(U4) OFFSET(U16),
+        PanicQos WIDTH(U4) OFFSET(U20),
+        Busy WIDTH(U1) OFFSET(U24),
+        OutstandingTransactions WIDTH(U1) OFFSET(U25),
+        WaitForOutstandingWrites WIDTH(U1) OFFSET(U28),
+        DisDebug WIDTH(U1) OFFSET(U29),
+        Abort WIDTH(U1) OFFSET(U30),
+        Halt WIDTH(U1) OFFSET(U31),
+    ]
+}
+
+register! {
+    /// DMA4 control block address, bus address shifted right by 5
+    Dma4DcbAddr,
+    u32,
+    RW,
+    Fields [
+        Addr WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// DMA4 transfer information
+    Dma4TxfrInfo,
+    u32,
+    RO,
+    Fields [
+        IntEn WIDTH(U1) OFFSET(U0),
+        TdMode WIDTH(U1) OFFSET(U1),
+        WaitResp WIDTH(U1) OFFSET(U2),
+        WaitReadResp WIDTH(U1) OFFSET(U3),
+        PeriphMap WIDTH(U5) OFFSET(U9),
+        SrcDReq WIDTH(U1) OFFSET(U14),
+        DestDReq WIDTH(U1) OFFSET(U15),
+        SrcWaits WIDTH(U8) OFFSET(U16),
+        DestWaits WIDTH(U8) OFFSET(U24),
+    ]
+}
+
+register! {
+    /// DMA4 source/destination address bits 31:0
+    Dma4Addr,
+    u32,
+    RO,
+    Fields [
+        Addr WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// DMA4 source/destination information, AddrHigh is address bits 39:32
+    Dma4AddrInfo,
+    u32,
+    RO,
+    Fields [
+        AddrHigh WIDTH(U8) OFFSET(U0),
+        BurstLength WIDTH(U4) OFFSET(U8),
+        Inc WIDTH(U1) OFFSET(U12),
+        Size WIDTH(U2) OFFSET(U13) [
+            Width32 = U0,
+            Width64 = U1,
+            Width128 = U2,
+            Width256 = U3
+        ],
+        Ignore WIDTH(U1) OFFSET(U15),
+        Stride WIDTH(U16) OFFSET(U16),
+    ]
+}
+
+register! {
+    /// DMA4 transfer length
+    Dma4TxfrLen,
+    u32,
+    RO,
+    Fields [
+        XLen WIDTH(U16) OFFSET(U0),
+        YLen WIDTH(U14) OFFSET(U16),
+    ]
+}
+
+register! {
+    /// DMA4 next control block address, bus address shifted right by 5
+    Dma4NextDcbAddr,
+    u32,
+    RO,
+    Fields [
+        Addr WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// DMA4 debug
+    Dma4Debug,
+    u32,
+    RW,
+    Fields [
+        WriteError WIDTH(U1) OFFSET(U0),
+        FifoError WIDTH(U1) OFFSET(U1),
+        ReadError WIDTH(U1) OFFSET(U2),
+        ReadCbError WIDTH(U1) OFFSET(U3),
+        IntOnError WIDTH(U1) OFFSET(U8),
+        AbortOnError WIDTH(U1) OFFSET(U9),
+        HaltOnError WIDTH(U1) OFFSET(U10),
+        DisableClkGate WIDTH(U1) OFFSET(U11),
+        ReadState WIDTH(U4) OFFSET(U14),
+        WriteState WIDTH(U4) OFFSET(U18),
+        Reset WIDTH(U1) OFFSET(U23),
+        DmaId WIDTH(U4) OFFSET(U24),
+        Version WIDTH(U4) OFFSET(U28),
+    ]
+}
+
+register! {
+    /// DMA4 debug 2
+    Dma4Debug2,
+    u32,
+    RO,
+    Fields [
+        OutstandingWrites WIDTH(U9) OFFSET(U0),
+        OutstandingReads WIDTH(U8) OFFSET(U16),
+    ]
+}
+
 #[allow(non_snake_case)]
 #[repr(C)]
 pub struct GlobalIntStatusRegisterBlock {
@@ -234,6 +383,23 @@ pub struct RegisterBlock {
     pub debug: Debug::Register,               // 0x20
 }
 
+/// DMA4 channel registers
+#[repr(C)]
+pub struct Dma4RegisterBlock {
+    pub cs: Dma4ControlStatus::Register,          // 0x00
+    pub dcb_addr: Dma4DcbAddr::Register,          // 0x04
+    __reserved_0: u32,                            // 0x08
+    pub debug: Dma4Debug::Register,               // 0x0C
+    pub ti: Dma4TxfrInfo::Register,               // 0x10
+    pub src_addr: Dma4Addr::Register,             // 0x14
+    pub src_info: Dma4AddrInfo::Register,         // 0x18
+    pub dest_addr: Dma4Addr::Register,            // 0x1C
+    pub dest_info: Dma4AddrInfo::Register,        // 0x20
+    pub txfr_len: Dma4TxfrLen::Register,          // 0x24
+    pub next_dcb_addr: Dma4NextDcbAddr::Register, // 0x28
+    pub debug2: Dma4Debug2::Register,             // 0x2C
+}
+
 pub struct DMA {
     // Starts at PADDR (channel 0)
     paddr: *const usize,
@@ -264,6 +430,49 @@ impl DMA {
     }
 }
 
+/// A DMA4 channel, channels 11:14
+pub struct DMA4 {
+    paddr: *const usize,
+}
+
+unsafe impl Send for DMA4 {}
+
+impl DMA4 {
+    pub fn new() -> Self {
+        Self {
+            paddr: (PADDR + CHANNEL11_OFFSET) as *const _,
+        }
+    }
+
+    pub fn as_channel(self, offset: usize) -> Self {
+        assert!(offset >= CHANNEL11_OFFSET && offset <= CHANNEL14_OFFSET);
+        Self {
+            paddr: (PADDR + offset) as *const _,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const Dma4RegisterBlock {
+        self.paddr as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut Dma4RegisterBlock {
+        self.paddr as *mut _
+    }
+}
+
+impl Deref for DMA4 {
+    type Target = Dma4RegisterBlock;
+    fn deref(&self) -> &Dma4RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for DMA4 {
+    fn deref_mut(&mut self) -> &mut Dma4RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
+
 impl Deref for DMA {
     type Target = RegisterBlock;
     fn deref(&self) -> &RegisterBlock {
@@ -13,19 +13,21 @@ register! {
 
 pub const NUM_FILTERS: usize = 48;
 
+/// Words per filter, each word holds 2 bytes of the pattern
 pub const FILTER_SIZE: usize = 128;
 
-#[repr(C)]
-pub struct HardwareFilterBlock {
-    pub hfb0: Hfb::Register, // 0x00
-    pub hfb1: Hfb::Register, // 0x04
-    pub hfb2: Hfb::Register, // 0x08
-    pub hfb3: Hfb::Register, // 0x0C
-}
+/// Pattern byte at an even offset
+pub const WORD_EVEN_BYTE_SHIFT: usize = 8;
+/// Pattern byte at an odd offset
+pub const WORD_ODD_BYTE_SHIFT: usize = 0;
+/// Nibble enables of the even byte (high nibble is bit 19)
+pub const WORD_EVEN_MASK_SHIFT: usize = 18;
+/// Nibble enables of the odd byte (high nibble is bit 17)
+pub const WORD_ODD_MASK_SHIFT: usize = 16;
 
 #[repr(C)]
 pub struct RegisterBlock {
-    pub filter_blocks: [HardwareFilterBlock; NUM_FILTERS],
+    pub filters: [[Hfb::Register; FILTER_SIZE]; NUM_FILTERS], // 0x0000
 }
 
 pub struct HFB {
@@ -9,6 +9,7 @@ register! {
     RW,
     Fields [
         Bits WIDTH(U32) OFFSET(U0),
+        Enable WIDTH(U1) OFFSET(U0),
     ]
 }
 
@@ -23,16 +24,7 @@ register! {
 }
 
 register! {
-    UnknownCtrl,
-    u32,
-    RW,
-    Fields [
-        Bits WIDTH(U32) OFFSET(U0),
-    ]
-}
-
-register! {
-    /// v3+
+    /// v3+, 8 bits per filter, the length of the pattern in bytes
     FltLen,
     u32,
     RW,
@@ -41,13 +33,21 @@ register! {
     ]
 }
 
+/// Filters per `FltEnable` register
+pub const FLT_ENABLE_FILTERS: usize = 32;
+
+/// Filters per `FltLen` register
+pub const FLT_LEN_FILTERS: usize = 4;
+
+/// NOTE: both filter enable and length registers are in reverse order,
+/// `flt_enable[0]` holds filters 32..48 and `flt_enable[1]` filters 0..32,
+/// `flt_lens[0]` holds filters 44..48 and so on
 #[repr(C)]
 pub struct RegisterBlock {
-    pub ctrl: Ctrl::Register,                          // 0x00
-    pub flt_enable: FltEnable::Register,               // 0x04
-    pub unknown_ctrl: UnknownCtrl::Register,           // 0x08
-    __reserved_1: [u32; 4],                            // 0x0C
-    pub flt_lens: [FltLen::Register; NUM_FILTERS / 4], // 0x1C
+    pub ctrl: Ctrl::Register,                                        // 0x00
+    pub flt_enable: [FltEnable::Register; 2],                        // 0x04
+    __reserved_1: [u32; 4],                                          // 0x0C
+    pub flt_lens: [FltLen::Register; NUM_FILTERS / FLT_LEN_FILTERS], // 0x1C
 }
 
 pub struct HFBREGS {
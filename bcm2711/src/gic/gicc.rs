@@ -0,0 +1,118 @@
+//! GIC-400 CPU Interface
+
+use crate::gic::GICC_PADDR;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+register! {
+    /// CPU Interface Control
+    Control,
+    u32,
+    RW,
+    Fields [
+        EnableGrp0 WIDTH(U1) OFFSET(U0),
+        EnableGrp1 WIDTH(U1) OFFSET(U1),
+        FiqEn WIDTH(U1) OFFSET(U3),
+        EoiModeNs WIDTH(U1) OFFSET(U9),
+    ]
+}
+
+register! {
+    /// Interrupt Priority Mask
+    PriorityMask,
+    u32,
+    RW,
+    Fields [
+        Priority WIDTH(U8) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Binary Point
+    BinaryPoint,
+    u32,
+    RW,
+    Fields [
+        BinaryPoint WIDTH(U3) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Interrupt Acknowledge
+    ///
+    /// Also used for the Highest Priority Pending Interrupt register
+    IntAck,
+    u32,
+    RO,
+    Fields [
+        IntId WIDTH(U10) OFFSET(U0),
+        CpuId WIDTH(U3) OFFSET(U10),
+    ]
+}
+
+register! {
+    /// End of Interrupt
+    EndOfInt,
+    u32,
+    RW,
+    Fields [
+        IntId WIDTH(U10) OFFSET(U0),
+        CpuId WIDTH(U3) OFFSET(U10),
+    ]
+}
+
+register! {
+    /// Running Priority
+    RunningPriority,
+    u32,
+    RO,
+    Fields [
+        Priority WIDTH(U8) OFFSET(U0),
+    ]
+}
+
+#[repr(C)]
+pub struct RegisterBlock {
+    pub control: Control::Register,                  // 0x00
+    pub priority_mask: PriorityMask::Register,       // 0x04
+    pub binary_point: BinaryPoint::Register,         // 0x08
+    pub int_ack: IntAck::Register,                   // 0x0C
+    pub eoi: EndOfInt::Register,                     // 0x10
+    pub running_priority: RunningPriority::Register, // 0x14
+    pub pending: IntAck::Register,                   // 0x18
+}
+
+pub struct GICC {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for GICC {}
+
+impl GICC {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        GICC_PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        GICC_PADDR as *mut _
+    }
+}
+
+impl Deref for GICC {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for GICC {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
@@ -0,0 +1,160 @@
+//! GIC-400 Distributor
+
+use crate::gic::GICD_PADDR;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+register! {
+    /// Distributor Control
+    Control,
+    u32,
+    RW,
+    Fields [
+        EnableGrp0 WIDTH(U1) OFFSET(U0),
+        EnableGrp1 WIDTH(U1) OFFSET(U1),
+    ]
+}
+
+register! {
+    /// Interrupt Controller Type
+    Type,
+    u32,
+    RO,
+    Fields [
+        ItLinesNumber WIDTH(U5) OFFSET(U0),
+        CpuNumber WIDTH(U3) OFFSET(U5),
+        SecurityExtn WIDTH(U1) OFFSET(U10),
+        Lspi WIDTH(U5) OFFSET(U11),
+    ]
+}
+
+register! {
+    /// Distributor Implementer Identification
+    Iidr,
+    u32,
+    RO,
+    Fields [
+        Bits WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// One bit per interrupt, 32 interrupts per register
+    ///
+    /// Used for the group, set/clear enable, set/clear pending and
+    /// set/clear active registers
+    IntBits,
+    u32,
+    RW,
+    Fields [
+        Bits WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Interrupt Priority, 8 bits per interrupt, 4 interrupts per register
+    Priority,
+    u32,
+    RW,
+    Fields [
+        Bits WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Interrupt Processor Targets, 8 bits per interrupt,
+    /// 4 interrupts per register
+    Targets,
+    u32,
+    RW,
+    Fields [
+        Bits WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Interrupt Configuration, 2 bits per interrupt,
+    /// 16 interrupts per register
+    ///
+    /// Bit 1 of each field selects edge-triggered (1) or level-sensitive (0)
+    Config,
+    u32,
+    RW,
+    Fields [
+        Bits WIDTH(U32) OFFSET(U0),
+    ]
+}
+
+register! {
+    /// Software Generated Interrupt
+    Sgi,
+    u32,
+    RW,
+    Fields [
+        IntId WIDTH(U4) OFFSET(U0),
+        Nsatt WIDTH(U1) OFFSET(U15),
+        CpuTargetList WIDTH(U8) OFFSET(U16),
+        TargetListFilter WIDTH(U2) OFFSET(U24) [
+            List = U0,
+            AllButSelf = U1,
+            OnlySelf = U2
+        ],
+    ]
+}
+
+#[repr(C)]
+pub struct RegisterBlock {
+    pub control: Control::Register,           // 0x000
+    pub typ: Type::Register,                  // 0x004
+    pub iidr: Iidr::Register,                 // 0x008
+    __reserved_0: [u32; 29],                  // 0x00C
+    pub group: [IntBits::Register; 32],       // 0x080
+    pub set_enable: [IntBits::Register; 32],  // 0x100
+    pub clr_enable: [IntBits::Register; 32],  // 0x180
+    pub set_pending: [IntBits::Register; 32], // 0x200
+    pub clr_pending: [IntBits::Register; 32], // 0x280
+    pub set_active: [IntBits::Register; 32],  // 0x300
+    pub clr_active: [IntBits::Register; 32],  // 0x380
+    pub priority: [Priority::Register; 255],  // 0x400
+    __reserved_1: u32,                        // 0x7FC
+    pub targets: [Targets::Register; 255],    // 0x800
+    __reserved_2: u32,                        // 0xBFC
+    pub config: [Config::Register; 64],       // 0xC00
+    __reserved_3: [u32; 128],                 // 0xD00
+    pub sgi: Sgi::Register,                   // 0xF00
+}
+
+pub struct GICD {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for GICD {}
+
+impl GICD {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        GICD_PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        GICD_PADDR as *mut _
+    }
+}
+
+impl Deref for GICD {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for GICD {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
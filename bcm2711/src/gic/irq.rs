@@ -0,0 +1,73 @@
+//! BCM2711 interrupt IDs, as seen by the GIC-400
+//!
+//! SPIs are numbered from 32, VideoCore peripheral interrupts start at
+//! `VC_BASE` (SPI 64).
+//!
+//! Some of the peripherals share a single line, i.e. all of the PL011 UARTs
+//! are combined into `UART`.
+
+/// Hypervisor physical timer (PPI)
+pub const HYP_TIMER: u32 = 26;
+/// Virtual timer (PPI)
+pub const VIRT_TIMER: u32 = 27;
+/// Secure physical timer (PPI)
+pub const SECURE_PHYS_TIMER: u32 = 29;
+/// Non-secure physical timer (PPI)
+pub const NON_SECURE_PHYS_TIMER: u32 = 30;
+
+/// ARM mailbox
+pub const MAILBOX: u32 = 65;
+
+/// First of the VideoCore peripheral interrupts
+pub const VC_BASE: u32 = 96;
+
+pub const SYS_TIMER_0: u32 = VC_BASE;
+pub const SYS_TIMER_1: u32 = VC_BASE + 1;
+pub const SYS_TIMER_2: u32 = VC_BASE + 2;
+pub const SYS_TIMER_3: u32 = VC_BASE + 3;
+
+pub const DMA0: u32 = VC_BASE + 16;
+pub const DMA1: u32 = VC_BASE + 17;
+pub const DMA2: u32 = VC_BASE + 18;
+pub const DMA3: u32 = VC_BASE + 19;
+pub const DMA4: u32 = VC_BASE + 20;
+pub const DMA5: u32 = VC_BASE + 21;
+pub const DMA6: u32 = VC_BASE + 22;
+/// Shared by DMA channels 7 and 8
+pub const DMA7_8: u32 = VC_BASE + 23;
+/// Shared by DMA channels 9 and 10
+pub const DMA9_10: u32 = VC_BASE + 24;
+/// DMA4 channel 11
+pub const DMA11: u32 = VC_BASE + 25;
+/// DMA4 channel 12
+pub const DMA12: u32 = VC_BASE + 26;
+/// DMA4 channel 13
+pub const DMA13: u32 = VC_BASE + 27;
+/// DMA4 channel 14
+pub const DMA14: u32 = VC_BASE + 28;
+
+/// Mini UART (UART1), SPI1 and SPI2
+pub const AUX: u32 = VC_BASE + 29;
+
+/// GPIO 0-27
+pub const GPIO_BANK0: u32 = VC_BASE + 49;
+/// GPIO 28-45
+pub const GPIO_BANK1: u32 = VC_BASE + 50;
+/// GPIO 46-57
+pub const GPIO_BANK2: u32 = VC_BASE + 51;
+/// Any of the GPIO banks
+pub const GPIO: u32 = VC_BASE + 52;
+
+/// All of the BSC (I2C) masters, except BSC2 and BSC7
+pub const I2C: u32 = VC_BASE + 53;
+/// SPI0, SPI3-6
+pub const SPI: u32 = VC_BASE + 54;
+/// UART0, UART2-5
+pub const UART: u32 = VC_BASE + 57;
+
+pub const RNG: u32 = VC_BASE + 61;
+
+/// GENET INTRL2_0
+pub const GENET_0: u32 = 189;
+/// GENET INTRL2_1
+pub const GENET_1: u32 = 190;
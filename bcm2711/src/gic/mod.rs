@@ -0,0 +1,25 @@
+//! GIC-400 Generic Interrupt Controller
+//!
+//! The GIC-400 is part of the ARM local peripherals, it replaces the
+//! legacy ARMC interrupt controller when `enable_gic=1` (default on the Pi4).
+
+pub mod gicc;
+pub mod gicd;
+pub mod irq;
+
+pub use crate::gic::gicc::GICC;
+pub use crate::gic::gicd::GICD;
+
+pub const PADDR: usize = 0xFF84_0000;
+
+pub const GICD_PADDR: usize = PADDR + 0x1000;
+pub const GICC_PADDR: usize = PADDR + 0x2000;
+
+/// Number of interrupt IDs implemented by the BCM2711's GIC-400
+pub const NUM_INTERRUPTS: usize = 256;
+
+/// IDs at or above this value are special, i.e. a spurious interrupt
+pub const SPECIAL_ID_START: u32 = 1020;
+
+/// Interrupt ID reported when no interrupt is pending
+pub const SPURIOUS_ID: u32 = 1023;
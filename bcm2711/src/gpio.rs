@@ -497,6 +497,347 @@ register! {
     ]
 }
 
+register! {
+    /// GPIO Function Select 3
+    FunSel3,
+    u32,
+    RW,
+    Fields [
+        Pin30 WIDTH(U3) OFFSET(U0) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            /// SA3
+            AF1 = U5,
+            /// PCM_DIN
+            AF2 = U6,
+            /// CTS0
+            AF3 = U7,
+            AF4 = U3,
+            /// CTS1
+            AF5 = U2
+        ],
+        Pin31 WIDTH(U3) OFFSET(U3) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            /// SA2
+            AF1 = U5,
+            /// PCM_DOUT
+            AF2 = U6,
+            /// RTS0
+            AF3 = U7,
+            AF4 = U3,
+            /// RTS1
+            AF5 = U2
+        ],
+        Pin32 WIDTH(U3) OFFSET(U6) [
+            Input = U0,
+            Output = U1,
+            /// GPCLK0
+            AF0 = U4,
+            /// SA1
+            AF1 = U5,
+            AF2 = U6,
+            /// TXD0
+            AF3 = U7,
+            AF4 = U3,
+            /// TXD1
+            AF5 = U2
+        ],
+        Pin33 WIDTH(U3) OFFSET(U9) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            /// SA0
+            AF1 = U5,
+            AF2 = U6,
+            /// RXD0
+            AF3 = U7,
+            AF4 = U3,
+            /// RXD1
+            AF5 = U2
+        ],
+        Pin34 WIDTH(U3) OFFSET(U12) [
+            Input = U0,
+            Output = U1,
+            /// GPCLK0
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            /// SD1_CLK
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin35 WIDTH(U3) OFFSET(U15) [
+            Input = U0,
+            Output = U1,
+            /// SPI0_CE1_N
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            /// SD1_CMD
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin36 WIDTH(U3) OFFSET(U18) [
+            Input = U0,
+            Output = U1,
+            /// SPI0_CE0_N
+            AF0 = U4,
+            AF1 = U5,
+            /// TXD0
+            AF2 = U6,
+            /// SD1_DAT0
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin37 WIDTH(U3) OFFSET(U21) [
+            Input = U0,
+            Output = U1,
+            /// SPI0_MISO
+            AF0 = U4,
+            AF1 = U5,
+            /// RXD0
+            AF2 = U6,
+            /// SD1_DAT1
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin38 WIDTH(U3) OFFSET(U24) [
+            Input = U0,
+            Output = U1,
+            /// SPI0_MOSI
+            AF0 = U4,
+            AF1 = U5,
+            /// RTS0
+            AF2 = U6,
+            /// SD1_DAT2
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin39 WIDTH(U3) OFFSET(U27) [
+            Input = U0,
+            Output = U1,
+            /// SPI0_SCLK
+            AF0 = U4,
+            AF1 = U5,
+            /// CTS0
+            AF2 = U6,
+            /// SD1_DAT3
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+    ]
+}
+
+register! {
+    /// GPIO Function Select 4
+    FunSel4,
+    u32,
+    RW,
+    Fields [
+        Pin40 WIDTH(U3) OFFSET(U0) [
+            Input = U0,
+            Output = U1,
+            /// PWM1_0
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin41 WIDTH(U3) OFFSET(U3) [
+            Input = U0,
+            Output = U1,
+            /// PWM1_1
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin42 WIDTH(U3) OFFSET(U6) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin43 WIDTH(U3) OFFSET(U9) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin44 WIDTH(U3) OFFSET(U12) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin45 WIDTH(U3) OFFSET(U15) [
+            Input = U0,
+            Output = U1,
+            /// PWM0_1
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin46 WIDTH(U3) OFFSET(U18) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin47 WIDTH(U3) OFFSET(U21) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin48 WIDTH(U3) OFFSET(U24) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin49 WIDTH(U3) OFFSET(U27) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+    ]
+}
+
+register! {
+    /// GPIO Function Select 5
+    FunSel5,
+    u32,
+    RW,
+    Fields [
+        Pin50 WIDTH(U3) OFFSET(U0) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin51 WIDTH(U3) OFFSET(U3) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin52 WIDTH(U3) OFFSET(U6) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin53 WIDTH(U3) OFFSET(U9) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin54 WIDTH(U3) OFFSET(U12) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin55 WIDTH(U3) OFFSET(U15) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin56 WIDTH(U3) OFFSET(U18) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+        Pin57 WIDTH(U3) OFFSET(U21) [
+            Input = U0,
+            Output = U1,
+            AF0 = U4,
+            AF1 = U5,
+            AF2 = U6,
+            AF3 = U7,
+            AF4 = U3,
+            AF5 = U2
+        ],
+    ]
+}
+
 register! {
     /// GPIO Output Set Register 0
     Set0,
@@ -514,7 +855,7 @@ register! {
     u32,
     RW,
     Fields [
-        /// Pins 32:53
+        /// Pins 32:57
         Pins WIDTH(U32) OFFSET(U0) [],
     ]
 }
@@ -536,7 +877,7 @@ register! {
     u32,
     RW,
     Fields [
-        /// Pins 32:53
+        /// Pins 32:57
         Pins WIDTH(U32) OFFSET(U0) [],
     ]
 }
@@ -558,13 +899,173 @@ register! {
     u32,
     RO,
     Fields [
-        /// Pins 32:53
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Event Detect Status Register 0
+    ///
+    /// Write 1 to clear.
+    EventDetectStatus0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Event Detect Status Register 1
+    ///
+    /// Write 1 to clear.
+    EventDetectStatus1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Rising Edge Detect Enable Register 0
+    RisingEdgeDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Rising Edge Detect Enable Register 1
+    RisingEdgeDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Falling Edge Detect Enable Register 0
+    FallingEdgeDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Falling Edge Detect Enable Register 1
+    FallingEdgeDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO High Detect Enable Register 0
+    HighDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO High Detect Enable Register 1
+    HighDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Low Detect Enable Register 0
+    LowDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Low Detect Enable Register 1
+    LowDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Asynchronous Rising Edge Detect Enable Register 0
+    AsyncRisingEdgeDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Asynchronous Rising Edge Detect Enable Register 1
+    AsyncRisingEdgeDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Asynchronous Falling Edge Detect Enable Register 0
+    AsyncFallingEdgeDetect0,
+    u32,
+    RW,
+    Fields [
+        /// Pins 0:31
+        Pins WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// GPIO Asynchronous Falling Edge Detect Enable Register 1
+    AsyncFallingEdgeDetect1,
+    u32,
+    RW,
+    Fields [
+        /// Pins 32:57
         Pins WIDTH(U32) OFFSET(U0) [],
     ]
 }
 
 register! {
     /// GPIO Pull-up/down Register
+    ///
+    /// BCM2835 legacy, the BCM2711 uses `PullUpDownControl0..3`
     PullUpDown,
     u32,
     RW,
@@ -579,6 +1080,8 @@ register! {
 
 register! {
     /// GPIO Pull-up/down Clock Register 0
+    ///
+    /// BCM2835 legacy, the BCM2711 uses `PullUpDownControl0..3`
     PullUpDownClock0,
     u32,
     RW,
@@ -611,26 +1114,419 @@ register! {
         Pin25 WIDTH(U1) OFFSET(U25) [],
         Pin26 WIDTH(U1) OFFSET(U26) [],
         Pin27 WIDTH(U1) OFFSET(U27) [],
+        Pin28 WIDTH(U1) OFFSET(U28) [],
+        Pin29 WIDTH(U1) OFFSET(U29) [],
+        Pin30 WIDTH(U1) OFFSET(U30) [],
+        Pin31 WIDTH(U1) OFFSET(U31) [],
+    ]
+}
+
+register! {
+    /// GPIO Pull-up/down Clock Register 1
+    ///
+    /// BCM2835 legacy, the BCM2711 uses `PullUpDownControl0..3`
+    PullUpDownClock1,
+    u32,
+    RW,
+    Fields [
+        Pin32 WIDTH(U1) OFFSET(U0) [],
+        Pin33 WIDTH(U1) OFFSET(U1) [],
+        Pin34 WIDTH(U1) OFFSET(U2) [],
+        Pin35 WIDTH(U1) OFFSET(U3) [],
+        Pin36 WIDTH(U1) OFFSET(U4) [],
+        Pin37 WIDTH(U1) OFFSET(U5) [],
+        Pin38 WIDTH(U1) OFFSET(U6) [],
+        Pin39 WIDTH(U1) OFFSET(U7) [],
+        Pin40 WIDTH(U1) OFFSET(U8) [],
+        Pin41 WIDTH(U1) OFFSET(U9) [],
+        Pin42 WIDTH(U1) OFFSET(U10) [],
+        Pin43 WIDTH(U1) OFFSET(U11) [],
+        Pin44 WIDTH(U1) OFFSET(U12) [],
+        Pin45 WIDTH(U1) OFFSET(U13) [],
+        Pin46 WIDTH(U1) OFFSET(U14) [],
+        Pin47 WIDTH(U1) OFFSET(U15) [],
+        Pin48 WIDTH(U1) OFFSET(U16) [],
+        Pin49 WIDTH(U1) OFFSET(U17) [],
+        Pin50 WIDTH(U1) OFFSET(U18) [],
+        Pin51 WIDTH(U1) OFFSET(U19) [],
+        Pin52 WIDTH(U1) OFFSET(U20) [],
+        Pin53 WIDTH(U1) OFFSET(U21) [],
+    ]
+}
+
+register! {
+    /// GPIO Pull-up / Pull-down Register 0
+    PullUpDownControl0,
+    u32,
+    RW,
+    Fields [
+        Pin0 WIDTH(U2) OFFSET(U0) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin1 WIDTH(U2) OFFSET(U2) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin2 WIDTH(U2) OFFSET(U4) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin3 WIDTH(U2) OFFSET(U6) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin4 WIDTH(U2) OFFSET(U8) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin5 WIDTH(U2) OFFSET(U10) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin6 WIDTH(U2) OFFSET(U12) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin7 WIDTH(U2) OFFSET(U14) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin8 WIDTH(U2) OFFSET(U16) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin9 WIDTH(U2) OFFSET(U18) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin10 WIDTH(U2) OFFSET(U20) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin11 WIDTH(U2) OFFSET(U22) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin12 WIDTH(U2) OFFSET(U24) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin13 WIDTH(U2) OFFSET(U26) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin14 WIDTH(U2) OFFSET(U28) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin15 WIDTH(U2) OFFSET(U30) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+    ]
+}
+
+register! {
+    /// GPIO Pull-up / Pull-down Register 1
+    PullUpDownControl1,
+    u32,
+    RW,
+    Fields [
+        Pin16 WIDTH(U2) OFFSET(U0) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin17 WIDTH(U2) OFFSET(U2) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin18 WIDTH(U2) OFFSET(U4) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin19 WIDTH(U2) OFFSET(U6) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin20 WIDTH(U2) OFFSET(U8) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin21 WIDTH(U2) OFFSET(U10) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin22 WIDTH(U2) OFFSET(U12) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin23 WIDTH(U2) OFFSET(U14) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin24 WIDTH(U2) OFFSET(U16) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin25 WIDTH(U2) OFFSET(U18) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin26 WIDTH(U2) OFFSET(U20) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin27 WIDTH(U2) OFFSET(U22) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin28 WIDTH(U2) OFFSET(U24) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin29 WIDTH(U2) OFFSET(U26) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin30 WIDTH(U2) OFFSET(U28) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin31 WIDTH(U2) OFFSET(U30) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+    ]
+}
+
+register! {
+    /// GPIO Pull-up / Pull-down Register 2
+    PullUpDownControl2,
+    u32,
+    RW,
+    Fields [
+        Pin32 WIDTH(U2) OFFSET(U0) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin33 WIDTH(U2) OFFSET(U2) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin34 WIDTH(U2) OFFSET(U4) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin35 WIDTH(U2) OFFSET(U6) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin36 WIDTH(U2) OFFSET(U8) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin37 WIDTH(U2) OFFSET(U10) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin38 WIDTH(U2) OFFSET(U12) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin39 WIDTH(U2) OFFSET(U14) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin40 WIDTH(U2) OFFSET(U16) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin41 WIDTH(U2) OFFSET(U18) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin42 WIDTH(U2) OFFSET(U20) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin43 WIDTH(U2) OFFSET(U22) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin44 WIDTH(U2) OFFSET(U24) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin45 WIDTH(U2) OFFSET(U26) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin46 WIDTH(U2) OFFSET(U28) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin47 WIDTH(U2) OFFSET(U30) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+    ]
+}
+
+register! {
+    /// GPIO Pull-up / Pull-down Register 3
+    PullUpDownControl3,
+    u32,
+    RW,
+    Fields [
+        Pin48 WIDTH(U2) OFFSET(U0) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin49 WIDTH(U2) OFFSET(U2) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin50 WIDTH(U2) OFFSET(U4) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin51 WIDTH(U2) OFFSET(U6) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin52 WIDTH(U2) OFFSET(U8) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin53 WIDTH(U2) OFFSET(U10) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin54 WIDTH(U2) OFFSET(U12) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin55 WIDTH(U2) OFFSET(U14) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin56 WIDTH(U2) OFFSET(U16) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
+        Pin57 WIDTH(U2) OFFSET(U18) [
+            None = U0,
+            PullUp = U1,
+            PullDown = U2
+        ],
     ]
 }
 
 #[repr(C)]
 pub struct RegisterBlock {
-    pub fun_sel0: FunSel0::Register,          // 0x00
-    pub fun_sel1: FunSel1::Register,          // 0x04
-    pub fun_sel2: FunSel2::Register,          // 0x08
-    __reserved_0: [u32; 4],                   // 0x0C
-    pub set0: Set0::Register,                 // 0x1C
-    pub set1: Set1::Register,                 // 0x20
-    __reserved_1: u32,                        // 0x24
-    pub clr0: Clear0::Register,               // 0x28
-    pub clr1: Clear1::Register,               // 0x2C
-    __reserved_2: u32,                        // 0x30
-    pub level0: PinLevel0::Register,          // 0x34
-    pub level1: PinLevel1::Register,          // 0x38
-    __reserved_3: [u32; 22],                  // 0x3C
-    pub pud: PullUpDown::Register,            // 0x94
-    pub pud_clk0: PullUpDownClock0::Register, // 0x98
+    pub fun_sel0: FunSel0::Register,              // 0x00
+    pub fun_sel1: FunSel1::Register,              // 0x04
+    pub fun_sel2: FunSel2::Register,              // 0x08
+    pub fun_sel3: FunSel3::Register,              // 0x0C
+    pub fun_sel4: FunSel4::Register,              // 0x10
+    pub fun_sel5: FunSel5::Register,              // 0x14
+    __reserved_0: u32,                            // 0x18
+    pub set0: Set0::Register,                     // 0x1C
+    pub set1: Set1::Register,                     // 0x20
+    __reserved_1: u32,                            // 0x24
+    pub clr0: Clear0::Register,                   // 0x28
+    pub clr1: Clear1::Register,                   // 0x2C
+    __reserved_2: u32,                            // 0x30
+    pub level0: PinLevel0::Register,              // 0x34
+    pub level1: PinLevel1::Register,              // 0x38
+    __reserved_3: u32,                            // 0x3C
+    pub eds0: EventDetectStatus0::Register,       // 0x40
+    pub eds1: EventDetectStatus1::Register,       // 0x44
+    __reserved_4: u32,                            // 0x48
+    pub ren0: RisingEdgeDetect0::Register,        // 0x4C
+    pub ren1: RisingEdgeDetect1::Register,        // 0x50
+    __reserved_5: u32,                            // 0x54
+    pub fen0: FallingEdgeDetect0::Register,       // 0x58
+    pub fen1: FallingEdgeDetect1::Register,       // 0x5C
+    __reserved_6: u32,                            // 0x60
+    pub hen0: HighDetect0::Register,              // 0x64
+    pub hen1: HighDetect1::Register,              // 0x68
+    __reserved_7: u32,                            // 0x6C
+    pub len0: LowDetect0::Register,               // 0x70
+    pub len1: LowDetect1::Register,               // 0x74
+    __reserved_8: u32,                            // 0x78
+    pub aren0: AsyncRisingEdgeDetect0::Register,  // 0x7C
+    pub aren1: AsyncRisingEdgeDetect1::Register,  // 0x80
+    __reserved_9: u32,                            // 0x84
+    pub afen0: AsyncFallingEdgeDetect0::Register, // 0x88
+    pub afen1: AsyncFallingEdgeDetect1::Register, // 0x8C
+    __reserved_10: u32,                           // 0x90
+    pub pud: PullUpDown::Register,                // 0x94
+    pub pud_clk0: PullUpDownClock0::Register,     // 0x98
+    pub pud_clk1: PullUpDownClock1::Register,     // 0x9C
+    __reserved_11: [u32; 17],                     // 0xA0
+    pub pup_pdn0: PullUpDownControl0::Register,   // 0xE4
+    pub pup_pdn1: PullUpDownControl1::Register,   // 0xE8
+    pub pup_pdn2: PullUpDownControl2::Register,   // 0xEC
+    pub pup_pdn3: PullUpDownControl3::Register,   // 0xF0
 }
 
 pub struct GPIO {
@@ -7,8 +7,10 @@ extern crate typenum;
 
 const MMIO_BASE: usize = 0xFE00_0000;
 
+pub mod cm;
 pub mod dma;
 pub mod genet;
+pub mod gic;
 pub mod gpio;
 pub mod i2c0;
 pub mod i2c1;
@@ -18,6 +20,8 @@ pub mod i2c4;
 pub mod i2c5;
 pub mod i2c6;
 pub mod mbox;
+pub mod pwm0;
+pub mod pwm1;
 pub mod rng;
 pub mod spi0;
 pub mod spi1;
@@ -25,3 +29,7 @@ pub mod spi2;
 pub mod sys_timer;
 pub mod uart0;
 pub mod uart1;
+pub mod uart2;
+pub mod uart3;
+pub mod uart4;
+pub mod uart5;
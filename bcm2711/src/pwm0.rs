@@ -0,0 +1,165 @@
+//! PWM0
+//!
+//! Two channel PWM, each channel can be fed from the shared FIFO.
+//! The PWM clock is provided by the clock manager, see `cm`.
+
+use crate::MMIO_BASE;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+pub const PADDR: usize = MMIO_BASE + 0x20_C000;
+
+register! {
+    Control,
+    u32,
+    RW,
+    Fields [
+        /// Channel 1 enable
+        Enable1 WIDTH(U1) OFFSET(U0) [],
+        /// Channel 1 serializer mode, otherwise PWM mode
+        Serializer1 WIDTH(U1) OFFSET(U1) [],
+        /// Channel 1 repeats the last FIFO data when the FIFO is empty
+        RepeatLast1 WIDTH(U1) OFFSET(U2) [],
+        /// Channel 1 output state when not transmitting
+        Silence1 WIDTH(U1) OFFSET(U3) [],
+        /// Channel 1 output polarity is inverted
+        Polarity1 WIDTH(U1) OFFSET(U4) [],
+        /// Channel 1 transmits from the FIFO, otherwise from DAT1
+        UseFifo1 WIDTH(U1) OFFSET(U5) [],
+        /// Clear the FIFO, write only
+        ClearFifo WIDTH(U1) OFFSET(U6) [],
+        /// Channel 1 M/S transmission, otherwise the PWM algorithm
+        MarkSpace1 WIDTH(U1) OFFSET(U7) [],
+        /// Channel 2 enable
+        Enable2 WIDTH(U1) OFFSET(U8) [],
+        /// Channel 2 serializer mode, otherwise PWM mode
+        Serializer2 WIDTH(U1) OFFSET(U9) [],
+        /// Channel 2 repeats the last FIFO data when the FIFO is empty
+        RepeatLast2 WIDTH(U1) OFFSET(U10) [],
+        /// Channel 2 output state when not transmitting
+        Silence2 WIDTH(U1) OFFSET(U11) [],
+        /// Channel 2 output polarity is inverted
+        Polarity2 WIDTH(U1) OFFSET(U12) [],
+        /// Channel 2 transmits from the FIFO, otherwise from DAT2
+        UseFifo2 WIDTH(U1) OFFSET(U13) [],
+        /// Channel 2 M/S transmission, otherwise the PWM algorithm
+        MarkSpace2 WIDTH(U1) OFFSET(U15) [],
+    ]
+}
+
+register! {
+    /// Error and gap flags are write 1 to clear
+    Status,
+    u32,
+    RW,
+    Fields [
+        FifoFull WIDTH(U1) OFFSET(U0) [],
+        FifoEmpty WIDTH(U1) OFFSET(U1) [],
+        FifoWriteError WIDTH(U1) OFFSET(U2) [],
+        FifoReadError WIDTH(U1) OFFSET(U3) [],
+        /// Channel 1 gap occurred, the FIFO ran empty
+        Gap1 WIDTH(U1) OFFSET(U4) [],
+        /// Channel 2 gap occurred, the FIFO ran empty
+        Gap2 WIDTH(U1) OFFSET(U5) [],
+        BusError WIDTH(U1) OFFSET(U8) [],
+        /// Channel 1 is transmitting
+        State1 WIDTH(U1) OFFSET(U9) [],
+        /// Channel 2 is transmitting
+        State2 WIDTH(U1) OFFSET(U10) [],
+    ]
+}
+
+register! {
+    DmaConfig,
+    u32,
+    RW,
+    Fields [
+        /// DREQ is raised when the FIFO level drops below the threshold
+        DreqThreshold WIDTH(U8) OFFSET(U0) [],
+        /// Panic is raised when the FIFO level drops below the threshold
+        PanicThreshold WIDTH(U8) OFFSET(U8) [],
+        Enable WIDTH(U1) OFFSET(U31) [],
+    ]
+}
+
+register! {
+    /// Channel range, the period in PWM clock cycles (or serializer bits)
+    Range,
+    u32,
+    RW,
+    Fields [
+        Range WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// Channel data, the duty cycle in PWM clock cycles
+    Data,
+    u32,
+    RW,
+    Fields [
+        Data WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+register! {
+    /// FIFO input, shared by both channels
+    ///
+    /// With both channels using the FIFO, the words alternate between
+    /// channel 1 and channel 2.
+    FifoInput,
+    u32,
+    RW,
+    Fields [
+        Data WIDTH(U32) OFFSET(U0) [],
+    ]
+}
+
+#[repr(C)]
+pub struct RegisterBlock {
+    pub ctl: Control::Register,    // 0x00
+    pub sta: Status::Register,     // 0x04
+    pub dmac: DmaConfig::Register, // 0x08
+    __reserved_0: u32,             // 0x0C
+    pub rng1: Range::Register,     // 0x10
+    pub dat1: Data::Register,      // 0x14
+    pub fif1: FifoInput::Register, // 0x18
+    __reserved_1: u32,             // 0x1C
+    pub rng2: Range::Register,     // 0x20
+    pub dat2: Data::Register,      // 0x24
+}
+
+pub struct PWM0 {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for PWM0 {}
+
+impl PWM0 {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        PADDR as *mut _
+    }
+}
+
+impl Deref for PWM0 {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for PWM0 {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
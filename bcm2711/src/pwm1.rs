@@ -0,0 +1,45 @@
+//! PWM1
+//!
+//! Same registers as PWM0, used for the analog audio output.
+
+use crate::pwm0::RegisterBlock;
+use crate::MMIO_BASE;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+pub const PADDR: usize = MMIO_BASE + 0x20_C800;
+
+pub struct PWM1 {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for PWM1 {}
+
+impl PWM1 {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        PADDR as *mut _
+    }
+}
+
+impl Deref for PWM1 {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for PWM1 {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
@@ -1,12 +1,113 @@
 //! SPI1
+//!
+//! The auxiliary SPI master, SPI2 shares the same register block.
+//! Enabled through `uart1::AuxEnable`.
 
-use crate::spi0::RegisterBlock;
 use crate::MMIO_BASE;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 
 pub const PADDR: usize = MMIO_BASE + 0x21_5080;
 
+register! {
+    /// Control Register 0
+    Control0,
+    u32,
+    RW,
+    Fields [
+        /// Number of bits to shift, ignored in variable width mode
+        ShiftLength WIDTH(U6) OFFSET(U0) [],
+        ShiftOutMsbFirst WIDTH(U1) OFFSET(U6) [],
+        InvertClock WIDTH(U1) OFFSET(U7) [],
+        /// Data is clocked out on the rising edge of the clock
+        OutRising WIDTH(U1) OFFSET(U8) [],
+        /// Clears the FIFOs, held in reset while set
+        ClearFifos WIDTH(U1) OFFSET(U9) [],
+        /// Data is clocked in on the rising edge of the clock
+        InRising WIDTH(U1) OFFSET(U10) [],
+        Enable WIDTH(U1) OFFSET(U11) [],
+        DoutHoldTime WIDTH(U2) OFFSET(U12) [
+            Cycles0 = U0,
+            Cycles1 = U1,
+            Cycles4 = U2,
+            Cycles7 = U3
+        ],
+        /// Shift length is taken from bits [28:24] of each TX FIFO entry
+        VariableWidth WIDTH(U1) OFFSET(U14) [],
+        /// Chip selects are taken from bits [31:29] of each TX FIFO entry
+        VariableCs WIDTH(U1) OFFSET(U15) [],
+        PostInputMode WIDTH(U1) OFFSET(U16) [],
+        /// Chip select pattern while a transfer is active, active low
+        ChipSelects WIDTH(U3) OFFSET(U17) [],
+        /// SPI clock = core clock / (2 * (speed + 1))
+        Speed WIDTH(U12) OFFSET(U20) [],
+    ]
+}
+
+register! {
+    /// Control Register 1
+    Control1,
+    u32,
+    RW,
+    Fields [
+        /// Don't clear the receive shift register before a transfer
+        KeepInput WIDTH(U1) OFFSET(U0) [],
+        ShiftInMsbFirst WIDTH(U1) OFFSET(U1) [],
+        /// Interrupt when the module is idle
+        DoneIrq WIDTH(U1) OFFSET(U6) [],
+        /// Interrupt when the TX FIFO is empty
+        TxEmptyIrq WIDTH(U1) OFFSET(U7) [],
+        /// Extra clock cycles chip selects are held high between transfers
+        CsHighTime WIDTH(U3) OFFSET(U8) [],
+    ]
+}
+
+register! {
+    /// Status Register
+    Stat,
+    u32,
+    RO,
+    Fields [
+        BitCount WIDTH(U6) OFFSET(U0) [],
+        Busy WIDTH(U1) OFFSET(U6) [],
+        RxEmpty WIDTH(U1) OFFSET(U7) [],
+        RxFull WIDTH(U1) OFFSET(U8) [],
+        TxEmpty WIDTH(U1) OFFSET(U9) [],
+        TxFull WIDTH(U1) OFFSET(U10) [],
+        RxLevel WIDTH(U8) OFFSET(U16) [],
+        TxLevel WIDTH(U8) OFFSET(U24) [],
+    ]
+}
+
+register! {
+    /// Data Register
+    ///
+    /// Writes to `io` de-assert the chip selects once the TX FIFO is
+    /// empty, writes to `txhold` keep them asserted
+    Data,
+    u32,
+    RW,
+    Fields [
+        Data WIDTH(U24) OFFSET(U0) [],
+        /// Variable width mode only
+        Width WIDTH(U5) OFFSET(U24) [],
+        /// Variable chip select mode only
+        ChipSelects WIDTH(U3) OFFSET(U29) [],
+    ]
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+pub struct RegisterBlock {
+    pub cntl0: Control0::Register,   // 0x00
+    pub cntl1: Control1::Register,   // 0x04
+    pub stat: Stat::Register,        // 0x08
+    pub peek: Data::Register,        // 0x0C
+    __reserved_0: [u32; 4],          // 0x10
+    pub io: [Data::Register; 4],     // 0x20
+    pub txhold: [Data::Register; 4], // 0x30
+}
+
 pub struct SPI1 {
     _marker: PhantomData<*const ()>,
 }
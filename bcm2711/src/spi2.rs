@@ -1,6 +1,6 @@
 //! SPI2
 
-use crate::spi0::RegisterBlock;
+use crate::spi1::RegisterBlock;
 use crate::MMIO_BASE;
 use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
@@ -21,11 +21,37 @@ register! {
     ]
 }
 
+register! {
+    /// Receive Status/Error Clear
+    ///
+    /// Errors for the character last read from the data register,
+    /// any write clears them.
+    ReceiveStatus,
+    u32,
+    RW,
+    Fields [
+        FramingError WIDTH(U1) OFFSET(U0) [],
+        ParityError WIDTH(U1) OFFSET(U1) [],
+        BreakError WIDTH(U1) OFFSET(U2) [],
+        OverrunError WIDTH(U1) OFFSET(U3) [],
+    ]
+}
+
 register! {
     Flag,
     u32,
     RO,
     Fields [
+        /// Clear to send, the complement of the nUARTCTS modem status
+        /// input
+        Cts WIDTH(U1) OFFSET(U0) [],
+
+        /// UART busy. If this bit is set, the UART is busy
+        /// transmitting data. This bit remains set until the complete
+        /// byte, including all the stop bits, has been sent from the
+        /// shift register.
+        Busy WIDTH(U1) OFFSET(U3) [],
+
         /// Receive FIFO empty. The meaning of this bit depends on the
         /// state of the FEN bit in the UARTLCR_H Register. If the
         /// FIFO is disabled, this bit is set when the receive holding
@@ -39,6 +65,12 @@ register! {
         /// holding register is full. If the FIFO is enabled, the TXFF
         /// bit is set when the transmit FIFO is full.
         TxFull WIDTH(U1) OFFSET(U5) [],
+
+        /// Receive FIFO full
+        RxFull WIDTH(U1) OFFSET(U6) [],
+
+        /// Transmit FIFO empty
+        TxEmpty WIDTH(U1) OFFSET(U7) [],
     ]
 }
 
@@ -65,8 +97,26 @@ register! {
 register! {
     LineControl,
     u32,
-    WO,
+    RW,
     Fields [
+        /// Send break. If this bit is set, a low-level is continually
+        /// output on the TXD output, after completing transmission of the
+        /// current character.
+        SendBreak WIDTH(U1) OFFSET(U0) [],
+
+        /// Parity enable
+        ParityEnable WIDTH(U1) OFFSET(U1) [],
+
+        /// Even parity select, odd parity when clear
+        EvenParity WIDTH(U1) OFFSET(U2) [],
+
+        /// Two stop bits select
+        TwoStopBits WIDTH(U1) OFFSET(U3) [],
+
+        /// Enable FIFOs, character mode (1-byte holding registers)
+        /// when clear
+        FifoEnable WIDTH(U1) OFFSET(U4) [],
+
         /// Word length. These bits indicate the number of data bits
         /// transmitted or received in a frame.
         WordLength WIDTH(U2) OFFSET(U5) [
@@ -74,14 +124,17 @@ register! {
             SixBit = U1,
             SevenBit = U2,
             EightBit = U3
-        ]
+        ],
+
+        /// Stick parity select
+        StickParity WIDTH(U1) OFFSET(U7) [],
     ]
 }
 
 register! {
     Control,
     u32,
-    WO,
+    RW,
     Fields [
         /// UART enable
         Enable WIDTH(U1) OFFSET(U0) [
@@ -92,6 +145,10 @@ register! {
             Enabled = U1
         ],
 
+        /// Loopback enable. If this bit is set to 1, the UARTTXD path
+        /// is fed through to the UARTRXD path.
+        LoopbackEnable WIDTH(U1) OFFSET(U7) [],
+
         /// Transmit enable. If this bit is set to 1, the transmit
         /// section of the UART is enabled. Data transmission occurs
         /// for UART signals. When the UART is disabled in the middle
@@ -111,6 +168,69 @@ register! {
             Disabled = U0,
             Enabled = U1
         ],
+
+        /// Request to send. This bit is the complement of the UART
+        /// request to send, nUARTRTS, modem status output.
+        Rts WIDTH(U1) OFFSET(U11) [],
+
+        /// RTS hardware flow control enable. If this bit is set to 1,
+        /// data is only requested when there is space in the receive
+        /// FIFO for it to be received.
+        RtsEnable WIDTH(U1) OFFSET(U14) [],
+
+        /// CTS hardware flow control enable. If this bit is set to 1,
+        /// data is only transmitted when the nUARTCTS signal is
+        /// asserted.
+        CtsEnable WIDTH(U1) OFFSET(U15) [],
+    ]
+}
+
+register! {
+    /// Interrupt FIFO Level Select
+    FifoLevel,
+    u32,
+    RW,
+    Fields [
+        TxLevel WIDTH(U3) OFFSET(U0) [
+            OneEighth = U0,
+            OneQuarter = U1,
+            OneHalf = U2,
+            ThreeQuarters = U3,
+            SevenEighths = U4
+        ],
+        RxLevel WIDTH(U3) OFFSET(U3) [
+            OneEighth = U0,
+            OneQuarter = U1,
+            OneHalf = U2,
+            ThreeQuarters = U3,
+            SevenEighths = U4
+        ],
+    ]
+}
+
+register! {
+    /// Interrupt bits, shared by the mask set/clear, raw status and
+    /// masked status registers
+    Int,
+    u32,
+    RW,
+    Fields [
+        /// nUARTCTS modem interrupt
+        Cts WIDTH(U1) OFFSET(U1) [],
+        /// Receive interrupt
+        Rx WIDTH(U1) OFFSET(U4) [],
+        /// Transmit interrupt
+        Tx WIDTH(U1) OFFSET(U5) [],
+        /// Receive timeout interrupt
+        RxTimeout WIDTH(U1) OFFSET(U6) [],
+        /// Framing error interrupt
+        FramingError WIDTH(U1) OFFSET(U7) [],
+        /// Parity error interrupt
+        ParityError WIDTH(U1) OFFSET(U8) [],
+        /// Break error interrupt
+        BreakError WIDTH(U1) OFFSET(U9) [],
+        /// Overrun error interrupt
+        OverrunError WIDTH(U1) OFFSET(U10) [],
     ]
 }
 
@@ -127,14 +247,18 @@ register! {
 #[repr(C)]
 pub struct RegisterBlock {
     pub dr: Data::Register,                        // 0x00
-    __reserved_0: [u32; 5],                        // 0x04
+    pub rsr: ReceiveStatus::Register,              // 0x04
+    __reserved_0: [u32; 4],                        // 0x08
     pub fr: Flag::Register,                        // 0x18
     __reserved_1: [u32; 2],                        // 0x1c
     pub ibrd: IntegerBaudRateDivisor::Register,    // 0x24
     pub fbrd: FractionalBaudRateDivisor::Register, // 0x28
     pub lcrh: LineControl::Register,               // 0x2C
     pub cr: Control::Register,                     // 0x30
-    __reserved_2: [u32; 4],                        // 0x34
+    pub ifls: FifoLevel::Register,                 // 0x34
+    pub imsc: Int::Register,                       // 0x38
+    pub ris: Int::Register,                        // 0x3C
+    pub mis: Int::Register,                        // 0x40
     pub icr: IntClear::Register,                   // 0x44
 }
 
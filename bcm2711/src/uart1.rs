@@ -40,6 +40,9 @@ register! {
     Fields [
         IntRx WIDTH(U1) OFFSET(U0) [],
         IntTx WIDTH(U1) OFFSET(U1) [],
+        /// Documented as don't care, but both bits must be set in order
+        /// to receive interrupts
+        IntLineStatus WIDTH(U2) OFFSET(U2) [],
     ]
 }
 
@@ -49,6 +52,16 @@ register! {
     u32,
     RW,
     Fields [
+        /// This bit is clear whenever an interrupt is pending
+        IntNotPending WIDTH(U1) OFFSET(U0) [],
+
+        /// On read this field shows the interrupt ID
+        IntId WIDTH(U2) OFFSET(U1) [
+            NoInts = U0,
+            TxEmpty = U1,
+            RxReady = U2
+        ],
+
         /// Writing with bit 1 set will clear the receive FIFO
         /// Writing with bit 2 set will clear the transmit FIFO
         FifoClear WIDTH(U2) OFFSET(U1) [
@@ -91,9 +104,17 @@ register! {
         /// symbol.
         DataReady WIDTH(U1) OFFSET(U0) [],
 
+        /// This bit is set if there was a receiver overrun, it's
+        /// cleared each time this register is read.
+        RxOverrun WIDTH(U1) OFFSET(U1) [],
+
         /// This bit is set if the transmit FIFO can accept at least
         /// one byte.
         TxEmpty WIDTH(U1) OFFSET(U5) [],
+
+        /// This bit is set if the transmit FIFO is empty and the
+        /// transmitter is idle.
+        TxIdle WIDTH(U1) OFFSET(U6) [],
     ]
 }
 
@@ -115,6 +136,14 @@ register! {
             Disabled = U0,
             Enabled = U1
         ],
+
+        /// If this bit is set the RTS line will de-assert if the
+        /// receive FIFO reaches its 'auto flow' level.
+        RtsFlowEnable WIDTH(U1) OFFSET(U2) [],
+
+        /// If this bit is set the transmitter will stop if the CTS
+        /// line is de-asserted.
+        CtsFlowEnable WIDTH(U1) OFFSET(U3) [],
     ]
 }
 
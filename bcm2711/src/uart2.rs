@@ -0,0 +1,45 @@
+//! UART2
+//!
+//! Secondary PL011 UART, same registers as UART0.
+
+use crate::uart0::RegisterBlock;
+use crate::MMIO_BASE;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+pub const PADDR: usize = MMIO_BASE + 0x20_1400;
+
+pub struct UART2 {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for UART2 {}
+
+impl UART2 {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        PADDR as *mut _
+    }
+}
+
+impl Deref for UART2 {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for UART2 {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
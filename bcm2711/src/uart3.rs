@@ -0,0 +1,45 @@
+//! UART3
+//!
+//! Secondary PL011 UART, same registers as UART0.
+
+use crate::uart0::RegisterBlock;
+use crate::MMIO_BASE;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+pub const PADDR: usize = MMIO_BASE + 0x20_1600;
+
+pub struct UART3 {
+    _marker: PhantomData<*const ()>,
+}
+
+unsafe impl Send for UART3 {}
+
+impl UART3 {
+    pub fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const RegisterBlock {
+        PADDR as *const _
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut RegisterBlock {
+        PADDR as *mut _
+    }
+}
+
+impl Deref for UART3 {
+    type Target = RegisterBlock;
+    fn deref(&self) -> &RegisterBlock {
+        unsafe { &*self.as_ptr() }
+    }
+}
+
+impl DerefMut for UART3 {
+    fn deref_mut(&mut self) -> &mut RegisterBlock {
+        unsafe { &mut *self.as_mut_ptr() }
+    }
+}
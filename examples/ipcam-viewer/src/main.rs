@@ -167,8 +167,8 @@ fn kernel_entry() -> ! {
     info!("Waiting for link-up");
 
     loop {
-        let status = eth.status().unwrap();
-        if status.link_status {
+        eth.poll_link().unwrap();
+        if let Some(status) = eth.link() {
             info!("Link is up");
             info!("Speed: {}", status.speed);
             info!("Full duplex: {}", status.full_duplex);
@@ -308,7 +308,35 @@ fn kernel_entry() -> ! {
                             None => (),
                             Some(image_info) => {
                                 info!(" {} : {}", decoder.decoded_count(), image_info);
-                                assert_eq!(image_info.image.len() / 4, WIDTH * HEIGHT);
+
+                                if image_info.width != WIDTH || image_info.height != HEIGHT {
+                                    // Fit the frame into the window
+                                    let src = convert::Packed::new(
+                                        image_info.image,
+                                        image_info.width,
+                                        image_info.height,
+                                        image_info.width * bbp,
+                                        convert::PixelFormat::Bgrx8888,
+                                    )
+                                    .unwrap();
+                                    let mut dst = convert::FrameBuffer::from_u32(
+                                        frontbuffer_mem,
+                                        WIDTH,
+                                        HEIGHT,
+                                        fb.pitch() as usize,
+                                        convert::PixelFormat::Bgrx8888,
+                                    )
+                                    .unwrap();
+                                    convert::scale_nearest(&src, &mut dst);
+
+                                    unsafe {
+                                        cache::clean_data_cache_range(
+                                            frontbuffer_mem.as_ptr() as _,
+                                            vc_mem_size,
+                                        );
+                                    }
+                                    return;
+                                }
 
                                 dcb.set_src(image_info.image.as_ptr() as u32);
 
@@ -1,11 +1,11 @@
 // TODO - redo most of this, still needs better connection managmenent
 
 use crate::hal::bcm2711::genet::NUM_DMA_DESC;
-use crate::hal::eth::MAX_MTU_SIZE;
+use crate::hal::eth::{LinkEvent, MAX_MTU_SIZE};
 use crate::hal::time::{Duration, Instant};
 use core::str;
 use heapless::{consts::U512, String};
-use log::{debug, warn};
+use log::{debug, info, warn};
 use rtsp::header::CSeq;
 use rtsp::*;
 use smoltcp::iface::EthernetInterface;
@@ -31,6 +31,8 @@ const RTSP_KEEP_ALIVE_INTERVAL: Duration = Duration {
     millis: 40_000,
 };
 
+const LINK_POLL_INTERVAL: Duration = Duration { millis: 500 };
+
 // 49152..=65535
 const EPHEMERAL_PORT: u16 = 49152;
 
@@ -63,6 +65,7 @@ pub struct Net<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> {
     session: Option<Session>,
     cseq: CSeq,
     last_keep_alive: Instant,
+    last_link_poll: Instant,
 }
 
 impl<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> Net<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> {
@@ -88,6 +91,7 @@ impl<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> Net<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> {
             session: None,
             cseq: CSeq::default(),
             last_keep_alive: Instant::from_millis(0),
+            last_link_poll: Instant::from_millis(0),
         };
 
         debug!("UDP endpoint {}", eth.udp_endpoint);
@@ -113,6 +117,21 @@ impl<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> Net<'a, 'b, 'c, 'd, 'e, 'f, 'rx, 'tx> {
         let mut reconnect = false;
         let mut tcp_state = TcpState::Closed;
 
+        // Pick up cable unplug/replug, the TCP connection management below
+        // reconnects once the link is back
+        if (time - self.last_link_poll) >= LINK_POLL_INTERVAL {
+            self.last_link_poll = time;
+            match self.iface.device_mut().eth.poll_link() {
+                Ok(Some(LinkEvent::Up(status))) => info!(
+                    "[{}] Link up, speed {}, full duplex {}",
+                    time, status.speed, status.full_duplex
+                ),
+                Ok(Some(LinkEvent::Down)) => warn!("[{}] Link down", time),
+                Ok(None) => (),
+                Err(e) => warn!("[{}] Link poll error {:?}", time, e),
+            }
+        }
+
         if self.rtsp_state == RtspState::Streaming {
             let mut tcp_socket = self.sockets.get::<TcpSocket>(self.tcp_handle);
 
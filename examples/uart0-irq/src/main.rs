@@ -0,0 +1,59 @@
+//! Interrupt driven UART0 echo
+//!
+//! The GIC dispatches the UART interrupt to `uart0_irq_handler`, which
+//! moves data through the ring buffers.
+
+#![no_std]
+#![no_main]
+
+extern crate bcm2711_hal as hal;
+
+use crate::hal::bcm2711::gic::{irq, GICC, GICD};
+use crate::hal::bcm2711::gpio::GPIO;
+use crate::hal::bcm2711::mbox::MBOX;
+use crate::hal::bcm2711::uart0::UART0;
+use crate::hal::clocks::Clocks;
+use crate::hal::gic::{self, Gic};
+use crate::hal::mailbox::Mailbox;
+use crate::hal::prelude::*;
+use crate::hal::serial::{self, Serial};
+use crate::hal::time::Bps;
+use core::fmt::Write;
+use nb::block;
+
+raspi3_boot::irq_handler!(gic::dispatch);
+
+fn kernel_entry() -> ! {
+    let mut mbox = Mailbox::new(MBOX::new());
+    let clocks = Clocks::freeze(&mut mbox).unwrap();
+    let gpio = GPIO::new();
+
+    let gp = gpio.split();
+    let tx = gp.p14.into_alternate_af0();
+    let rx = gp.p15.into_alternate_af0();
+
+    let serial = Serial::uart0(UART0::new(), (tx, rx), Bps(115200), clocks);
+
+    let (rx_buffer, tx_buffer) = unsafe {
+        static mut RX_BUFFER: [u8; 256] = [0; 256];
+        static mut TX_BUFFER: [u8; 256] = [0; 256];
+        (&mut RX_BUFFER[..], &mut TX_BUFFER[..])
+    };
+    let mut serial = serial.into_buffered(rx_buffer, tx_buffer);
+
+    let mut gic = Gic::new(GICD::new(), GICC::new());
+    gic.register(irq::UART, serial::uart0_irq_handler).unwrap();
+    gic.enable(irq::UART).unwrap();
+    unsafe { gic::enable_irqs() };
+
+    writeln!(serial, "UART0 IRQ example, type something").ok();
+
+    loop {
+        match block!(serial.read()) {
+            Ok(byte) => block!(serial.write(byte)).unwrap(),
+            Err(e) => writeln!(serial, "Rx error {:?}", e).unwrap(),
+        }
+    }
+}
+
+raspi3_boot::entry!(kernel_entry);
@@ -0,0 +1,95 @@
+//! Exception handling
+//!
+//! IRQs are forwarded to the `__irq_handler` symbol, provided by the
+//! `irq_handler!` macro. Everything else is unexpected and panics.
+
+use core::fmt;
+
+/// General purpose registers saved on exception entry
+#[repr(C)]
+pub struct ExceptionContext {
+    /// x0 - x29
+    pub gpr: [u64; 30],
+    /// Link register, x30
+    pub lr: u64,
+    _pad: u64,
+}
+
+impl fmt::Debug for ExceptionContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, reg) in self.gpr.iter().enumerate() {
+            writeln!(f, "x{:02}: {:#018X}", i, reg)?;
+        }
+        write!(f, "lr : {:#018X}", self.lr)
+    }
+}
+
+/// Exception syndrome, `_boot_cores` always runs us at EL1 so exceptions
+/// are only ever taken to EL1
+struct Syndrome {
+    esr: u64,
+    elr: u64,
+    far: u64,
+}
+
+macro_rules! mrs {
+    ($reg:literal) => {{
+        let val: u64;
+        asm!(concat!("mrs $0, ", $reg) : "=r"(val) : : : "volatile");
+        val
+    }};
+}
+
+impl Syndrome {
+    fn read() -> Self {
+        unsafe {
+            Syndrome {
+                esr: mrs!("ESR_EL1"),
+                elr: mrs!("ELR_EL1"),
+                far: mrs!("FAR_EL1"),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Syndrome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ESR: {:#010X} (EC {:#04X}) ELR: {:#018X} FAR: {:#018X}",
+            self.esr,
+            self.esr >> 26,
+            self.elr,
+            self.far
+        )
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn default_exception_handler(e: &mut ExceptionContext) {
+    panic!("Unexpected exception\n{}\n{:?}", Syndrome::read(), e);
+}
+
+#[no_mangle]
+unsafe extern "C" fn default_irq_handler() {
+    panic!("IRQ taken without a handler, see `irq_handler!`");
+}
+
+#[no_mangle]
+unsafe extern "C" fn current_elx_synchronous(e: &mut ExceptionContext) {
+    panic!("Synchronous exception\n{}\n{:?}", Syndrome::read(), e);
+}
+
+#[no_mangle]
+unsafe extern "C" fn current_elx_irq(_e: &mut ExceptionContext) {
+    extern "C" {
+        fn __irq_handler();
+    }
+
+    __irq_handler();
+}
+
+#[no_mangle]
+unsafe extern "C" fn current_elx_serror(e: &mut ExceptionContext) {
+    panic!("SError\n{}\n{:?}", Syndrome::read(), e);
+}
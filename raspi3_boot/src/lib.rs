@@ -26,7 +26,7 @@
 
 #![deny(warnings)]
 #![no_std]
-#![feature(global_asm)]
+#![feature(asm, global_asm)]
 #![cfg_attr(feature = "panic-uart1", feature(core_intrinsics))]
 
 //! Low-level boot of the Raspberry's processor
@@ -40,6 +40,10 @@ extern crate bcm2711_hal as hal;
 #[cfg(feature = "panic-uart1")]
 mod panic_uart1;
 
+mod exception;
+
+pub use crate::exception::ExceptionContext;
+
 #[macro_export]
 macro_rules! entry {
     ($path:path) => {
@@ -53,6 +57,23 @@ macro_rules! entry {
     };
 }
 
+/// Installs the IRQ handler, called for every IRQ exception with IRQs masked.
+///
+/// Typically the interrupt controller's dispatcher, i.e.
+/// `raspi3_boot::irq_handler!(bcm2711_hal::gic::dispatch);`
+#[macro_export]
+macro_rules! irq_handler {
+    ($path:path) => {
+        #[export_name = "__irq_handler"]
+        pub unsafe extern "C" fn __irq_handler() {
+            // type check the given path
+            let f: fn() = $path;
+
+            f()
+        }
+    };
+}
+
 /// Reset function.
 ///
 /// Initializes the bss section before calling into the user's `main()`.
@@ -76,3 +97,6 @@ pub unsafe extern "C" fn reset() -> ! {
 
 // Disable all cores except core 0, and then jump to reset()
 global_asm!(include_str!("boot_cores.S"));
+
+// Exception vector table
+global_asm!(include_str!("vectors.S"));
@@ -1,23 +1,13 @@
 //! [RFC3984](https://tools.ietf.org/html/rfc3984#section-5.2)
 //!
 //! NOTE: only supports NAL unit types:
-//! * 1 (single NAL unit packed per H.264)
-//! * 6 (SEI)
-//! * 7 (SPS)
-//! * 8 (PPS)
+//! * 1-23 (single NAL unit packet, e.g. slices, IDR, SEI, SPS, PPS)
+//! * 24 (STAP-A single-time aggregation packet)
 //! * 28 (FU-A fragmentation unit)
 
 use crate::{Error, NalUnitType};
 use core::fmt;
 
-const SUPPORTED_UNIT_TYPES: [NalUnitType; 5] = [
-    NalUnitType::SingleNalUnit,
-    NalUnitType::Sei,
-    NalUnitType::Sps,
-    NalUnitType::Pps,
-    NalUnitType::FuA,
-];
-
 /// NAL unit header
 ///
 /// [RFC2435](https://tools.ietf.org/html/rfc2435#section-1.3)
@@ -67,10 +57,10 @@ impl<T: AsRef<[u8]>> Header<T> {
 
     pub fn check_nal_unit_type(&self) -> Result<(), Error> {
         let typ = self.nal_unit_type();
-        if !(&SUPPORTED_UNIT_TYPES).contains(&typ) {
-            Err(Error::NalUnitType(typ))
-        } else {
-            Ok(())
+        match typ {
+            NalUnitType::StapA | NalUnitType::FuA => Ok(()),
+            _ if typ.is_single_nal_unit() => Ok(()),
+            _ => Err(Error::NalUnitType(typ)),
         }
     }
 
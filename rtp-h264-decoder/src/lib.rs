@@ -5,8 +5,9 @@
 pub use crate::fragmentation_unit::FragmentationUnit;
 pub use crate::header::Header;
 pub use crate::nal_unit_type::NalUnitType;
-use log::trace;
+use log::{trace, warn};
 pub use rtp;
+use rtp::{Depacketizer, Frame, Statistics};
 
 pub mod fragmentation_unit;
 pub mod header;
@@ -14,7 +15,11 @@ pub mod nal_unit_type;
 
 pub const START_SEQ: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
 
-// TODO
+/// Size field preceding each NAL unit of a STAP-A
+const STAP_A_NALU_SIZE_LEN: usize = 2;
+
+/// Default payload type, H.264 is always dynamically assigned so the
+/// actual value should come from the session description
 pub const RTP_PAYLOAD_TYPE_H264: u8 = 96;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -26,10 +31,22 @@ pub enum Error {
     RtpPayloadType(u8),
 }
 
+/// Reassembles NAL units into Annex B access units, each NAL unit is
+/// prefixed with `START_SEQ`.
+///
+/// An access unit is complete when the MARKER packet is received.
 // TODO - wait for SPS/PPS/etc
 #[derive(Debug)]
 pub struct H264Decoder<'b> {
-    last_seq_num: u16,
+    payload_type: u8,
+    stats: Statistics,
+    /// RTP timestamp of the access unit being reassembled
+    timestamp: Option<u32>,
+    last_seq_num: Option<u16>,
+    /// A FU-A start fragment was received, waiting for the end fragment
+    fragmented: bool,
+    /// Packets were lost, the access unit is dropped once complete
+    corrupt: bool,
     buffered: usize,
     buffer: &'b mut [u8],
 }
@@ -43,34 +60,317 @@ impl<'b> H264Decoder<'b> {
         }
 
         Ok(H264Decoder {
-            last_seq_num: 0,
+            payload_type: RTP_PAYLOAD_TYPE_H264,
+            stats: Statistics::new(),
+            timestamp: None,
+            last_seq_num: None,
+            fragmented: false,
+            corrupt: false,
             buffered: 0,
             buffer: defrag_storage,
         })
     }
 
     pub fn reset(&mut self) {
-        //self.first_marker_found = false;
-        self.buffered = 0;
-        self.last_seq_num = 0;
+        self.stats = Statistics::new();
+        self.last_seq_num = None;
+        self.start_access_unit(None);
     }
 
-    pub fn decode(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<(), Error> {
+    pub fn decode(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<Frame<'_>>, Error> {
         // TODO - check len and version on packet
         // - check extension, not supported
+        self.stats.packets = self.stats.packets.wrapping_add(1);
 
         let rtp_payload_type = packet.payload_type();
-        if rtp_payload_type != RTP_PAYLOAD_TYPE_H264 {
+        if rtp_payload_type != self.payload_type {
             return Err(Error::RtpPayloadType(rtp_payload_type));
         }
 
         trace!("{}", packet);
-        trace!("prev sequence_number: {}", self.last_seq_num);
+        trace!("prev sequence_number: {:?}", self.last_seq_num);
+
+        let timestamp = packet.timestamp();
+        if self.timestamp != Some(timestamp) {
+            if self.buffered != 0 || self.corrupt {
+                warn!("Incomplete access unit {:?}, dropping", self.timestamp);
+                self.stats.dropped_frames = self.stats.dropped_frames.wrapping_add(1);
+            }
+            self.start_access_unit(Some(timestamp));
+        }
+
+        let seq_num = packet.sequence_number();
+        if let Some(prev) = self.last_seq_num {
+            if seq_num != prev.wrapping_add(1) {
+                warn!("Lost packets, sequence {} -> {}", prev, seq_num);
+                self.corrupt = true;
+            }
+        }
+        self.last_seq_num = Some(seq_num);
+
+        if let Err(e) = self.buffer_nal_unit(packet.payload()) {
+            self.corrupt = true;
+            return Err(e);
+        }
+
+        if !packet.contains_marker() {
+            return Ok(None);
+        }
+
+        let size = self.buffered;
+        let corrupt = self.corrupt;
+        self.start_access_unit(None);
+        if corrupt || size == 0 {
+            warn!("Corrupt access unit {}, dropping", timestamp);
+            self.stats.dropped_frames = self.stats.dropped_frames.wrapping_add(1);
+            Ok(None)
+        } else {
+            trace!("Access unit {} complete, size {}", timestamp, size);
+            self.stats.frames = self.stats.frames.wrapping_add(1);
+            Ok(Some(Frame {
+                timestamp,
+                data: &self.buffer[..size],
+            }))
+        }
+    }
+
+    fn start_access_unit(&mut self, timestamp: Option<u32>) {
+        self.timestamp = timestamp;
+        self.fragmented = false;
+        self.corrupt = false;
+        self.buffered = 0;
+    }
 
-        let hdr = Header::new_checked(packet.payload())?;
+    fn buffer_nal_unit(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let hdr = Header::new_checked(payload)?;
 
         trace!("{}", hdr);
 
-        todo!()
+        match hdr.nal_unit_type() {
+            NalUnitType::FuA => (),
+            _ if self.fragmented => return Err(Error::Syntax),
+            NalUnitType::StapA => return self.buffer_aggregation(hdr.payload()),
+            _ => {
+                self.write(&START_SEQ)?;
+                return self.write(payload);
+            }
+        }
+
+        let fu = FragmentationUnit::new_checked(hdr.payload())?;
+        if fu.start() {
+            if self.fragmented {
+                return Err(Error::Syntax);
+            }
+            self.fragmented = true;
+            // Reconstruct the NAL unit header from the FU indicator
+            let nal_hdr = (payload[0] & 0xE0) | fu.typ();
+            self.write(&START_SEQ)?;
+            self.write(&[nal_hdr])?;
+        } else if !self.fragmented {
+            return Err(Error::Syntax);
+        }
+
+        self.write(fu.payload())?;
+        if fu.end() {
+            self.fragmented = false;
+        }
+        Ok(())
+    }
+
+    /// STAP-A, each NAL unit is prefixed with its 16-bit size
+    fn buffer_aggregation(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            return Err(Error::Truncated);
+        }
+
+        while !data.is_empty() {
+            if data.len() < STAP_A_NALU_SIZE_LEN {
+                return Err(Error::Truncated);
+            }
+            let size = usize::from(u16::from_be_bytes([data[0], data[1]]));
+            let end = STAP_A_NALU_SIZE_LEN + size;
+            if size == 0 {
+                return Err(Error::Syntax);
+            }
+            if data.len() < end {
+                return Err(Error::Truncated);
+            }
+            self.write(&START_SEQ)?;
+            self.write(&data[STAP_A_NALU_SIZE_LEN..end])?;
+            data = &data[end..];
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        let end = self.buffered + data.len();
+        if end > self.buffer.len() {
+            return Err(Error::StorageOverflow);
+        }
+        self.buffer[self.buffered..end].copy_from_slice(data);
+        self.buffered = end;
+        Ok(())
+    }
+}
+
+impl<'b> Depacketizer for H264Decoder<'b> {
+    type Error = Error;
+
+    fn payload_type(&self) -> u8 {
+        self.payload_type
+    }
+
+    fn set_payload_type(&mut self, payload_type: u8) {
+        self.payload_type = payload_type;
+    }
+
+    fn push(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<Frame<'_>>, Error> {
+        self.decode(packet)
+    }
+
+    fn reset(&mut self) {
+        H264Decoder::reset(self)
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet<'a>(
+        seq_num: u16,
+        timestamp: u32,
+        marker: bool,
+        payload: &[u8],
+        buffer: &'a mut [u8; 64],
+    ) -> rtp::Packet<&'a [u8]> {
+        buffer[0] = 0x80;
+        buffer[1] = RTP_PAYLOAD_TYPE_H264 | if marker { 0x80 } else { 0 };
+        buffer[2..4].copy_from_slice(&seq_num.to_be_bytes());
+        buffer[4..8].copy_from_slice(&timestamp.to_be_bytes());
+        buffer[8..12].copy_from_slice(&[0x27, 0x13, 0x17, 0xD1]);
+        let len = rtp::HEADER_SIZE + payload.len();
+        buffer[rtp::HEADER_SIZE..len].copy_from_slice(payload);
+        rtp::Packet::new_checked(&buffer[..len]).unwrap()
+    }
+
+    #[test]
+    fn single_and_fragmented_units() {
+        let mut storage = [0; 256];
+        let mut dec = H264Decoder::new(&mut storage).unwrap();
+        let mut buffer = [0; 64];
+
+        // SPS, then a slice split into 3 FU-A fragments (NRI 3, type 5)
+        let p = packet(10, 3000, false, &[0x67, 0x42, 0x00], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        let p = packet(11, 3000, false, &[0x7C, 0x85, 0xA0, 0xA1], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        let p = packet(12, 3000, false, &[0x7C, 0x05, 0xA2], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        let p = packet(13, 3000, true, &[0x7C, 0x45, 0xA3], &mut buffer);
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(frame.timestamp, 3000);
+        assert_eq!(
+            frame.data,
+            &[0, 0, 0, 1, 0x67, 0x42, 0x00, 0, 0, 0, 1, 0x65, 0xA0, 0xA1, 0xA2, 0xA3]
+        );
+        assert_eq!(dec.statistics().frames, 1);
+        assert_eq!(dec.statistics().dropped_frames, 0);
+    }
+
+    #[test]
+    fn single_nal_unit_types() {
+        let mut storage = [0; 256];
+        let mut dec = H264Decoder::new(&mut storage).unwrap();
+        let mut buffer = [0; 64];
+
+        // Unfragmented IDR slice
+        let p = packet(1, 3000, true, &[0x65, 0x88, 0x80], &mut buffer);
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(frame.data, &[0, 0, 0, 1, 0x65, 0x88, 0x80]);
+
+        // Access unit delimiter (9) and end of sequence (10)
+        let p = packet(2, 6000, false, &[0x09, 0xF0], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        let p = packet(3, 6000, true, &[0x0A], &mut buffer);
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(frame.data, &[0, 0, 0, 1, 0x09, 0xF0, 0, 0, 0, 1, 0x0A]);
+
+        // STAP-B (25) and type 0 aren't supported
+        let p = packet(4, 9000, true, &[0x19, 0x00], &mut buffer);
+        assert_eq!(
+            dec.push(&p),
+            Err(Error::NalUnitType(NalUnitType::Unknown(25)))
+        );
+        let p = packet(5, 12000, true, &[0x00, 0x00], &mut buffer);
+        assert_eq!(
+            dec.push(&p),
+            Err(Error::NalUnitType(NalUnitType::Unknown(0)))
+        );
+    }
+
+    #[test]
+    fn aggregation_packets() {
+        let mut storage = [0; 256];
+        let mut dec = H264Decoder::new(&mut storage).unwrap();
+        let mut buffer = [0; 64];
+
+        // STAP-A with SPS, PPS and an IDR slice
+        let p = packet(
+            1,
+            3000,
+            true,
+            &[
+                0x78, 0x00, 0x03, 0x67, 0x42, 0x00, 0x00, 0x02, 0x68, 0xCE, 0x00, 0x02, 0x65, 0x88,
+            ],
+            &mut buffer,
+        );
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(
+            frame.data,
+            &[0, 0, 0, 1, 0x67, 0x42, 0x00, 0, 0, 0, 1, 0x68, 0xCE, 0, 0, 0, 1, 0x65, 0x88]
+        );
+
+        // NAL unit size runs past the end of the packet
+        let p = packet(2, 6000, true, &[0x78, 0x00, 0x04, 0x67, 0x42], &mut buffer);
+        assert_eq!(dec.push(&p), Err(Error::Truncated));
+        assert_eq!(dec.statistics().frames, 1);
+        assert_eq!(dec.statistics().dropped_frames, 0);
+
+        // Next access unit resets the corrupt one
+        let p = packet(3, 9000, true, &[0x78, 0x00, 0x01, 0x09], &mut buffer);
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(frame.data, &[0, 0, 0, 1, 0x09]);
+        assert_eq!(dec.statistics().dropped_frames, 1);
+    }
+
+    #[test]
+    fn drop_lost_packets() {
+        let mut storage = [0; 256];
+        let mut dec = H264Decoder::new(&mut storage).unwrap();
+        let mut buffer = [0; 64];
+
+        let p = packet(1, 3000, false, &[0x7C, 0x85, 0xA0], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        // Sequence 2 lost
+        let p = packet(3, 3000, true, &[0x7C, 0x45, 0xA3], &mut buffer);
+        assert_eq!(dec.push(&p), Ok(None));
+        assert_eq!(dec.statistics().dropped_frames, 1);
+
+        // Next access unit is fine
+        let p = packet(4, 6000, true, &[0x61, 0xB0], &mut buffer);
+        let frame = dec.push(&p).unwrap().unwrap();
+        assert_eq!(frame.data, &[0, 0, 0, 1, 0x61, 0xB0]);
+
+        dec.set_payload_type(97);
+        assert_eq!(
+            dec.push(&p),
+            Err(Error::RtpPayloadType(RTP_PAYLOAD_TYPE_H264))
+        );
+        assert_eq!(dec.statistics().packets, 4);
     }
 }
@@ -6,12 +6,17 @@ pub enum NalUnitType {
     /// [RFC3984](https://tools.ietf.org/html/rfc3984#section-5.6)
     /// Type 1
     SingleNalUnit,
+    /// 5 (IDR slice)
+    Idr,
     /// 6
     Sei,
     /// 7
     Sps,
     /// 8
     Pps,
+    /// [RFC3984](https://tools.ietf.org/html/rfc3984#section-5.7.1)
+    /// Type 24
+    StapA,
     /// [RFC2435](https://tools.ietf.org/html/rfc2435#section-5.8)
     /// Type 28
     FuA,
@@ -19,13 +24,23 @@ pub enum NalUnitType {
     Unknown(u8),
 }
 
+impl NalUnitType {
+    /// Types 1-23 are a whole NAL unit carried in a single packet
+    pub fn is_single_nal_unit(self) -> bool {
+        let val: u8 = self.into();
+        (1..=23).contains(&val)
+    }
+}
+
 impl From<u8> for NalUnitType {
     fn from(val: u8) -> Self {
         match val {
             1 => NalUnitType::SingleNalUnit,
+            5 => NalUnitType::Idr,
             6 => NalUnitType::Sei,
             7 => NalUnitType::Sps,
             8 => NalUnitType::Pps,
+            24 => NalUnitType::StapA,
             28 => NalUnitType::FuA,
             _ => NalUnitType::Unknown(val),
         }
@@ -36,9 +51,11 @@ impl Into<u8> for NalUnitType {
     fn into(self) -> u8 {
         match self {
             NalUnitType::SingleNalUnit => 1,
+            NalUnitType::Idr => 5,
             NalUnitType::Sei => 6,
             NalUnitType::Sps => 7,
             NalUnitType::Pps => 8,
+            NalUnitType::StapA => 24,
             NalUnitType::FuA => 28,
             NalUnitType::Unknown(v) => v,
         }
@@ -50,9 +67,11 @@ impl fmt::Display for NalUnitType {
         let val: u8 = self.clone().into();
         match *self {
             NalUnitType::SingleNalUnit => write!(f, "NAL unit ({})", val),
+            NalUnitType::Idr => write!(f, "IDR ({})", val),
             NalUnitType::Sei => write!(f, "SEI ({})", val),
             NalUnitType::Sps => write!(f, "SPS ({})", val),
             NalUnitType::Pps => write!(f, "PPS ({})", val),
+            NalUnitType::StapA => write!(f, "STAP-A ({})", val),
             NalUnitType::FuA => write!(f, "FU-A ({})", val),
             NalUnitType::Unknown(_v) => write!(f, "Unknown ({})", val),
         }
@@ -1,18 +1,26 @@
 // TODO - make a proper example
 // RUST_LOG=trace
 // cargo run --example rtsp-decode --target x86_64-unknown-linux-gnu
+//
+// Pass an output directory to dump the reassembled frames as .jpg files
+// instead of decoding them:
+// cargo run --example rtsp-decode --target x86_64-unknown-linux-gnu -- /tmp/frames
 
 use rtp_jpeg_decoder::*;
 use rtsp::*;
 use std::convert::TryFrom;
+use std::fs;
 use std::io::prelude::*;
 use std::net::UdpSocket;
 use std::net::{Shutdown, TcpStream};
+use std::path::PathBuf;
 use std::str;
 
 fn main() -> std::io::Result<()> {
     env_logger::init();
 
+    let dump_dir = std::env::args().nth(1).map(PathBuf::from);
+
     let mut frame_buffer = vec![0; 65536];
 
     let dec = NanoJPeg::init();
@@ -87,11 +95,24 @@ fn main() -> std::io::Result<()> {
 
                 match rtp::Packet::new_checked(&rx[..amt]) {
                     Err(e) => panic!("rtp::Packet error {:?}", e),
-                    Ok(pkt) => match decoder.decode(&pkt) {
-                        Err(e) => panic!("JPEGDecoder error {:?}", e),
-                        Ok(maybe_image) => match maybe_image {
-                            None => println!("Ok"),
-                            Some(image_info) => println!("{}", image_info),
+                    Ok(pkt) => match &dump_dir {
+                        Some(dir) => match decoder.reassemble(&pkt) {
+                            Err(e) => panic!("JPEGDecoder error {:?}", e),
+                            Ok(maybe_jpeg) => match maybe_jpeg {
+                                None => println!("Ok"),
+                                Some(jpeg) => {
+                                    let path = dir.join(format!("frame_{}.jpg", pkt.timestamp()));
+                                    println!("Writing {} bytes to {}", jpeg.len(), path.display());
+                                    fs::write(path, jpeg)?;
+                                }
+                            },
+                        },
+                        None => match decoder.decode(&pkt) {
+                            Err(e) => panic!("JPEGDecoder error {:?}", e),
+                            Ok(maybe_image) => match maybe_image {
+                                None => println!("Ok"),
+                                Some(image_info) => println!("{}", image_info),
+                            },
                         },
                     },
                 }
@@ -0,0 +1,445 @@
+//! Colorspace conversion and scaling into a framebuffer
+//!
+//! Sources are either the planar YCbCr output of
+//! `JPEGDecoder::decode_planes`, or packed RGB like `ImageInfo::image`.
+//! Scaling happens in the source colorspace so each destination pixel is
+//! only converted once.
+
+use crate::clamp;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// Zero width or height
+    Dimensions,
+    /// Pitch/stride is smaller than a row
+    Pitch,
+    BufferTooSmall,
+}
+
+/// Byte layout of a packed pixel, in memory order
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum PixelFormat {
+    Rgb888,
+    Bgr888,
+    /// R, G, B, unused
+    Rgbx8888,
+    /// B, G, R, unused, i.e. a little-endian `0x00RRGGBB` u32
+    Bgrx8888,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb888 | PixelFormat::Bgr888 => 3,
+            PixelFormat::Rgbx8888 | PixelFormat::Bgrx8888 => 4,
+        }
+    }
+
+    #[inline]
+    fn read(self, data: &[u8]) -> [u8; 3] {
+        match self {
+            PixelFormat::Rgb888 | PixelFormat::Rgbx8888 => [data[0], data[1], data[2]],
+            PixelFormat::Bgr888 | PixelFormat::Bgrx8888 => [data[2], data[1], data[0]],
+        }
+    }
+
+    #[inline]
+    fn write(self, rgb: [u8; 3], data: &mut [u8]) {
+        match self {
+            PixelFormat::Rgb888 => data[..3].copy_from_slice(&rgb),
+            PixelFormat::Bgr888 => data[..3].copy_from_slice(&[rgb[2], rgb[1], rgb[0]]),
+            PixelFormat::Rgbx8888 => data[..4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 0]),
+            PixelFormat::Bgrx8888 => data[..4].copy_from_slice(&[rgb[2], rgb[1], rgb[0], 0]),
+        }
+    }
+}
+
+/// Chroma subsampling of a planar image
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Subsampling {
+    Yuv444,
+    /// Half horizontal chroma resolution, RTP JPEG type 0
+    Yuv422,
+    /// Half horizontal and vertical chroma resolution, RTP JPEG type 1
+    Yuv420,
+}
+
+impl Subsampling {
+    /// Horizontal and vertical chroma shift
+    pub(crate) fn shift(self) -> (usize, usize) {
+        match self {
+            Subsampling::Yuv444 => (0, 0),
+            Subsampling::Yuv422 => (1, 0),
+            Subsampling::Yuv420 => (1, 1),
+        }
+    }
+}
+
+/// Something that can be sampled by the scaler
+pub trait Source {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    /// Returns the three components at `(x, y)`, in the source colorspace
+    fn components(&self, x: usize, y: usize) -> [u8; 3];
+
+    /// Converts components to RGB
+    fn to_rgb(&self, components: [u8; 3]) -> [u8; 3];
+}
+
+/// Planar YCbCr image, full range (JFIF)
+#[derive(Debug, Copy, Clone)]
+pub struct Planes<'a> {
+    width: usize,
+    height: usize,
+    subsampling: Subsampling,
+    y: &'a [u8],
+    y_stride: usize,
+    cb: &'a [u8],
+    cr: &'a [u8],
+    c_stride: usize,
+}
+
+impl<'a> Planes<'a> {
+    /// Strides are in bytes, the chroma planes share a stride
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        width: usize,
+        height: usize,
+        subsampling: Subsampling,
+        y: &'a [u8],
+        y_stride: usize,
+        cb: &'a [u8],
+        cr: &'a [u8],
+        c_stride: usize,
+    ) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::Dimensions);
+        }
+        let (hs, vs) = subsampling.shift();
+        let c_width = (width + (1 << hs) - 1) >> hs;
+        let c_height = (height + (1 << vs) - 1) >> vs;
+        if y_stride < width || c_stride < c_width {
+            return Err(Error::Pitch);
+        }
+        let c_size = (c_stride * (c_height - 1)) + c_width;
+        if y.len() < (y_stride * (height - 1)) + width || cb.len() < c_size || cr.len() < c_size {
+            return Err(Error::BufferTooSmall);
+        }
+
+        Ok(Planes {
+            width,
+            height,
+            subsampling,
+            y,
+            y_stride,
+            cb,
+            cr,
+            c_stride,
+        })
+    }
+
+    pub fn subsampling(&self) -> Subsampling {
+        self.subsampling
+    }
+}
+
+impl<'a> Source for Planes<'a> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    fn components(&self, x: usize, y: usize) -> [u8; 3] {
+        let (hs, vs) = self.subsampling.shift();
+        let c = ((y >> vs) * self.c_stride) + (x >> hs);
+        [self.y[(y * self.y_stride) + x], self.cb[c], self.cr[c]]
+    }
+
+    #[inline]
+    fn to_rgb(&self, components: [u8; 3]) -> [u8; 3] {
+        ycbcr_to_rgb(components[0], components[1], components[2])
+    }
+}
+
+/// Packed RGB image, e.g. the output of `NanoJPeg`
+#[derive(Debug, Copy, Clone)]
+pub struct Packed<'a> {
+    width: usize,
+    height: usize,
+    pitch: usize,
+    format: PixelFormat,
+    data: &'a [u8],
+}
+
+impl<'a> Packed<'a> {
+    /// Pitch is in bytes
+    pub fn new(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        pitch: usize,
+        format: PixelFormat,
+    ) -> Result<Self, Error> {
+        check_packed(data.len(), width, height, pitch, format)?;
+        Ok(Packed {
+            width,
+            height,
+            pitch,
+            format,
+            data,
+        })
+    }
+}
+
+impl<'a> Source for Packed<'a> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    fn components(&self, x: usize, y: usize) -> [u8; 3] {
+        let offset = (y * self.pitch) + (x * self.format.bytes_per_pixel());
+        self.format.read(&self.data[offset..])
+    }
+
+    #[inline]
+    fn to_rgb(&self, components: [u8; 3]) -> [u8; 3] {
+        components
+    }
+}
+
+/// Destination of a conversion, rows are `pitch` bytes apart
+#[derive(Debug)]
+pub struct FrameBuffer<'a> {
+    width: usize,
+    height: usize,
+    pitch: usize,
+    format: PixelFormat,
+    data: &'a mut [u8],
+}
+
+impl<'a> FrameBuffer<'a> {
+    /// Pitch is in bytes
+    pub fn new(
+        data: &'a mut [u8],
+        width: usize,
+        height: usize,
+        pitch: usize,
+        format: PixelFormat,
+    ) -> Result<Self, Error> {
+        check_packed(data.len(), width, height, pitch, format)?;
+        Ok(FrameBuffer {
+            width,
+            height,
+            pitch,
+            format,
+            data,
+        })
+    }
+
+    /// Framebuffer of `u32` pixels, pitch is still in bytes
+    pub fn from_u32(
+        data: &'a mut [u32],
+        width: usize,
+        height: usize,
+        pitch: usize,
+        format: PixelFormat,
+    ) -> Result<Self, Error> {
+        let len = data.len() * 4;
+        // Safe: u8 has no alignment requirements and the length covers the
+        // same memory
+        let data = unsafe { core::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, len) };
+        Self::new(data, width, height, pitch, format)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    fn put(&mut self, x: usize, y: usize, rgb: [u8; 3]) {
+        let offset = (y * self.pitch) + (x * self.format.bytes_per_pixel());
+        self.format.write(rgb, &mut self.data[offset..]);
+    }
+}
+
+/// Full range ITU-R BT.601 YCbCr to RGB, 16.16 fixed point
+#[inline]
+pub fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    let y = (i32::from(y) << 16) + (1 << 15);
+    let cb = i32::from(cb) - 128;
+    let cr = i32::from(cr) - 128;
+    let r = (y + (91_881 * cr)) >> 16;
+    let g = (y - (22_554 * cb) - (46_802 * cr)) >> 16;
+    let b = (y + (116_130 * cb)) >> 16;
+    [
+        clamp(r, 0, 255) as u8,
+        clamp(g, 0, 255) as u8,
+        clamp(b, 0, 255) as u8,
+    ]
+}
+
+/// Scales the source to fill the framebuffer, picking the nearest
+/// source pixel
+pub fn scale_nearest<S: Source>(src: &S, dst: &mut FrameBuffer) {
+    let x_step = step(src.width(), dst.width);
+    let y_step = step(src.height(), dst.height);
+
+    for dy in 0..dst.height {
+        let sy = (dy * y_step) >> 16;
+        for dx in 0..dst.width {
+            let sx = (dx * x_step) >> 16;
+            let rgb = src.to_rgb(src.components(sx, sy));
+            dst.put(dx, dy, rgb);
+        }
+    }
+}
+
+/// Scales the source to fill the framebuffer, interpolating between the
+/// four nearest source pixels
+pub fn scale_bilinear<S: Source>(src: &S, dst: &mut FrameBuffer) {
+    let x_step = step(src.width(), dst.width);
+    let y_step = step(src.height(), dst.height);
+
+    for dy in 0..dst.height {
+        let (y0, y1, wy) = sample_position(dy, y_step, src.height());
+        for dx in 0..dst.width {
+            let (x0, x1, wx) = sample_position(dx, x_step, src.width());
+            let top = lerp(src.components(x0, y0), src.components(x1, y0), wx);
+            let bottom = lerp(src.components(x0, y1), src.components(x1, y1), wx);
+            let rgb = src.to_rgb(lerp(top, bottom, wy));
+            dst.put(dx, dy, rgb);
+        }
+    }
+}
+
+fn check_packed(
+    len: usize,
+    width: usize,
+    height: usize,
+    pitch: usize,
+    format: PixelFormat,
+) -> Result<(), Error> {
+    if width == 0 || height == 0 {
+        return Err(Error::Dimensions);
+    }
+    let row_size = width * format.bytes_per_pixel();
+    if pitch < row_size {
+        return Err(Error::Pitch);
+    }
+    if len < (pitch * (height - 1)) + row_size {
+        return Err(Error::BufferTooSmall);
+    }
+    Ok(())
+}
+
+/// Source pixels per destination pixel, 16.16 fixed point
+fn step(src: usize, dst: usize) -> usize {
+    (src << 16) / dst
+}
+
+/// Returns the two source pixels around the center of destination pixel
+/// `d` and the 8-bit weight of the second one
+#[inline]
+fn sample_position(d: usize, step: usize, size: usize) -> (usize, usize, u32) {
+    let pos = ((d * step) + (step >> 1)).saturating_sub(1 << 15);
+    let p0 = core::cmp::min(pos >> 16, size - 1);
+    let p1 = core::cmp::min(p0 + 1, size - 1);
+    (p0, p1, ((pos >> 8) & 0xFF) as u32)
+}
+
+#[inline]
+fn lerp(a: [u8; 3], b: [u8; 3], weight: u32) -> [u8; 3] {
+    let mix = |a: u8, b: u8| ((u32::from(a) * (256 - weight)) + (u32::from(b) * weight) + 128) >> 8;
+    [
+        mix(a[0], b[0]) as u8,
+        mix(a[1], b[1]) as u8,
+        mix(a[2], b[2]) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colorspace() {
+        assert_eq!(ycbcr_to_rgb(0, 128, 128), [0, 0, 0]);
+        assert_eq!(ycbcr_to_rgb(255, 128, 128), [255, 255, 255]);
+        assert_eq!(ycbcr_to_rgb(76, 85, 255), [254, 0, 0]);
+        assert_eq!(ycbcr_to_rgb(150, 44, 21), [0, 255, 1]);
+        assert_eq!(ycbcr_to_rgb(29, 255, 107), [0, 0, 254]);
+    }
+
+    #[test]
+    fn nearest_downscale_with_pitch() {
+        // 4x2 4:2:0, left half dark, right half light
+        let y = [0, 0, 255, 255, 0, 0, 255, 255];
+        let c = [128, 128];
+        let src = Planes::new(4, 2, Subsampling::Yuv420, &y, 4, &c, &c, 2).unwrap();
+
+        let mut fb = [0xAA_u32; 6];
+        {
+            let mut dst = FrameBuffer::from_u32(&mut fb, 2, 1, 12, PixelFormat::Bgrx8888).unwrap();
+            scale_nearest(&src, &mut dst);
+        }
+        assert_eq!(u32::from_le(fb[0]), 0);
+        assert_eq!(u32::from_le(fb[1]), 0x00FF_FFFF);
+        // Padding is untouched
+        assert_eq!(&fb[2..], &[0xAA; 4]);
+    }
+
+    #[test]
+    fn bilinear() {
+        let data = [0, 0, 0, 200, 100, 50];
+        let src = Packed::new(&data, 2, 1, 6, PixelFormat::Rgb888).unwrap();
+
+        // Upscale 2 -> 4, inner pixels are blended
+        let mut out = [0; 12];
+        let mut dst = FrameBuffer::new(&mut out, 4, 1, 12, PixelFormat::Bgr888).unwrap();
+        scale_bilinear(&src, &mut dst);
+        assert_eq!(out, [0, 0, 0, 13, 25, 50, 38, 75, 150, 50, 100, 200]);
+
+        // Same size is a plain conversion
+        let mut out = [0; 6];
+        let mut dst = FrameBuffer::new(&mut out, 2, 1, 6, PixelFormat::Rgb888).unwrap();
+        scale_bilinear(&src, &mut dst);
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn bad_buffers() {
+        let mut out = [0; 8];
+        assert_eq!(
+            FrameBuffer::new(&mut out, 2, 1, 4, PixelFormat::Rgb888).err(),
+            Some(Error::Pitch)
+        );
+        assert_eq!(
+            FrameBuffer::new(&mut out, 2, 2, 6, PixelFormat::Rgb888).err(),
+            Some(Error::BufferTooSmall)
+        );
+        let y = [0; 16];
+        assert_eq!(
+            Planes::new(4, 4, Subsampling::Yuv422, &y, 4, &y[..6], &y, 2).err(),
+            Some(Error::BufferTooSmall)
+        );
+        assert_eq!(
+            Packed::new(&y, 0, 4, 4, PixelFormat::Rgb888).err(),
+            Some(Error::Dimensions)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Tracks which byte ranges of a frame have been received
+
+use crate::Error;
+
+/// Maximum number of disjoint byte ranges tracked while a frame is
+/// being reassembled
+pub const MAX_RANGES: usize = 16;
+
+/// Sorted list of disjoint, half-open `[start, end)` byte ranges.
+/// Adjacent ranges are merged as fragments arrive, so an in-order
+/// frame only ever occupies a single entry.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct Coverage {
+    count: usize,
+    ranges: [(usize, usize); MAX_RANGES],
+}
+
+impl Coverage {
+    pub const fn new() -> Self {
+        Coverage {
+            count: 0,
+            ranges: [(0, 0); MAX_RANGES],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    /// Adds the range `[start, end)`.
+    ///
+    /// Returns `Ok(false)` if the exact range was already present
+    /// (duplicate fragment).
+    pub fn insert(&mut self, start: usize, end: usize) -> Result<bool, Error> {
+        if start >= end {
+            return Ok(false);
+        }
+
+        // Index of the first range that starts after the new one
+        let mut index = self.count;
+        for (i, r) in self.ranges[..self.count].iter().enumerate() {
+            if (start, end) == *r || (start >= r.0 && end <= r.1) {
+                return Ok(false);
+            }
+            if start < r.1 && r.0 < end {
+                return Err(Error::FragmentOverlap);
+            }
+            if r.0 > start {
+                index = i;
+                break;
+            }
+        }
+
+        let merge_prev = index > 0 && self.ranges[index - 1].1 == start;
+        let merge_next = index < self.count && self.ranges[index].0 == end;
+
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                self.ranges[index - 1].1 = self.ranges[index].1;
+                self.remove(index);
+            }
+            (true, false) => self.ranges[index - 1].1 = end,
+            (false, true) => self.ranges[index].0 = start,
+            (false, false) => {
+                if self.count == MAX_RANGES {
+                    return Err(Error::FragmentRanges);
+                }
+                self.ranges.copy_within(index..self.count, index + 1);
+                self.ranges[index] = (start, end);
+                self.count += 1;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns true if `[0, size)` has been received without any holes
+    pub fn is_contiguous(&self, size: usize) -> bool {
+        self.count == 1 && self.ranges[0] == (0, size)
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.ranges.copy_within(index + 1..self.count, index);
+        self.count -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_order() {
+        let mut c = Coverage::new();
+        assert_eq!(c.insert(0, 10), Ok(true));
+        assert_eq!(c.insert(10, 20), Ok(true));
+        assert_eq!(c.insert(20, 25), Ok(true));
+        assert_eq!(c.count, 1);
+        assert!(c.is_contiguous(25));
+        assert!(!c.is_contiguous(30));
+    }
+
+    #[test]
+    fn out_of_order() {
+        let mut c = Coverage::new();
+        assert_eq!(c.insert(20, 25), Ok(true));
+        assert_eq!(c.insert(0, 10), Ok(true));
+        assert_eq!(c.count, 2);
+        assert!(!c.is_contiguous(25));
+        assert_eq!(c.insert(10, 20), Ok(true));
+        assert_eq!(c.count, 1);
+        assert!(c.is_contiguous(25));
+    }
+
+    #[test]
+    fn duplicates_and_overlaps() {
+        let mut c = Coverage::new();
+        assert_eq!(c.insert(0, 10), Ok(true));
+        assert_eq!(c.insert(0, 10), Ok(false));
+        assert_eq!(c.insert(2, 8), Ok(false));
+        assert_eq!(c.insert(5, 15), Err(Error::FragmentOverlap));
+        c.clear();
+        assert!(!c.is_contiguous(10));
+    }
+
+    #[test]
+    fn too_many_ranges() {
+        let mut c = Coverage::new();
+        for i in 0..MAX_RANGES {
+            assert_eq!(c.insert(i * 10, (i * 10) + 5), Ok(true));
+        }
+        assert_eq!(c.insert(1000, 1005), Err(Error::FragmentRanges));
+        // Merging into an existing range still works when full
+        assert_eq!(c.insert(5, 10), Ok(true));
+    }
+}
@@ -1,14 +1,29 @@
 //! [RFC2435](https://tools.ietf.org/html/rfc2435#section-3.1)
 //!
-//! NOTE: doesn't handle restart marker headers or quantization table headers
-//! type <= 63
-//! Q value <= 127
+//! Handles the restart marker header (type 64-127) and the quantization
+//! table header (Q 128-255, first packet of a frame only), `payload` is the
+//! scan data following them.
+//!
+//! NOTE: only supports types 0 and 1 (and 64, 65 with restart markers)
 
 use byteorder::{BigEndian, ByteOrder};
 
 /// First 8 bytes are called the "main JPEG header"
 pub const MAIN_SIZE: usize = 8;
 
+/// Restart marker header, present when type is 64-127
+pub const RESTART_MARKER_SIZE: usize = 4;
+
+/// Quantization table header, present in the first packet of a frame
+/// when Q is 128-255
+pub const QTABLE_HEADER_SIZE: usize = 4;
+
+/// Type values with a restart marker header are offset by this
+pub const TYPE_RESTART_OFFSET: u8 = 64;
+
+/// Q values 128-255 use dynamic quantization tables
+pub const QVALUE_DYNAMIC_MIN: u8 = 128;
+
 /// Width and height are encoded in 8-pixel multiples.
 /// The maximum width is 2040 pixels.
 /// The maximum height is 2040 pixels.
@@ -47,6 +62,20 @@ mod field {
     pub const HEIGHT: usize = 7;
     /// Remaining
     pub const PAYLOAD: Rest = super::MAIN_SIZE..;
+
+    /// Restart marker header
+    /// Restart interval (16 bits)
+    pub const RESTART_INTERVAL: Field = 8..10;
+    /// F (1 bit), L (1 bit), restart count (14 bits)
+    pub const RESTART_COUNT: Field = 10..12;
+
+    /// Quantization table header, relative to its start
+    /// MBZ (8 bits)
+    pub const QT_MBZ: usize = 0;
+    /// Precision (8 bits)
+    pub const QT_PRECISION: usize = 1;
+    /// Length (16 bits)
+    pub const QT_LENGTH: Field = 2..4;
 }
 
 impl<T: AsRef<[u8]>> Header<T> {
@@ -65,6 +94,19 @@ impl<T: AsRef<[u8]>> Header<T> {
     pub fn check_len(&self) -> Result<(), Error> {
         let len = self.buffer.as_ref().len();
         if len < field::PAYLOAD.start {
+            return Err(Error::Truncated);
+        }
+        let mut headers_len = field::PAYLOAD.start;
+        if self.has_restart_header() {
+            headers_len += RESTART_MARKER_SIZE;
+        }
+        if self.has_qtable_header() {
+            if len < headers_len + QTABLE_HEADER_SIZE {
+                return Err(Error::Truncated);
+            }
+            headers_len += QTABLE_HEADER_SIZE + usize::from(self.qtable_length());
+        }
+        if len < headers_len {
             Err(Error::Truncated)
         } else {
             Ok(())
@@ -72,17 +114,15 @@ impl<T: AsRef<[u8]>> Header<T> {
     }
 
     pub fn check_type(&self) -> Result<(), Error> {
-        // Doesn't handle restart marker header yet
-        if self.typ() >= 64 {
-            Err(Error::Type)
-        } else {
-            Ok(())
+        match self.base_type() {
+            0 | 1 => Ok(()),
+            _ => Err(Error::Type),
         }
     }
 
     pub fn check_qvalue(&self) -> Result<(), Error> {
-        // Doesn't handle quantization table header yet
-        if self.typ() >= 128 {
+        // Q = 0 is reserved
+        if self.qvalue() == 0 {
             Err(Error::QValue)
         } else {
             Ok(())
@@ -93,11 +133,97 @@ impl<T: AsRef<[u8]>> Header<T> {
         self.buffer
     }
 
-    // TODO - assumes no restart marker header or quantization table header
+    /// Size of the main JPEG header, see `headers_len` for the size
+    /// including the restart marker and quantization table headers
     pub fn header_len() -> usize {
         field::PAYLOAD.start
     }
 
+    /// Size of all of the headers preceding the scan data
+    pub fn headers_len(&self) -> usize {
+        let mut len = self.qtable_header_start();
+        if self.has_qtable_header() {
+            len += QTABLE_HEADER_SIZE + usize::from(self.qtable_length());
+        }
+        len
+    }
+
+    /// Type 64-127, a restart marker header follows the main header
+    #[inline]
+    pub fn has_restart_header(&self) -> bool {
+        self.typ() >= TYPE_RESTART_OFFSET && self.typ() < 2 * TYPE_RESTART_OFFSET
+    }
+
+    /// Q 128-255 in the first packet of a frame, a quantization table
+    /// header follows the main and restart marker headers
+    #[inline]
+    pub fn has_qtable_header(&self) -> bool {
+        self.qvalue() >= QVALUE_DYNAMIC_MIN && self.fragment_offset() == 0
+    }
+
+    /// Type without the restart marker offset
+    #[inline]
+    pub fn base_type(&self) -> u8 {
+        if self.has_restart_header() {
+            self.typ() - TYPE_RESTART_OFFSET
+        } else {
+            self.typ()
+        }
+    }
+
+    /// Number of MCUs between restart markers
+    #[inline]
+    pub fn restart_interval(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        BigEndian::read_u16(&data[field::RESTART_INTERVAL])
+    }
+
+    /// Packet starts with the first restart interval of a chunk
+    #[inline]
+    pub fn restart_first(&self) -> bool {
+        let data = self.buffer.as_ref();
+        (data[field::RESTART_COUNT.start] >> 7) & 0x01 != 0
+    }
+
+    /// Packet ends with the last restart interval of a chunk
+    #[inline]
+    pub fn restart_last(&self) -> bool {
+        let data = self.buffer.as_ref();
+        (data[field::RESTART_COUNT.start] >> 6) & 0x01 != 0
+    }
+
+    #[inline]
+    pub fn restart_count(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        BigEndian::read_u16(&data[field::RESTART_COUNT]) & 0x3FFF
+    }
+
+    /// Precision of the quantization tables, bit N is set when table N
+    /// has 16-bit values
+    #[inline]
+    pub fn qtable_precision(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[self.qtable_header_start() + field::QT_PRECISION]
+    }
+
+    /// Size of the quantization tables in bytes, zero when the tables
+    /// weren't sent
+    #[inline]
+    pub fn qtable_length(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        let start = self.qtable_header_start();
+        BigEndian::read_u16(&data[start + field::QT_LENGTH.start..start + field::QT_LENGTH.end])
+    }
+
+    #[inline]
+    fn qtable_header_start(&self) -> usize {
+        if self.has_restart_header() {
+            field::PAYLOAD.start + RESTART_MARKER_SIZE
+        } else {
+            field::PAYLOAD.start
+        }
+    }
+
     #[inline]
     pub fn type_specific(&self) -> u8 {
         let data = self.buffer.as_ref();
@@ -138,10 +264,104 @@ impl<T: AsRef<[u8]>> Header<T> {
 }
 
 impl<'a, T: AsRef<[u8]> + ?Sized> Header<&'a T> {
+    /// Scan data following all of the headers
     #[inline]
     pub fn payload(&self) -> &'a [u8] {
         let data = self.buffer.as_ref();
-        &data[field::PAYLOAD]
+        &data[self.headers_len()..]
+    }
+
+    /// Quantization tables, in zig-zag order, when the packet has a
+    /// quantization table header
+    #[inline]
+    pub fn qtables(&self) -> Option<&'a [u8]> {
+        if self.has_qtable_header() {
+            let data = self.buffer.as_ref();
+            let start = self.qtable_header_start() + QTABLE_HEADER_SIZE;
+            Some(&data[start..start + usize::from(self.qtable_length())])
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Header<T> {
+    #[inline]
+    pub fn set_type_specific(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::TYPE_SPEC] = value;
+    }
+
+    #[inline]
+    pub fn set_fragment_offset(&mut self, value: u32) {
+        let data = self.buffer.as_mut();
+        BigEndian::write_u24(&mut data[field::FRAG_OFFSET], value);
+    }
+
+    #[inline]
+    pub fn set_typ(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::TYPE] = value;
+    }
+
+    #[inline]
+    pub fn set_qvalue(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::QVALUE] = value;
+    }
+
+    /// Sets width in pixels
+    #[inline]
+    pub fn set_width(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        data[field::WIDTH] = (value / WIDTH_HEIGHT_MULT) as u8;
+    }
+
+    /// Sets height in pixels
+    #[inline]
+    pub fn set_height(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        data[field::HEIGHT] = (value / WIDTH_HEIGHT_MULT) as u8;
+    }
+
+    /// Sets the restart marker header, the type must be 64-127
+    #[inline]
+    pub fn set_restart(&mut self, interval: u16, first: bool, last: bool, count: u16) {
+        let data = self.buffer.as_mut();
+        BigEndian::write_u16(&mut data[field::RESTART_INTERVAL], interval);
+        let flags = (if first { 0x8000 } else { 0 }) | (if last { 0x4000 } else { 0 });
+        BigEndian::write_u16(&mut data[field::RESTART_COUNT], flags | (count & 0x3FFF));
+    }
+
+    /// Sets the quantization table header, the Q value must be 128-255
+    /// and the fragment offset 0
+    #[inline]
+    pub fn set_qtable_header(&mut self, precision: u8, length: u16) {
+        let start = self.qtable_header_start();
+        let data = self.buffer.as_mut();
+        data[start + field::QT_MBZ] = 0;
+        data[start + field::QT_PRECISION] = precision;
+        BigEndian::write_u16(
+            &mut data[start + field::QT_LENGTH.start..start + field::QT_LENGTH.end],
+            length,
+        );
+    }
+
+    /// Quantization tables, the header must be set first
+    #[inline]
+    pub fn qtables_mut(&mut self) -> &mut [u8] {
+        let start = self.qtable_header_start() + QTABLE_HEADER_SIZE;
+        let end = start + usize::from(self.qtable_length());
+        let data = self.buffer.as_mut();
+        &mut data[start..end]
+    }
+
+    /// Scan data following all of the headers
+    #[inline]
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        let start = self.headers_len();
+        let data = self.buffer.as_mut();
+        &mut data[start..]
     }
 }
 
@@ -177,4 +397,69 @@ mod tests {
         assert_eq!(h.payload().len(), PAYLOAD_BYTES.len());
         assert_eq!(h.payload(), &PAYLOAD_BYTES[..]);
     }
+
+    #[test]
+    fn restart_and_qtable_headers() {
+        let mut bytes = [0; 8 + 4 + 4 + 128 + 2];
+        {
+            let mut h = Header::new_unchecked(&mut bytes[..]);
+            h.set_typ(65);
+            h.set_qvalue(255);
+            h.set_width(640);
+            h.set_height(480);
+            h.set_restart(8, true, true, 0x3FFF);
+            h.set_qtable_header(0, 128);
+            h.qtables_mut()[0] = 0x10;
+            h.payload_mut().copy_from_slice(&[0xAB, 0xCD]);
+        }
+        let h = Header::new_checked(&bytes[..]).unwrap();
+        assert_eq!(h.base_type(), 1);
+        assert_eq!(h.restart_interval(), 8);
+        assert!(h.restart_first() && h.restart_last());
+        assert_eq!(h.restart_count(), 0x3FFF);
+        assert_eq!(h.qtable_precision(), 0);
+        assert_eq!(h.qtables().unwrap().len(), 128);
+        assert_eq!(h.qtables().unwrap()[0], 0x10);
+        assert_eq!(h.headers_len(), bytes.len() - 2);
+        assert_eq!(h.payload(), &[0xAB, 0xCD]);
+
+        // Tables run past the end of the packet
+        assert_eq!(
+            Header::new_checked(&bytes[..100]).err(),
+            Some(Error::Truncated)
+        );
+
+        // Only the first packet carries the tables
+        let mut h = Header::new_unchecked(&mut bytes[..]);
+        h.set_fragment_offset(1420);
+        let h = Header::new_checked(&bytes[..]).unwrap();
+        assert_eq!(h.qtables(), None);
+        assert_eq!(h.payload().len(), bytes.len() - 12);
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut bytes = PACKET_BYTES;
+        bytes[4] = 2;
+        assert_eq!(Header::new_checked(&bytes[..]).err(), Some(Error::Type));
+        bytes[4] = 129;
+        assert_eq!(Header::new_checked(&bytes[..]).err(), Some(Error::Type));
+        bytes[4] = 1;
+        bytes[5] = 0;
+        assert_eq!(Header::new_checked(&bytes[..]).err(), Some(Error::QValue));
+    }
+
+    #[test]
+    fn construct() {
+        let mut bytes = [0xA5; 36];
+        let mut h = Header::new_unchecked(&mut bytes[..]);
+        h.set_type_specific(0);
+        h.set_fragment_offset(1420);
+        h.set_typ(1);
+        h.set_qvalue(63);
+        h.set_width(640);
+        h.set_height(480);
+        h.payload_mut().copy_from_slice(&PAYLOAD_BYTES[..]);
+        assert_eq!(&bytes[..], &PACKET_BYTES[..]);
+    }
 }
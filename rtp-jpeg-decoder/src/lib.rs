@@ -5,23 +5,36 @@
 
 #![no_std]
 
+use crate::coverage::Coverage;
 use crate::header::Header;
 use byteorder::{BigEndian, ByteOrder};
+use core::ops::Range;
 use log::{trace, warn};
 pub use nanojpeg_rs::{ImageInfo, NanoJPeg};
 pub use rtp;
+use rtp::{Depacketizer, Frame, Statistics};
 
+pub mod convert;
+mod coverage;
 pub mod header;
+pub mod packetizer;
+pub mod planar;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Error {
     BadFirstPacket,
     StorageOverflow,
     TableSize,
-    DroppedSequence,
+    /// Q 128-255 frame without in-band or previously received tables
+    MissingQTables,
+    /// A fragment partially overlaps previously received data
+    FragmentOverlap,
+    /// Too many holes in the frame being reassembled
+    FragmentRanges,
     RtpPayloadType(u8),
     Header(header::Error),
     Decoder(nanojpeg_rs::Error),
+    Planar(planar::Error),
 }
 
 /// JPEG payload type
@@ -29,14 +42,59 @@ pub enum Error {
 /// [RFC189](https://tools.ietf.org/html/rfc1890)
 pub const RTP_PAYLOAD_TYPE_JPEG: u8 = 26;
 
+/// Space reserved at the front of the defragmentation storage for the
+/// generated JFIF headers.
+/// Fragments are stored after it, at their fragment offset.
+pub const HEADERS_RESERVED_SIZE: usize = 1024;
+
+/// A fragment further behind the current frame than this many frame
+/// intervals isn't late, the source restarted with a new timestamp base
+pub const RESYNC_FRAME_INTERVALS: u32 = 4;
+
+/// Frame interval assumed until two frames have been seen, 30 fps at the
+/// 90 kHz RTP clock
+pub const DEFAULT_FRAME_INTERVAL: u32 = 3000;
+
+/// Main JPEG header fields, the same in every fragment of a frame
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct FrameHeader {
+    /// Type without the restart marker offset
+    typ: u8,
+    qvalue: u8,
+    width: u16,
+    height: u16,
+    /// Zero when there are no restart markers
+    restart_interval: u16,
+}
+
+/// Quantization tables received in-band, natural order
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct QTables {
+    qvalue: u8,
+    luma: [u8; 64],
+    chroma: [u8; 64],
+}
+
 #[derive(Debug)]
 pub struct JPEGDecoder<'b> {
     dec: NanoJPeg,
     dec_count: usize,
-    /// Waits for the first packet with MARKER bit set, starts
-    /// decoding on the following first frame packet
-    first_marker_found: bool,
-    last_seq_num: u16,
+    payload_type: u8,
+    stats: Statistics,
+    /// SSRC of the stream, a new source restarts reassembly
+    ssrc: Option<u32>,
+    /// RTP timestamp of the frame being reassembled
+    timestamp: Option<u32>,
+    /// Timestamp increment between the last two frames
+    frame_interval: Option<u32>,
+    frame_header: Option<FrameHeader>,
+    /// Size of the scan data, known once the MARKER packet is received
+    frame_size: Option<usize>,
+    frame_complete: bool,
+    /// Kept across frames, a Q 128-255 frame may reuse the tables of a
+    /// previous frame with the same Q
+    qtables: Option<QTables>,
+    coverage: Coverage,
     buffered: usize,
     buffer: &'b mut [u8],
 }
@@ -44,7 +102,7 @@ pub struct JPEGDecoder<'b> {
 impl<'b> JPEGDecoder<'b> {
     pub fn new(dec: NanoJPeg, defrag_storage: &'b mut [u8]) -> Result<Self, Error> {
         // TODO - update this
-        let min_size = 1024;
+        let min_size = HEADERS_RESERVED_SIZE + 1024;
         if defrag_storage.len() < min_size {
             return Err(Error::StorageOverflow);
         }
@@ -52,118 +110,301 @@ impl<'b> JPEGDecoder<'b> {
         Ok(JPEGDecoder {
             dec,
             dec_count: 0,
-            first_marker_found: false,
-            last_seq_num: 0,
+            payload_type: RTP_PAYLOAD_TYPE_JPEG,
+            stats: Statistics::new(),
+            ssrc: None,
+            timestamp: None,
+            frame_interval: None,
+            frame_header: None,
+            frame_size: None,
+            frame_complete: false,
+            qtables: None,
+            coverage: Coverage::new(),
             buffered: 0,
             buffer: defrag_storage,
         })
     }
 
     pub fn reset(&mut self) {
-        self.first_marker_found = false;
+        self.ssrc = None;
+        self.timestamp = None;
+        self.frame_interval = None;
+        self.frame_header = None;
+        self.frame_size = None;
+        self.frame_complete = false;
+        self.qtables = None;
+        self.coverage.clear();
         self.buffered = 0;
-        self.last_seq_num = 0;
         self.dec_count = 0;
+        self.stats = Statistics::new();
     }
 
     pub fn decode(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<ImageInfo>, Error> {
+        if let Some(frame) = self.defrag(packet)? {
+            // Run it through the nanojpeg decoder
+            let info = self.dec.decode(&self.buffer[frame])?;
+            self.dec_count = self.dec_count.wrapping_add(1);
+            Ok(Some(info))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Like `decode`, but leaves the image as Y/Cb/Cr planes in `storage`
+    /// for `convert` to do the colorspace conversion and scaling.
+    ///
+    /// `planar::storage_size` returns the storage needed for a frame.
+    pub fn decode_planes<'p>(
+        &mut self,
+        packet: &rtp::Packet<&[u8]>,
+        storage: &'p mut [u8],
+    ) -> Result<Option<convert::Planes<'p>>, Error> {
+        if let Some(frame) = self.defrag(packet)? {
+            let planes = planar::decode(&self.buffer[frame], storage)?;
+            self.dec_count = self.dec_count.wrapping_add(1);
+            Ok(Some(planes))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reassembles the frame without decoding it.
+    ///
+    /// Returns the complete JFIF byte stream (SOI through EOI) once all of
+    /// the frame's fragments have been received, suitable for saving,
+    /// forwarding or handing to another decoder.
+    /// The slice is only valid until the next packet is pushed.
+    pub fn reassemble(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<&[u8]>, Error> {
+        if let Some(frame) = self.defrag(packet)? {
+            Ok(Some(&self.buffer[frame]))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn decoded_count(&self) -> usize {
+        self.dec_count
+    }
+
+    /// Number of incomplete frames that were discarded
+    pub fn dropped_count(&self) -> usize {
+        self.stats.dropped_frames
+    }
+
+    /// Places the packet's fragment at its fragment offset.
+    ///
+    /// Returns the range of the complete JFIF image within the storage
+    /// once all of the frame's fragments have been received.
+    fn defrag(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<Range<usize>>, Error> {
         // TODO - check len and version on packet
+        self.stats.packets = self.stats.packets.wrapping_add(1);
 
         let rtp_payload_type = packet.payload_type();
-        if rtp_payload_type != RTP_PAYLOAD_TYPE_JPEG {
+        if rtp_payload_type != self.payload_type {
             return Err(Error::RtpPayloadType(rtp_payload_type));
         }
 
         trace!("sequence_number: {}", packet.sequence_number());
-        trace!("prev sequence_number: {}", self.last_seq_num);
         trace!("timestamp: {}", packet.timestamp());
 
-        if !self.first_marker_found {
-            if packet.contains_marker() {
-                self.first_marker_found = true;
-                self.buffered = 0;
-                self.last_seq_num = packet.sequence_number();
-                trace!("Found marker, starting to buffer");
+        let hdr = Header::new_checked(packet.payload())?;
+        let timestamp = packet.timestamp();
+
+        let ssrc = packet.sync_source();
+        match self.ssrc {
+            Some(prev) if prev != ssrc => {
+                warn!("SSRC changed from 0x{:X} to 0x{:X}, resyncing", prev, ssrc);
+                self.resync();
             }
-            Ok(None)
-        } else {
-            let hdr = Header::new_checked(packet.payload())?;
+            _ => (),
+        }
+        self.ssrc = Some(ssrc);
 
-            trace!("fragment_offset {}", hdr.fragment_offset());
+        let resync_threshold =
+            RESYNC_FRAME_INTERVALS * self.frame_interval.unwrap_or(DEFAULT_FRAME_INTERVAL);
 
-            if self.buffered == 0 && hdr.fragment_offset() != 0 {
+        match self.timestamp {
+            Some(ts) if ts == timestamp => {
+                if self.frame_complete {
+                    trace!("Frame {} already complete, ignoring fragment", ts);
+                    return Ok(None);
+                }
+            }
+            Some(ts)
+                if (timestamp.wrapping_sub(ts) as i32) < 0
+                    && ts.wrapping_sub(timestamp) <= resync_threshold =>
+            {
                 warn!(
-                    "Buffered count {} does not match fragment offset {}, resetting",
-                    self.buffered,
-                    hdr.fragment_offset()
+                    "Late fragment with timestamp {}, current frame is {}, ignoring",
+                    timestamp, ts
                 );
-                // Wait until next frame
-                self.reset();
-                // TODO - return?
-                // only an issue if seq num doesn't match up?
+                return Ok(None);
             }
-
-            if packet.sequence_number() != self.last_seq_num.wrapping_add(1) {
+            Some(ts) if (timestamp.wrapping_sub(ts) as i32) < 0 => {
                 warn!(
-                    "Discontiguous sequence number {}, expected {}, resetting",
-                    packet.sequence_number(),
-                    self.last_seq_num.wrapping_add(1)
+                    "Timestamp jumped back from {} to {}, resyncing",
+                    ts, timestamp
                 );
-                self.reset();
-                return Err(Error::DroppedSequence);
+                self.resync();
+                self.start_frame(timestamp, &hdr);
+            }
+            _ => {
+                if let Some(ts) = self.timestamp {
+                    self.drop_incomplete_frame();
+                    self.frame_interval = Some(timestamp.wrapping_sub(ts));
+                }
+                self.start_frame(timestamp, &hdr);
             }
+        }
 
-            self.last_seq_num = packet.sequence_number();
+        if let Some(tables) = hdr.qtables() {
+            self.store_qtables(hdr.qvalue(), hdr.qtable_precision(), tables)?;
+        }
 
-            // Generate q tables and headers when first packet is recv'd
-            if self.buffered == 0 {
-                self.generate_headers(&hdr)?;
-                trace!("Generated headers, buffered {}", self.buffered);
-            }
+        let offset = hdr.fragment_offset() as usize;
+        let payload = hdr.payload();
+        let payload_size = payload.len();
 
-            // Buffer the fragment
-            let payload_size = hdr.payload().len();
-            if (self.buffered + payload_size) > self.buffer.len() {
-                return Err(Error::StorageOverflow);
-            }
-            self.buffer[self.buffered..self.buffered + payload_size].copy_from_slice(hdr.payload());
-            self.buffered += payload_size;
+        trace!("fragment_offset {}", offset);
+        trace!("payload_size {}", payload_size);
 
-            trace!("payload_size {}", payload_size);
-            trace!("buffered {}", self.buffered);
+        // Leave room for the EOI marker
+        let start = HEADERS_RESERVED_SIZE + offset;
+        if (start + payload_size + EOI_SIZE) > self.buffer.len() {
+            return Err(Error::StorageOverflow);
+        }
+
+        if !self.coverage.insert(offset, offset + payload_size)? {
+            trace!("Duplicate fragment at offset {}, ignoring", offset);
+            return Ok(None);
+        }
 
-            if packet.contains_marker() {
-                trace!("Found marker, fragment_offset {}", hdr.fragment_offset());
-                //trace!("end-1 = 0x{:X}", self.buffer[self.buffered - 2]);
-                //trace!("end = 0x{:X}", self.buffer[self.buffered - 1]);
+        // Buffer the fragment
+        self.buffer[start..start + payload_size].copy_from_slice(payload);
 
-                // EOI
-                self.write_u8(0xFF)?;
-                self.write_segment(EOI, None)?;
+        if packet.contains_marker() {
+            trace!("Found marker, fragment_offset {}", offset);
+            self.frame_size = Some(offset + payload_size);
+        }
 
-                // Reset
-                let buffer_size = self.buffered;
-                self.buffered = 0;
+        match self.frame_size {
+            Some(size) if self.coverage.is_contiguous(size) => {
+                let frame = self.finish_frame(size)?;
+                self.frame_complete = true;
+                self.stats.frames = self.stats.frames.wrapping_add(1);
+                Ok(Some(frame))
+            }
+            _ => Ok(None),
+        }
+    }
 
-                // Run it through the nanojpeg decoder
-                let info = self.dec.decode(&self.buffer[..buffer_size])?;
-                self.dec_count = self.dec_count.wrapping_add(1);
+    /// Abandons the frame being reassembled and forgets the stream's
+    /// timing, e.g. the source restarted
+    fn resync(&mut self) {
+        self.drop_incomplete_frame();
+        self.timestamp = None;
+        self.frame_interval = None;
+        self.frame_header = None;
+        self.frame_size = None;
+        self.frame_complete = false;
+        self.qtables = None;
+        self.coverage.clear();
+    }
 
-                return Ok(Some(info));
+    fn drop_incomplete_frame(&mut self) {
+        if let Some(ts) = self.timestamp {
+            if !self.frame_complete {
+                warn!("Incomplete frame {}, dropping", ts);
+                self.stats.dropped_frames = self.stats.dropped_frames.wrapping_add(1);
             }
+        }
+    }
 
-            Ok(None)
+    fn start_frame(&mut self, timestamp: u32, hdr: &Header<&[u8]>) {
+        self.timestamp = Some(timestamp);
+        self.frame_header = Some(FrameHeader {
+            typ: hdr.base_type(),
+            qvalue: hdr.qvalue(),
+            width: hdr.width(),
+            height: hdr.height(),
+            restart_interval: if hdr.has_restart_header() {
+                hdr.restart_interval()
+            } else {
+                0
+            },
+        });
+        self.frame_size = None;
+        self.frame_complete = false;
+        self.coverage.clear();
+    }
+
+    /// Keeps the luma and chroma tables of the quantization table header,
+    /// a zero length means the previously received tables are reused
+    fn store_qtables(&mut self, qvalue: u8, precision: u8, tables: &[u8]) -> Result<(), Error> {
+        if tables.is_empty() {
+            return Ok(());
+        }
+        // Only 8-bit tables
+        if precision != 0 || tables.len() != LUMA_QTABLE.len() + CHROMA_QTABLE.len() {
+            return Err(Error::TableSize);
+        }
+
+        let mut qtables = QTables {
+            qvalue,
+            luma: [0; 64],
+            chroma: [0; 64],
+        };
+        let (luma, chroma) = tables.split_at(LUMA_QTABLE.len());
+        for (zz, &i) in UNZIGZAG.iter().enumerate() {
+            qtables.luma[usize::from(i)] = luma[zz];
+            qtables.chroma[usize::from(i)] = chroma[zz];
         }
+        self.qtables = Some(qtables);
+        Ok(())
     }
 
-    pub fn decoded_count(&self) -> usize {
-        self.dec_count
+    /// Generates the headers in front of the scan data and appends
+    /// the EOI marker
+    fn finish_frame(&mut self, size: usize) -> Result<Range<usize>, Error> {
+        let frame_header = self.frame_header.ok_or(Error::BadFirstPacket)?;
+
+        // Generate q tables and headers at the start of the storage
+        self.buffered = 0;
+        self.generate_headers(&frame_header)?;
+        let headers_size = self.buffered;
+        if headers_size > HEADERS_RESERVED_SIZE {
+            return Err(Error::StorageOverflow);
+        }
+        trace!("Generated headers, size {}", headers_size);
+
+        // Move them up against the scan data
+        let frame_start = HEADERS_RESERVED_SIZE - headers_size;
+        self.buffer.copy_within(..headers_size, frame_start);
+
+        // EOI
+        self.buffered = HEADERS_RESERVED_SIZE + size;
+        self.write_u8(0xFF)?;
+        self.write_segment(EOI, None)?;
+
+        trace!("buffered {}", self.buffered - frame_start);
+
+        Ok(frame_start..self.buffered)
     }
 
-    fn generate_headers(&mut self, hdr: &Header<&[u8]>) -> Result<(), Error> {
+    fn generate_headers(&mut self, hdr: &FrameHeader) -> Result<(), Error> {
         let mut lqt = [0; 64];
         let mut cqt = [0; 64];
-        make_tables(hdr.qvalue(), &mut lqt, &mut cqt)?;
+        if hdr.qvalue >= header::QVALUE_DYNAMIC_MIN {
+            match self.qtables {
+                Some(t) if t.qvalue == hdr.qvalue => {
+                    lqt = t.luma;
+                    cqt = t.chroma;
+                }
+                _ => return Err(Error::MissingQTables),
+            }
+        } else {
+            make_tables(hdr.qvalue, &mut lqt, &mut cqt)?;
+        }
 
         self.write_segment(SOI, None)?;
 
@@ -173,7 +414,7 @@ impl<'b> JPEGDecoder<'b> {
         }
 
         // SOF
-        self.generate_frame_header(8, hdr.width(), hdr.height(), hdr.typ())?;
+        self.generate_frame_header(8, hdr.width, hdr.height, hdr.typ)?;
 
         // DHT's
         self.generate_huffman_header(
@@ -201,6 +442,12 @@ impl<'b> JPEGDecoder<'b> {
             &STD_CHROMA_AC_VALUES,
         )?;
 
+        // DRI
+        if hdr.restart_interval != 0 {
+            self.write_segment(DRI, Some(2))?;
+            self.write_u16(hdr.restart_interval)?;
+        }
+
         // SOS
         self.generate_scan_header()?;
         Ok(())
@@ -331,6 +578,38 @@ impl<'b> JPEGDecoder<'b> {
     }
 }
 
+impl<'b> Depacketizer for JPEGDecoder<'b> {
+    type Error = Error;
+
+    fn payload_type(&self) -> u8 {
+        self.payload_type
+    }
+
+    fn set_payload_type(&mut self, payload_type: u8) {
+        self.payload_type = payload_type;
+    }
+
+    /// Same as `reassemble`, the frame data is the JFIF byte stream
+    fn push(&mut self, packet: &rtp::Packet<&[u8]>) -> Result<Option<Frame<'_>>, Error> {
+        if let Some(frame) = self.defrag(packet)? {
+            Ok(Some(Frame {
+                timestamp: packet.timestamp(),
+                data: &self.buffer[frame],
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn reset(&mut self) {
+        JPEGDecoder::reset(self)
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.stats
+    }
+}
+
 fn make_tables(qvalue: u8, lqt: &mut [u8], cqt: &mut [u8]) -> Result<(), Error> {
     if (lqt.len() != LUMA_QTABLE.len()) || (cqt.len() != CHROMA_QTABLE.len()) {
         return Err(Error::TableSize);
@@ -387,6 +666,15 @@ impl From<nanojpeg_rs::Error> for Error {
     }
 }
 
+impl From<planar::Error> for Error {
+    fn from(e: planar::Error) -> Error {
+        Error::Planar(e)
+    }
+}
+
+/// Fill byte and EOI marker
+const EOI_SIZE: usize = 3;
+
 // Markers
 // Baseline DCT
 static SOF: u8 = 0xC0;
@@ -400,6 +688,8 @@ static EOI: u8 = 0xD9;
 static SOS: u8 = 0xDA;
 // Quantization Tables
 static DQT: u8 = 0xDB;
+// Restart Interval
+static DRI: u8 = 0xDD;
 
 static DCCLASS: u8 = 0;
 static ACCLASS: u8 = 1;
@@ -501,3 +791,251 @@ static STD_CHROMA_AC_VALUES: [u8; 162] = [
     0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
     0xF9, 0xFA,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCAN_DATA_SIZE: usize = 30;
+    const FRAGMENT_SIZE: usize = 10;
+
+    fn scan_data() -> [u8; SCAN_DATA_SIZE] {
+        let mut data = [0; SCAN_DATA_SIZE];
+        for (i, b) in data.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        data
+    }
+
+    /// Builds the RTP packet for fragment `index` of a frame
+    fn fragment(index: usize, timestamp: u32, buffer: &mut [u8; 64]) -> usize {
+        let offset = index * FRAGMENT_SIZE;
+        let marker = offset + FRAGMENT_SIZE == SCAN_DATA_SIZE;
+        buffer[0] = 0x80;
+        buffer[1] = RTP_PAYLOAD_TYPE_JPEG | if marker { 0x80 } else { 0 };
+        BigEndian::write_u16(&mut buffer[2..4], index as u16);
+        BigEndian::write_u32(&mut buffer[4..8], timestamp);
+        BigEndian::write_u32(&mut buffer[8..12], 0x2713_17D1);
+        let jpeg = &mut buffer[rtp::HEADER_SIZE..];
+        BigEndian::write_u32(&mut jpeg[0..4], offset as u32);
+        jpeg[4..8].copy_from_slice(&[1, 63, 80, 60]);
+        let payload = &mut jpeg[header::MAIN_SIZE..header::MAIN_SIZE + FRAGMENT_SIZE];
+        payload.copy_from_slice(&scan_data()[offset..offset + FRAGMENT_SIZE]);
+        rtp::HEADER_SIZE + header::MAIN_SIZE + FRAGMENT_SIZE
+    }
+
+    fn push(dec: &mut JPEGDecoder, index: usize, timestamp: u32) -> Option<Range<usize>> {
+        push_from(dec, index, timestamp, 0x2713_17D1)
+    }
+
+    fn push_from(
+        dec: &mut JPEGDecoder,
+        index: usize,
+        timestamp: u32,
+        ssrc: u32,
+    ) -> Option<Range<usize>> {
+        let mut buffer = [0; 64];
+        let len = fragment(index, timestamp, &mut buffer);
+        BigEndian::write_u32(&mut buffer[8..12], ssrc);
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+        dec.defrag(&pkt).unwrap()
+    }
+
+    fn check_frame(storage: &[u8], frame: Range<usize>) {
+        let jpeg = &storage[frame.clone()];
+        assert_eq!(frame.end, HEADERS_RESERVED_SIZE + SCAN_DATA_SIZE + EOI_SIZE);
+        assert_eq!(&jpeg[..2], &[0xFF, SOI]);
+        assert_eq!(&jpeg[jpeg.len() - EOI_SIZE..], &[0xFF, 0xFF, EOI]);
+        assert_eq!(
+            &storage[HEADERS_RESERVED_SIZE..HEADERS_RESERVED_SIZE + SCAN_DATA_SIZE],
+            &scan_data()[..]
+        );
+    }
+
+    #[test]
+    fn reassemble_in_order() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        assert_eq!(push(&mut dec, 0, 1000), None);
+        assert_eq!(push(&mut dec, 1, 1000), None);
+        let frame = push(&mut dec, 2, 1000).unwrap();
+        // Duplicates of a completed frame are ignored
+        assert_eq!(push(&mut dec, 2, 1000), None);
+        assert_eq!(dec.dropped_count(), 0);
+        check_frame(dec.buffer, frame);
+    }
+
+    #[test]
+    fn reassemble_reordered() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        assert_eq!(push(&mut dec, 2, 1000), None);
+        assert_eq!(push(&mut dec, 0, 1000), None);
+        let frame = push(&mut dec, 1, 1000).unwrap();
+        assert_eq!(dec.dropped_count(), 0);
+        check_frame(dec.buffer, frame);
+    }
+
+    #[test]
+    fn reassemble_jfif() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        let mut buffer = [0; 64];
+        for index in 0..2 {
+            let len = fragment(index, 1000, &mut buffer);
+            let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+            assert_eq!(dec.reassemble(&pkt), Ok(None));
+        }
+        let len = fragment(2, 1000, &mut buffer);
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+        let jpeg = dec.reassemble(&pkt).unwrap().unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, SOI]);
+        assert_eq!(&jpeg[jpeg.len() - 2..], &[0xFF, EOI]);
+        let scan = &jpeg[jpeg.len() - EOI_SIZE - SCAN_DATA_SIZE..jpeg.len() - EOI_SIZE];
+        assert_eq!(scan, &scan_data()[..]);
+        assert_eq!(dec.decoded_count(), 0);
+    }
+
+    #[test]
+    fn decode_planes() {
+        // 16x16 4:2:0 at Q 50, DC coefficients only
+        let (luma, cb, cr) = ([4, -4, 8, 0], 8, -8);
+        let mut scan = [0; 64];
+        let mut w = planar::tests::BitWriter::new(&mut scan);
+        let mut pred = 0;
+        for &dc in luma.iter() {
+            w.block(true, dc - pred, &[]);
+            pred = dc;
+        }
+        w.block(false, cb, &[]);
+        w.block(false, cr, &[]);
+        let scan_len = w.finish();
+
+        let mut buffer = [0; 128];
+        buffer[0] = 0x80;
+        buffer[1] = RTP_PAYLOAD_TYPE_JPEG | 0x80;
+        let jpeg = &mut buffer[rtp::HEADER_SIZE..];
+        jpeg[4..8].copy_from_slice(&[1, 50, 2, 2]);
+        jpeg[header::MAIN_SIZE..header::MAIN_SIZE + scan_len].copy_from_slice(&scan[..scan_len]);
+        let len = rtp::HEADER_SIZE + header::MAIN_SIZE + scan_len;
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        let mut plane_storage = [0; 384];
+        assert_eq!(
+            planar::storage_size(16, 16, convert::Subsampling::Yuv420),
+            plane_storage.len()
+        );
+        let planes = dec
+            .decode_planes(&pkt, &mut plane_storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dec.decoded_count(), 1);
+
+        // Luma DC quantizer is 16, chroma 17
+        let (cb, cr) = (128 + (cb * 17 / 8), 128 + (cr * 17 / 8));
+        let mut fb = [0_u32; 4];
+        let mut dst =
+            convert::FrameBuffer::from_u32(&mut fb, 2, 2, 8, convert::PixelFormat::Rgbx8888)
+                .unwrap();
+        convert::scale_nearest(&planes, &mut dst);
+        for (px, &dc) in fb.iter().zip(luma.iter()) {
+            let rgb = convert::ycbcr_to_rgb((128 + (dc * 2)) as u8, cb as u8, cr as u8);
+            assert_eq!(px.to_le_bytes(), [rgb[0], rgb[1], rgb[2], 0]);
+        }
+    }
+
+    #[test]
+    fn depacketizer() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        let mut buffer = [0; 64];
+
+        let len = fragment(0, 1000, &mut buffer);
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+        Depacketizer::set_payload_type(&mut dec, 98);
+        assert_eq!(
+            Depacketizer::push(&mut dec, &pkt),
+            Err(Error::RtpPayloadType(RTP_PAYLOAD_TYPE_JPEG))
+        );
+        Depacketizer::set_payload_type(&mut dec, RTP_PAYLOAD_TYPE_JPEG);
+
+        for index in 0..2 {
+            let len = fragment(index, 1000, &mut buffer);
+            let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+            assert_eq!(Depacketizer::push(&mut dec, &pkt), Ok(None));
+        }
+        let len = fragment(2, 1000, &mut buffer);
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+        let frame = Depacketizer::push(&mut dec, &pkt).unwrap().unwrap();
+        assert_eq!(frame.timestamp, 1000);
+        assert_eq!(&frame.data[..2], &[0xFF, SOI]);
+
+        let stats = dec.statistics();
+        assert_eq!(stats.packets, 4);
+        assert_eq!(stats.frames, 1);
+        assert_eq!(stats.dropped_frames, 0);
+        Depacketizer::reset(&mut dec);
+        assert_eq!(dec.statistics(), Statistics::new());
+    }
+
+    #[test]
+    fn drop_incomplete_frame() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        assert_eq!(push(&mut dec, 0, 1000), None);
+        assert_eq!(push(&mut dec, 2, 1000), None);
+        // Next frame starts before the missing fragment shows up
+        assert_eq!(push(&mut dec, 0, 4000), None);
+        assert_eq!(dec.dropped_count(), 1);
+        // Late fragment of the old frame is ignored
+        assert_eq!(push(&mut dec, 1, 1000), None);
+        assert_eq!(push(&mut dec, 1, 4000), None);
+        let frame = push(&mut dec, 2, 4000).unwrap();
+        check_frame(dec.buffer, frame);
+    }
+
+    #[test]
+    fn resync_on_timestamp_jump_back() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        for ts in [900_000, 903_000].iter() {
+            for index in 0..3 {
+                push(&mut dec, index, *ts);
+            }
+        }
+        assert_eq!(dec.statistics().frames, 2);
+        assert_eq!(push(&mut dec, 0, 906_000), None);
+
+        // Camera restarted with a lower timestamp base
+        assert_eq!(push(&mut dec, 0, 1000), None);
+        assert_eq!(dec.dropped_count(), 1);
+        assert_eq!(push(&mut dec, 1, 1000), None);
+        let frame = push(&mut dec, 2, 1000).unwrap();
+        check_frame(dec.buffer, frame);
+
+        // Late fragments are still ignored once the interval is known
+        assert_eq!(push(&mut dec, 0, 4000), None);
+        assert_eq!(push(&mut dec, 1, 1000), None);
+        assert_eq!(push(&mut dec, 1, 4000), None);
+        let frame = push(&mut dec, 2, 4000).unwrap();
+        check_frame(dec.buffer, frame);
+        assert_eq!(dec.dropped_count(), 1);
+    }
+
+    #[test]
+    fn resync_on_ssrc_change() {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        assert_eq!(push(&mut dec, 0, 5000), None);
+        assert_eq!(push(&mut dec, 1, 5000), None);
+
+        // New session, the timestamp is only slightly behind
+        assert_eq!(push_from(&mut dec, 0, 4000, 0x1234_5678), None);
+        assert_eq!(dec.dropped_count(), 1);
+        assert_eq!(push_from(&mut dec, 1, 4000, 0x1234_5678), None);
+        let frame = push_from(&mut dec, 2, 4000, 0x1234_5678).unwrap();
+        check_frame(dec.buffer, frame);
+    }
+}
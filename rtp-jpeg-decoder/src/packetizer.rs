@@ -0,0 +1,656 @@
+//! RTP JPEG packetizer
+//!
+//! [RFC2435](https://tools.ietf.org/html/rfc2435#section-3)
+//!
+//! Takes a baseline JFIF image, strips the headers and fragments the scan
+//! data into RTP JPEG payloads.
+//!
+//! NOTE: only supports images that can be described by the RFC:
+//! * 8-bit baseline DCT, 3 components
+//! * YUV 4:2:2 (type 0) or YUV 4:2:0 (type 1) sampling
+//! * the standard Huffman tables from section K.3
+//! * width and height multiples of 8, up to 2040 pixels
+
+use crate::header::{
+    self, Header, QTABLE_HEADER_SIZE, QVALUE_DYNAMIC_MIN, RESTART_MARKER_SIZE, TYPE_RESTART_OFFSET,
+    WIDTH_HEIGHT_MULT,
+};
+use crate::{
+    make_tables, DHT, DQT, DRI, EOI, SOF, SOI, SOS, STD_CHROMA_AC_CODE_LENGTHS,
+    STD_CHROMA_AC_VALUES, STD_CHROMA_DC_CODE_LENGTHS, STD_CHROMA_DC_VALUES,
+    STD_LUMA_AC_CODE_LENGTHS, STD_LUMA_AC_VALUES, STD_LUMA_DC_CODE_LENGTHS, STD_LUMA_DC_VALUES,
+    UNZIGZAG,
+};
+use byteorder::{BigEndian, ByteOrder};
+
+/// Largest width or height that can be represented
+pub const MAX_DIMENSION: u16 = 2040;
+
+/// Largest fragment offset that can be represented (24 bits)
+pub const MAX_SCAN_SIZE: usize = 0x00FF_FFFF;
+
+/// Q value used when the quantization tables are sent in-band
+pub const QVALUE_DYNAMIC_TABLES: u8 = 255;
+
+const QTABLE_SIZE: usize = 64;
+
+// Markers
+const SOF_PROGRESSIVE: u8 = 0xC2;
+const COM: u8 = 0xFE;
+const TEM: u8 = 0x01;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+const APP0: u8 = 0xE0;
+const APP15: u8 = 0xEF;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    Truncated,
+    /// Missing SOI, SOF, SOS or EOI
+    Syntax,
+    /// Marker that can't be represented, e.g. progressive DCT
+    Marker(u8),
+    Precision,
+    Components,
+    Sampling,
+    Dimensions,
+    QTable,
+    HuffmanTable,
+    /// Scan data is larger than what the fragment offset can address
+    ScanSize,
+    /// Output buffer can't hold the headers and at least one byte of scan
+    /// data
+    BufferTooSmall,
+}
+
+/// An emitted RTP JPEG payload
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Fragment {
+    /// Number of bytes written to the payload buffer
+    pub size: usize,
+    /// The RTP MARKER bit should be set, last fragment of the frame
+    pub marker: bool,
+}
+
+#[derive(Debug)]
+pub struct Packetizer<'a> {
+    typ: u8,
+    qvalue: u8,
+    width: u16,
+    height: u16,
+    restart_interval: u16,
+    /// Luma and chroma tables, zig-zag order
+    qtables: [&'a [u8]; 2],
+    scan: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Packetizer<'a> {
+    /// Parses the JFIF image.
+    ///
+    /// The Q value is derived by matching the image's quantization tables
+    /// against the RFC's scaled tables.
+    /// When they don't match, or when `include_qtables` is set, the tables
+    /// are sent in-band in the first packet (Q = 255).
+    pub fn new(jfif: &'a [u8], include_qtables: bool) -> Result<Self, Error> {
+        let mut p = parse(jfif)?;
+
+        if !include_qtables {
+            if let Some(q) = derive_qvalue(&p.qtables) {
+                p.qvalue = q;
+            }
+        }
+
+        Ok(p)
+    }
+
+    #[inline]
+    pub fn typ(&self) -> u8 {
+        self.typ
+    }
+
+    #[inline]
+    pub fn qvalue(&self) -> u8 {
+        self.qvalue
+    }
+
+    /// Returns width in pixels
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Returns height in pixels
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Size of the scan data, excluding all headers
+    #[inline]
+    pub fn scan_size(&self) -> usize {
+        self.scan.len()
+    }
+
+    /// Returns true once the last fragment has been emitted
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.offset >= self.scan.len()
+    }
+
+    /// Restart from the first fragment
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Emits the next RTP payload into `buffer`, filling as much of it as
+    /// possible.
+    ///
+    /// The length of `buffer` is the maximum payload size, i.e. the MTU
+    /// minus the IP/UDP/RTP headers.
+    pub fn emit_next(&mut self, buffer: &mut [u8]) -> Result<Option<Fragment>, Error> {
+        if self.is_done() {
+            return Ok(None);
+        }
+
+        let include_qtables = self.offset == 0 && self.qvalue >= QVALUE_DYNAMIC_MIN;
+        let mut headers_size = header::MAIN_SIZE;
+        if self.restart_interval != 0 {
+            headers_size += RESTART_MARKER_SIZE;
+        }
+        if include_qtables {
+            headers_size += QTABLE_HEADER_SIZE + (2 * QTABLE_SIZE);
+        }
+
+        if buffer.len() <= headers_size {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let mut hdr = Header::new_unchecked(&mut buffer[..]);
+        hdr.set_type_specific(0);
+        hdr.set_fragment_offset(self.offset as u32);
+        hdr.set_typ(self.typ);
+        hdr.set_qvalue(self.qvalue);
+        hdr.set_width(self.width);
+        hdr.set_height(self.height);
+
+        if self.restart_interval != 0 {
+            // F and L bits set, count = 0x3FFF: restart intervals are
+            // not aligned with packet boundaries
+            hdr.set_restart(self.restart_interval, true, true, 0x3FFF);
+        }
+
+        if include_qtables {
+            // 8-bit precision for both tables
+            hdr.set_qtable_header(0, (2 * QTABLE_SIZE) as u16);
+            for (dst, table) in hdr
+                .qtables_mut()
+                .chunks_mut(QTABLE_SIZE)
+                .zip(self.qtables.iter())
+            {
+                dst.copy_from_slice(table);
+            }
+        }
+
+        let payload = hdr.payload_mut();
+        let remaining = self.scan.len() - self.offset;
+        let chunk = core::cmp::min(remaining, payload.len());
+        payload[..chunk].copy_from_slice(&self.scan[self.offset..self.offset + chunk]);
+        self.offset += chunk;
+
+        Ok(Some(Fragment {
+            size: headers_size + chunk,
+            marker: self.is_done(),
+        }))
+    }
+}
+
+fn parse(jfif: &[u8]) -> Result<Packetizer<'_>, Error> {
+    if jfif.len() < 4 || jfif[0] != 0xFF || jfif[1] != SOI {
+        return Err(Error::Syntax);
+    }
+
+    let mut qtables: [Option<&[u8]>; 4] = [None; 4];
+    let mut frame: Option<(u8, u16, u16, [u8; 2])> = None;
+    let mut restart_interval = 0;
+    let mut pos = 2;
+
+    loop {
+        // Skip any fill bytes
+        while pos < jfif.len() && jfif[pos] == 0xFF {
+            pos += 1;
+        }
+        if pos >= jfif.len() {
+            return Err(Error::Truncated);
+        }
+        if jfif[pos - 1] != 0xFF {
+            return Err(Error::Syntax);
+        }
+        let marker = jfif[pos];
+        pos += 1;
+
+        if marker == TEM || (RST0..=RST7).contains(&marker) {
+            // Standalone markers
+            continue;
+        }
+        if marker == SOI || marker == EOI {
+            return Err(Error::Syntax);
+        }
+
+        if pos + 2 > jfif.len() {
+            return Err(Error::Truncated);
+        }
+        let len = usize::from(BigEndian::read_u16(&jfif[pos..pos + 2]));
+        if len < 2 || pos + len > jfif.len() {
+            return Err(Error::Truncated);
+        }
+        let segment = &jfif[pos + 2..pos + len];
+        pos += len;
+
+        match marker {
+            m if m == DQT => parse_dqt(segment, &mut qtables)?,
+            m if m == SOF => frame = Some(parse_sof(segment)?),
+            m if m == DHT => check_dht(segment)?,
+            m if m == DRI => {
+                if segment.len() != 2 {
+                    return Err(Error::Truncated);
+                }
+                restart_interval = BigEndian::read_u16(segment);
+            }
+            m if m == SOS => {
+                check_sos(segment)?;
+                break;
+            }
+            COM | APP0..=APP15 => (),
+            _ => return Err(Error::Marker(marker)),
+        }
+    }
+
+    let (typ, width, height, table_ids) = frame.ok_or(Error::Syntax)?;
+    let luma = qtables[usize::from(table_ids[0])].ok_or(Error::QTable)?;
+    let chroma = qtables[usize::from(table_ids[1])].ok_or(Error::QTable)?;
+
+    let scan = scan_data(&jfif[pos..])?;
+    if scan.is_empty() {
+        return Err(Error::Syntax);
+    }
+    if scan.len() > MAX_SCAN_SIZE {
+        return Err(Error::ScanSize);
+    }
+
+    Ok(Packetizer {
+        typ: if restart_interval != 0 {
+            typ + TYPE_RESTART_OFFSET
+        } else {
+            typ
+        },
+        qvalue: QVALUE_DYNAMIC_TABLES,
+        width,
+        height,
+        restart_interval,
+        qtables: [luma, chroma],
+        scan,
+        offset: 0,
+    })
+}
+
+fn parse_dqt<'a>(mut segment: &'a [u8], qtables: &mut [Option<&'a [u8]>; 4]) -> Result<(), Error> {
+    while !segment.is_empty() {
+        let pq_tq = segment[0];
+        if (pq_tq >> 4) != 0 {
+            // Only 8-bit tables
+            return Err(Error::Precision);
+        }
+        let id = usize::from(pq_tq & 0x0F);
+        if id >= qtables.len() {
+            return Err(Error::QTable);
+        }
+        if segment.len() < 1 + QTABLE_SIZE {
+            return Err(Error::Truncated);
+        }
+        qtables[id] = Some(&segment[1..1 + QTABLE_SIZE]);
+        segment = &segment[1 + QTABLE_SIZE..];
+    }
+    Ok(())
+}
+
+/// Returns type, width, height and the luma/chroma quantization table ids
+fn parse_sof(segment: &[u8]) -> Result<(u8, u16, u16, [u8; 2]), Error> {
+    if segment.len() < 6 {
+        return Err(Error::Truncated);
+    }
+    if segment[0] != 8 {
+        return Err(Error::Precision);
+    }
+    let height = BigEndian::read_u16(&segment[1..3]);
+    let width = BigEndian::read_u16(&segment[3..5]);
+    if segment[5] != 3 || segment.len() != 6 + (3 * 3) {
+        return Err(Error::Components);
+    }
+
+    let dimension_ok =
+        |d: u16| d != 0 && d <= MAX_DIMENSION && (d / WIDTH_HEIGHT_MULT) * WIDTH_HEIGHT_MULT == d;
+    if !dimension_ok(width) || !dimension_ok(height) {
+        return Err(Error::Dimensions);
+    }
+
+    let comp = |i: usize| &segment[6 + (i * 3)..9 + (i * 3)];
+    let typ = match comp(0)[1] {
+        0x21 => 0,
+        0x22 => 1,
+        _ => return Err(Error::Sampling),
+    };
+    if comp(1)[1] != 0x11 || comp(2)[1] != 0x11 {
+        return Err(Error::Sampling);
+    }
+    // Both chroma components share a table
+    if comp(1)[2] != comp(2)[2] || comp(0)[2] > 3 || comp(1)[2] > 3 {
+        return Err(Error::QTable);
+    }
+
+    Ok((typ, width, height, [comp(0)[2], comp(1)[2]]))
+}
+
+/// The receiver regenerates the standard tables, anything else can't be
+/// represented
+fn check_dht(mut segment: &[u8]) -> Result<(), Error> {
+    while !segment.is_empty() {
+        if segment.len() < 17 {
+            return Err(Error::Truncated);
+        }
+        let (lengths, values): (&[u8], &[u8]) = match segment[0] {
+            0x00 => (&STD_LUMA_DC_CODE_LENGTHS, &STD_LUMA_DC_VALUES),
+            0x10 => (&STD_LUMA_AC_CODE_LENGTHS, &STD_LUMA_AC_VALUES),
+            0x01 => (&STD_CHROMA_DC_CODE_LENGTHS, &STD_CHROMA_DC_VALUES),
+            0x11 => (&STD_CHROMA_AC_CODE_LENGTHS, &STD_CHROMA_AC_VALUES),
+            _ => return Err(Error::HuffmanTable),
+        };
+        let count: usize = segment[1..17].iter().map(|&n| usize::from(n)).sum();
+        if segment.len() < 17 + count {
+            return Err(Error::Truncated);
+        }
+        if &segment[1..17] != lengths || &segment[17..17 + count] != values {
+            return Err(Error::HuffmanTable);
+        }
+        segment = &segment[17 + count..];
+    }
+    Ok(())
+}
+
+fn check_sos(segment: &[u8]) -> Result<(), Error> {
+    if segment.is_empty() || segment[0] != 3 || segment.len() != 1 + (3 * 2) + 3 {
+        return Err(Error::Components);
+    }
+    // Luma uses tables 0, chroma uses tables 1
+    if segment[2] != 0x00 || segment[4] != 0x11 || segment[6] != 0x11 {
+        return Err(Error::HuffmanTable);
+    }
+    Ok(())
+}
+
+/// Returns the entropy-coded data, up to but not including the EOI marker
+/// and any fill bytes preceding it
+fn scan_data(data: &[u8]) -> Result<&[u8], Error> {
+    let mut pos = 0;
+    while pos + 1 < data.len() {
+        if data[pos] == 0xFF {
+            let next = data[pos + 1];
+            if next == 0x00 || (RST0..=RST7).contains(&next) {
+                pos += 2;
+                continue;
+            }
+            // Skip fill bytes
+            let mut end = pos + 1;
+            while end < data.len() && data[end] == 0xFF {
+                end += 1;
+            }
+            return match data.get(end) {
+                Some(&m) if m == EOI => Ok(&data[..pos]),
+                Some(&m) if m == SOS || m == SOF_PROGRESSIVE => Err(Error::Marker(m)),
+                Some(_) => Err(Error::Syntax),
+                None => Err(Error::Truncated),
+            };
+        }
+        pos += 1;
+    }
+    Err(Error::Truncated)
+}
+
+/// Finds the RFC Q value that produces the given tables
+fn derive_qvalue(qtables: &[&[u8]; 2]) -> Option<u8> {
+    let mut lqt = [0; QTABLE_SIZE];
+    let mut cqt = [0; QTABLE_SIZE];
+    (1..=100).find(|&q| {
+        make_tables(q, &mut lqt, &mut cqt).is_ok()
+            && matches_zigzag(&lqt, qtables[0])
+            && matches_zigzag(&cqt, qtables[1])
+    })
+}
+
+fn matches_zigzag(natural: &[u8], zigzag: &[u8]) -> bool {
+    UNZIGZAG
+        .iter()
+        .zip(zigzag.iter())
+        .all(|(&i, &v)| natural[usize::from(i)] == v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JPEGDecoder, NanoJPeg, RTP_PAYLOAD_TYPE_JPEG};
+
+    const SCAN_DATA: [u8; 40] = [
+        0x2F, 0x6F, 0xCF, 0xB7, 0x6E, 0x72, 0xA0, 0xF4, 0xC9, 0xF5, 0xAD, 0xAF, 0x07, 0x6A, 0xD7,
+        0xDF, 0xDA, 0x96, 0x7A, 0x6F, 0x9F, 0xFE, 0x89, 0xF3, 0xFE, 0xEF, 0x62, 0xFF, 0x00, 0x11,
+        0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB,
+    ];
+
+    /// Builds an RTP packet around the next payload, returns the packet
+    /// size and marker
+    fn next_packet(
+        p: &mut Packetizer,
+        seq: u16,
+        max_payload: usize,
+        buffer: &mut [u8],
+    ) -> Option<(usize, bool)> {
+        let payload = &mut buffer[rtp::HEADER_SIZE..rtp::HEADER_SIZE + max_payload];
+        let frag = p.emit_next(payload).unwrap()?;
+        buffer[0] = 0x80;
+        buffer[1] = RTP_PAYLOAD_TYPE_JPEG | if frag.marker { 0x80 } else { 0 };
+        BigEndian::write_u16(&mut buffer[2..4], seq);
+        BigEndian::write_u32(&mut buffer[4..8], 90_000);
+        BigEndian::write_u32(&mut buffer[8..12], 0x2713_17D1);
+        Some((rtp::HEADER_SIZE + frag.size, frag.marker))
+    }
+
+    /// Depacketizes SCAN_DATA into a JFIF image using the decoder's
+    /// generated headers
+    fn make_jfif(storage: &mut [u8], jfif: &mut [u8]) -> usize {
+        make_jfif_with_qvalue(storage, jfif, 63)
+    }
+
+    fn make_jfif_with_qvalue(storage: &mut [u8], jfif: &mut [u8], qvalue: u8) -> usize {
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), storage).unwrap();
+        let mut buffer = [0; 64];
+        buffer[0] = 0x80;
+        buffer[1] = RTP_PAYLOAD_TYPE_JPEG | 0x80;
+        let mut hdr = Header::new_unchecked(&mut buffer[rtp::HEADER_SIZE..]);
+        hdr.set_typ(1);
+        hdr.set_qvalue(qvalue);
+        hdr.set_width(640);
+        hdr.set_height(480);
+        let len = rtp::HEADER_SIZE + header::MAIN_SIZE + SCAN_DATA.len();
+        buffer[rtp::HEADER_SIZE + header::MAIN_SIZE..len].copy_from_slice(&SCAN_DATA);
+        let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+        let frame = dec.reassemble(&pkt).unwrap().unwrap();
+        jfif[..frame.len()].copy_from_slice(frame);
+        frame.len()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let jfif_len = make_jfif(&mut storage, &mut jfif);
+        let jfif = &jfif[..jfif_len];
+
+        let mut p = Packetizer::new(jfif, false).unwrap();
+        assert_eq!(p.typ(), 1);
+        assert_eq!(p.qvalue(), 63);
+        assert_eq!(p.width(), 640);
+        assert_eq!(p.height(), 480);
+        assert_eq!(p.scan_size(), SCAN_DATA.len());
+
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        let mut buffer = [0; 64];
+        let max_payload = header::MAIN_SIZE + 16;
+        let mut seq = 0;
+        let mut frame = None;
+        while let Some((len, marker)) = next_packet(&mut p, seq, max_payload, &mut buffer) {
+            let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+            let hdr = Header::new_checked(pkt.payload()).unwrap();
+            assert_eq!(hdr.fragment_offset(), u32::from(seq) * 16);
+            assert_eq!(marker, p.is_done());
+            if let Some(f) = dec.reassemble(&pkt).unwrap() {
+                assert!(marker);
+                frame = Some(f.len());
+            }
+            seq += 1;
+        }
+        assert_eq!(seq, 3);
+        assert_eq!(frame, Some(jfif_len));
+    }
+
+    #[test]
+    fn in_band_qtables() {
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let jfif_len = make_jfif(&mut storage, &mut jfif);
+
+        let mut p = Packetizer::new(&jfif[..jfif_len], true).unwrap();
+        assert_eq!(p.qvalue(), QVALUE_DYNAMIC_TABLES);
+
+        let mut buffer = [0; 512];
+        let frag = p.emit_next(&mut buffer).unwrap().unwrap();
+        assert!(frag.marker);
+        let hdr = Header::new_checked(&buffer[..frag.size]).unwrap();
+        assert_eq!(hdr.qvalue(), QVALUE_DYNAMIC_TABLES);
+        assert_eq!(hdr.qtable_precision(), 0);
+        assert_eq!(hdr.qtable_length(), 128);
+
+        let mut lqt = [0; QTABLE_SIZE];
+        let mut cqt = [0; QTABLE_SIZE];
+        make_tables(63, &mut lqt, &mut cqt).unwrap();
+        let tables = hdr.qtables().unwrap();
+        assert!(matches_zigzag(&lqt, &tables[..64]));
+        assert!(matches_zigzag(&cqt, &tables[64..]));
+        assert_eq!(hdr.payload(), &SCAN_DATA[..]);
+
+        assert_eq!(p.emit_next(&mut buffer), Ok(None));
+    }
+
+    /// Packetizes the image and reassembles it, returns the size of the
+    /// reassembled image
+    fn packetize_reassemble(p: &mut Packetizer, max_payload: usize, out: &mut [u8]) -> usize {
+        let mut storage = [0; 4096];
+        let mut dec = JPEGDecoder::new(NanoJPeg::init(), &mut storage).unwrap();
+        let mut buffer = [0; 256];
+        let mut seq = 0;
+        while let Some((len, marker)) = next_packet(p, seq, max_payload, &mut buffer) {
+            let pkt = rtp::Packet::new_checked(&buffer[..len]).unwrap();
+            if let Some(frame) = dec.reassemble(&pkt).unwrap() {
+                assert!(marker);
+                out[..frame.len()].copy_from_slice(frame);
+                return frame.len();
+            }
+            seq += 1;
+        }
+        panic!("Frame not reassembled");
+    }
+
+    #[test]
+    fn round_trip_qtables() {
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let jfif_len = make_jfif(&mut storage, &mut jfif);
+        let jfif = &jfif[..jfif_len];
+
+        let mut p = Packetizer::new(jfif, true).unwrap();
+        let mut out = [0; 2048];
+        let max_payload = header::MAIN_SIZE + QTABLE_HEADER_SIZE + 128 + 16;
+        let out_len = packetize_reassemble(&mut p, max_payload, &mut out);
+        // The in-band tables regenerate the same DQT segments
+        assert_eq!(&out[..out_len], jfif);
+        let p = Packetizer::new(&out[..out_len], false).unwrap();
+        assert_eq!(p.qvalue(), 63);
+    }
+
+    #[test]
+    fn round_trip_restart_markers() {
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let len = make_jfif(&mut storage, &mut jfif);
+
+        // Insert a DRI segment in front of the SOS
+        let sos = jfif[..len]
+            .windows(2)
+            .position(|w| w == [0xFF, SOS])
+            .unwrap();
+        let dri = [0xFF, DRI, 0x00, 0x04, 0x00, 0x08];
+        jfif.copy_within(sos..len, sos + dri.len());
+        jfif[sos..sos + dri.len()].copy_from_slice(&dri);
+        let jfif = &jfif[..len + dri.len()];
+
+        let mut p = Packetizer::new(jfif, false).unwrap();
+        assert_eq!(p.typ(), 1 + TYPE_RESTART_OFFSET);
+
+        let mut buffer = [0; 64];
+        let (len, _) = next_packet(&mut p, 0, 32, &mut buffer).unwrap();
+        let hdr = Header::new_checked(&buffer[rtp::HEADER_SIZE..len]).unwrap();
+        assert!(hdr.has_restart_header());
+        assert_eq!(hdr.base_type(), 1);
+        assert_eq!(hdr.restart_interval(), 8);
+        assert_eq!(hdr.payload(), &SCAN_DATA[..32 - 12]);
+        p.reset();
+
+        let mut out = [0; 2048];
+        let out_len = packetize_reassemble(&mut p, 32, &mut out);
+        assert_eq!(&out[..out_len], jfif);
+    }
+
+    #[test]
+    fn qvalue_100() {
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let jfif_len = make_jfif_with_qvalue(&mut storage, &mut jfif, 100);
+        let p = Packetizer::new(&jfif[..jfif_len], false).unwrap();
+        assert_eq!(p.qvalue(), 100);
+    }
+
+    #[test]
+    fn unsupported() {
+        assert_eq!(
+            Packetizer::new(&[0xFF, 0xD9, 0, 0], false).err(),
+            Some(Error::Syntax)
+        );
+
+        let mut storage = [0; 4096];
+        let mut jfif = [0; 2048];
+        let jfif_len = make_jfif(&mut storage, &mut jfif);
+        let sof = jfif[..jfif_len]
+            .windows(2)
+            .position(|w| w == [0xFF, SOF])
+            .unwrap();
+        jfif[sof + 1] = SOF_PROGRESSIVE;
+        assert_eq!(
+            Packetizer::new(&jfif[..jfif_len], false).err(),
+            Some(Error::Marker(SOF_PROGRESSIVE))
+        );
+
+        assert_eq!(
+            Packetizer::new(&jfif[..sof], false).err(),
+            Some(Error::Truncated)
+        );
+    }
+}
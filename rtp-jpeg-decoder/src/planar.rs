@@ -0,0 +1,1009 @@
+//! Baseline JPEG decoding into Y/Cb/Cr planes
+//!
+//! Unlike `NanoJPeg`, the components are left planar at their own
+//! resolution. The colorspace conversion is done separately, together with
+//! scaling, by `convert`.
+//!
+//! Only what RFC2435 can carry is supported: baseline Huffman coded DCT,
+//! three 8-bit components, luma sampled 1x1, 2x1 or 2x2 and chroma 1x1,
+//! and restart intervals.
+
+use crate::convert::{Planes, Subsampling};
+use crate::{clamp, DHT, DQT, DRI, EOI, SOF, SOI, SOS, UNZIGZAG};
+use byteorder::{BigEndian, ByteOrder};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Error {
+    /// The stream ended before the scan
+    Truncated,
+    /// Malformed marker segment or entropy coded data
+    Syntax,
+    /// Valid JPEG, but not a baseline YCbCr image this decoder handles
+    Unsupported,
+    StorageOverflow,
+}
+
+/// Extended sequential DCT, decoded like baseline
+const SOF1: u8 = 0xC1;
+/// Differential/progressive/lossless/arithmetic frames are 0xC0-0xCF,
+/// except DHT, JPG (0xC8) and DAC (0xCC)
+const SOF_FIRST: u8 = 0xC0;
+const SOF_LAST: u8 = 0xCF;
+const JPG: u8 = 0xC8;
+const DAC: u8 = 0xCC;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+
+/// Codes up to this long are decoded with a single table lookup
+const LOOKUP_BITS: u32 = 9;
+
+/// Dequantized coefficients are clamped to the 8-bit sample range, keeps
+/// the IDCT within `i32` on corrupt data
+const COEF_MIN: i32 = -2048;
+const COEF_MAX: i32 = 2047;
+
+/// `round(4096 * C(u) * cos((2x + 1) * u * PI / 16))`, indexed by `x * 8 + u`
+#[rustfmt::skip]
+static IDCT_TABLE: [i32; 64] = [
+    2896,  4017,  3784,  3406,  2896,  2276,  1567,   799,
+    2896,  3406,  1567,  -799, -2896, -4017, -3784, -2276,
+    2896,  2276, -1567, -4017, -2896,   799,  3784,  3406,
+    2896,   799, -3784, -2276,  2896,  3406, -1567, -4017,
+    2896,  -799, -3784,  2276,  2896, -3406, -1567,  4017,
+    2896, -2276, -1567,  4017, -2896,  -799,  3784, -3406,
+    2896, -3406,  1567,   799, -2896,  4017, -3784,  2276,
+    2896, -4017,  3784, -3406,  2896, -2276,  1567,  -799,
+];
+
+/// Bytes of storage `decode` needs for an image, the planes are padded to
+/// whole MCUs
+pub fn storage_size(width: usize, height: usize, subsampling: Subsampling) -> usize {
+    Layout::new(width, height, subsampling).size()
+}
+
+/// Decodes a JFIF image into planes stored in `storage`.
+///
+/// The planes are padded to whole MCUs, `storage_size` returns the
+/// storage needed.
+pub fn decode<'a>(jfif: &[u8], storage: &'a mut [u8]) -> Result<Planes<'a>, Error> {
+    let mut dec = Decoder::new();
+    let (scan, data) = dec.parse(jfif)?;
+
+    let frame = dec.frame.ok_or(Error::Syntax)?;
+    let layout = Layout::new(frame.width, frame.height, frame.subsampling);
+    if storage.len() < layout.size() {
+        return Err(Error::StorageOverflow);
+    }
+
+    let (y, rest) = storage.split_at_mut(layout.y_size());
+    let (cb, rest) = rest.split_at_mut(layout.c_size());
+    let cr = &mut rest[..layout.c_size()];
+    let mut planes = [y, cb, cr];
+
+    dec.decode_scan(&frame, &scan, data, &layout, &mut planes)?;
+
+    let [y, cb, cr] = planes;
+    Planes::new(
+        frame.width,
+        frame.height,
+        frame.subsampling,
+        y,
+        layout.y_stride,
+        cb,
+        cr,
+        layout.c_stride,
+    )
+    .map_err(|_| Error::Syntax)
+}
+
+/// Plane dimensions, in whole MCUs
+#[derive(Debug, Copy, Clone)]
+struct Layout {
+    mcus_x: usize,
+    mcus_y: usize,
+    y_stride: usize,
+    y_rows: usize,
+    c_stride: usize,
+    c_rows: usize,
+}
+
+impl Layout {
+    fn new(width: usize, height: usize, subsampling: Subsampling) -> Self {
+        let (hs, vs) = subsampling.shift();
+        let (mcu_width, mcu_height) = (8 << hs, 8 << vs);
+        let mcus_x = (width + mcu_width - 1) >> (3 + hs);
+        let mcus_y = (height + mcu_height - 1) >> (3 + vs);
+        Layout {
+            mcus_x,
+            mcus_y,
+            y_stride: mcus_x * mcu_width,
+            y_rows: mcus_y * mcu_height,
+            c_stride: mcus_x * 8,
+            c_rows: mcus_y * 8,
+        }
+    }
+
+    fn y_size(&self) -> usize {
+        self.y_stride * self.y_rows
+    }
+
+    fn c_size(&self) -> usize {
+        self.c_stride * self.c_rows
+    }
+
+    fn size(&self) -> usize {
+        self.y_size() + (2 * self.c_size())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Component {
+    id: u8,
+    /// Sampling factors
+    h: usize,
+    v: usize,
+    qtable: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Frame {
+    width: usize,
+    height: usize,
+    subsampling: Subsampling,
+    /// Y, Cb, Cr
+    components: [Component; 3],
+}
+
+/// A component of the scan, in scan order
+#[derive(Debug, Copy, Clone, Default)]
+struct ScanComponent {
+    /// Index into `Frame::components` and the planes
+    index: usize,
+    dc_table: usize,
+    ac_table: usize,
+}
+
+struct Decoder {
+    /// Zigzag order
+    qtables: [[u16; 64]; 4],
+    dc_tables: [HuffmanTable; 2],
+    ac_tables: [HuffmanTable; 2],
+    /// MCUs, zero when there are no restart markers
+    restart_interval: usize,
+    frame: Option<Frame>,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        Decoder {
+            qtables: [[1; 64]; 4],
+            dc_tables: [HuffmanTable::new(); 2],
+            ac_tables: [HuffmanTable::new(); 2],
+            restart_interval: 0,
+            frame: None,
+        }
+    }
+
+    /// Reads the marker segments up to the scan, returns the scan header and
+    /// the data following it
+    fn parse<'d>(&mut self, data: &'d [u8]) -> Result<([ScanComponent; 3], &'d [u8]), Error> {
+        if data.len() < 2 || data[0] != 0xFF || data[1] != SOI {
+            return Err(Error::Syntax);
+        }
+        let mut pos = 2;
+
+        loop {
+            // Skip fill bytes
+            while data.get(pos) == Some(&0xFF) && data.get(pos + 1) == Some(&0xFF) {
+                pos += 1;
+            }
+            if data.len() < pos + 4 {
+                return Err(Error::Truncated);
+            }
+            if data[pos] != 0xFF {
+                return Err(Error::Syntax);
+            }
+            let marker = data[pos + 1];
+            if marker == EOI {
+                return Err(Error::Truncated);
+            }
+            let len = usize::from(BigEndian::read_u16(&data[pos + 2..pos + 4]));
+            if len < 2 {
+                return Err(Error::Syntax);
+            }
+            let segment = data.get(pos + 4..pos + 2 + len).ok_or(Error::Truncated)?;
+            pos += 2 + len;
+
+            if marker == SOS {
+                let scan = self.parse_scan_header(segment)?;
+                return Ok((scan, &data[pos..]));
+            } else if marker == DQT {
+                self.parse_qtables(segment)?;
+            } else if marker == DHT {
+                self.parse_huffman_tables(segment)?;
+            } else if marker == DRI {
+                if segment.len() != 2 {
+                    return Err(Error::Syntax);
+                }
+                self.restart_interval = usize::from(BigEndian::read_u16(segment));
+            } else if marker == SOF || marker == SOF1 {
+                self.parse_frame_header(segment)?;
+            } else if (SOF_FIRST..=SOF_LAST).contains(&marker)
+                && marker != DHT
+                && marker != JPG
+                && marker != DAC
+            {
+                return Err(Error::Unsupported);
+            }
+            // Anything else (APPn, COM, ...) is skipped
+        }
+    }
+
+    fn parse_qtables(&mut self, mut segment: &[u8]) -> Result<(), Error> {
+        while !segment.is_empty() {
+            let precision = segment[0] >> 4;
+            let id = usize::from(segment[0] & 0x0F);
+            let size = if precision == 0 { 64 } else { 128 };
+            if id >= self.qtables.len() || precision > 1 {
+                return Err(Error::Syntax);
+            }
+            let table = segment.get(1..1 + size).ok_or(Error::Syntax)?;
+            for (k, q) in self.qtables[id].iter_mut().enumerate() {
+                *q = if precision == 0 {
+                    u16::from(table[k])
+                } else {
+                    BigEndian::read_u16(&table[2 * k..])
+                };
+            }
+            segment = &segment[1 + size..];
+        }
+        Ok(())
+    }
+
+    fn parse_huffman_tables(&mut self, mut segment: &[u8]) -> Result<(), Error> {
+        while !segment.is_empty() {
+            let class = segment[0] >> 4;
+            let id = usize::from(segment[0] & 0x0F);
+            let counts = segment.get(1..17).ok_or(Error::Syntax)?;
+            let num_values: usize = counts.iter().map(|&c| usize::from(c)).sum();
+            let values = segment.get(17..17 + num_values).ok_or(Error::Syntax)?;
+            let table = match class {
+                0 => self.dc_tables.get_mut(id),
+                1 => self.ac_tables.get_mut(id),
+                _ => return Err(Error::Syntax),
+            }
+            .ok_or(Error::Unsupported)?;
+            table.build(counts, values)?;
+            segment = &segment[17 + num_values..];
+        }
+        Ok(())
+    }
+
+    fn parse_frame_header(&mut self, segment: &[u8]) -> Result<(), Error> {
+        if segment.len() < 6 {
+            return Err(Error::Syntax);
+        }
+        let precision = segment[0];
+        let height = usize::from(BigEndian::read_u16(&segment[1..3]));
+        let width = usize::from(BigEndian::read_u16(&segment[3..5]));
+        let num_components = usize::from(segment[5]);
+        if precision != 8 || num_components != 3 {
+            return Err(Error::Unsupported);
+        }
+        if width == 0 || height == 0 || segment.len() < 6 + (3 * num_components) {
+            return Err(Error::Syntax);
+        }
+
+        let mut components = [Component::default(); 3];
+        for (c, spec) in components.iter_mut().zip(segment[6..].chunks(3)) {
+            c.id = spec[0];
+            c.h = usize::from(spec[1] >> 4);
+            c.v = usize::from(spec[1] & 0x0F);
+            c.qtable = usize::from(spec[2]);
+            if c.qtable >= self.qtables.len() {
+                return Err(Error::Syntax);
+            }
+        }
+
+        if components[1..].iter().any(|c| c.h != 1 || c.v != 1) {
+            return Err(Error::Unsupported);
+        }
+        let subsampling = match (components[0].h, components[0].v) {
+            (1, 1) => Subsampling::Yuv444,
+            (2, 1) => Subsampling::Yuv422,
+            (2, 2) => Subsampling::Yuv420,
+            _ => return Err(Error::Unsupported),
+        };
+
+        self.frame = Some(Frame {
+            width,
+            height,
+            subsampling,
+            components,
+        });
+        Ok(())
+    }
+
+    fn parse_scan_header(&self, segment: &[u8]) -> Result<[ScanComponent; 3], Error> {
+        let frame = self.frame.as_ref().ok_or(Error::Syntax)?;
+        let num_components = usize::from(*segment.first().ok_or(Error::Syntax)?);
+        if num_components != 3 {
+            // Non-interleaved scans
+            return Err(Error::Unsupported);
+        }
+        if segment.len() != 1 + (2 * num_components) + 3 {
+            return Err(Error::Syntax);
+        }
+
+        let mut scan = [ScanComponent::default(); 3];
+        for (s, spec) in scan.iter_mut().zip(segment[1..].chunks(2)) {
+            s.index = frame
+                .components
+                .iter()
+                .position(|c| c.id == spec[0])
+                .ok_or(Error::Syntax)?;
+            s.dc_table = usize::from(spec[1] >> 4);
+            s.ac_table = usize::from(spec[1] & 0x0F);
+            if s.dc_table >= self.dc_tables.len() || s.ac_table >= self.ac_tables.len() {
+                return Err(Error::Unsupported);
+            }
+        }
+
+        // Spectral selection and successive approximation are fixed for
+        // sequential DCT
+        if segment[7..] != [0, 63, 0] {
+            return Err(Error::Unsupported);
+        }
+
+        Ok(scan)
+    }
+
+    fn decode_scan(
+        &self,
+        frame: &Frame,
+        scan: &[ScanComponent; 3],
+        data: &[u8],
+        layout: &Layout,
+        planes: &mut [&mut [u8]; 3],
+    ) -> Result<(), Error> {
+        let mut bits = BitReader::new(data);
+        let mut predictions = [0_i32; 3];
+        let mut block = [0_i32; 64];
+
+        for mcu in 0..(layout.mcus_x * layout.mcus_y) {
+            if self.restart_interval != 0 && mcu != 0 && mcu % self.restart_interval == 0 {
+                bits.restart()?;
+                predictions = [0; 3];
+            }
+
+            let (mcu_x, mcu_y) = (mcu % layout.mcus_x, mcu / layout.mcus_x);
+            for s in scan.iter() {
+                let c = &frame.components[s.index];
+                let stride = if s.index == 0 {
+                    layout.y_stride
+                } else {
+                    layout.c_stride
+                };
+
+                for v in 0..c.v {
+                    for h in 0..c.h {
+                        self.decode_block(s, c, &mut bits, &mut predictions[s.index], &mut block)?;
+                        let x = ((mcu_x * c.h) + h) * 8;
+                        let y = ((mcu_y * c.v) + v) * 8;
+                        idct(&block, &mut planes[s.index][(y * stride) + x..], stride);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes and dequantizes a block into natural order
+    fn decode_block(
+        &self,
+        s: &ScanComponent,
+        c: &Component,
+        bits: &mut BitReader,
+        prediction: &mut i32,
+        block: &mut [i32; 64],
+    ) -> Result<(), Error> {
+        let qtable = &self.qtables[c.qtable];
+        let dequantize = |value: i32, k: usize| {
+            clamp(value.wrapping_mul(i32::from(qtable[k])), COEF_MIN, COEF_MAX)
+        };
+
+        *block = [0; 64];
+
+        let size = self.dc_tables[s.dc_table].decode(bits)?;
+        if size > 15 {
+            return Err(Error::Syntax);
+        }
+        *prediction = prediction.wrapping_add(bits.receive_extend(size));
+        block[0] = dequantize(*prediction, 0);
+
+        let ac_table = &self.ac_tables[s.ac_table];
+        let mut k = 1;
+        while k < 64 {
+            let rs = ac_table.decode(bits)?;
+            let (run, size) = (usize::from(rs >> 4), rs & 0x0F);
+            if size == 0 {
+                if run == 15 {
+                    // ZRL, 16 zeros
+                    k += 16;
+                    continue;
+                }
+                // EOB
+                break;
+            }
+            k += run;
+            if k > 63 {
+                return Err(Error::Syntax);
+            }
+            block[usize::from(UNZIGZAG[k])] = dequantize(bits.receive_extend(size), k);
+            k += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+struct HuffmanTable {
+    /// `length << 8 | value` of the code starting with the next
+    /// `LOOKUP_BITS` bits, zero if the code is longer
+    lookup: [u16; 1 << LOOKUP_BITS],
+    /// Largest code of each length, -1 if there are none
+    max_code: [i32; 17],
+    /// Index of the value of a code, minus the code, for each length
+    value_offset: [i32; 17],
+    values: [u8; 256],
+}
+
+impl HuffmanTable {
+    fn new() -> Self {
+        HuffmanTable {
+            lookup: [0; 1 << LOOKUP_BITS],
+            max_code: [-1; 17],
+            value_offset: [0; 17],
+            values: [0; 256],
+        }
+    }
+
+    /// Builds the canonical codes, section C.2
+    fn build(&mut self, counts: &[u8], values: &[u8]) -> Result<(), Error> {
+        if values.len() > self.values.len() {
+            return Err(Error::Syntax);
+        }
+        *self = HuffmanTable::new();
+        self.values[..values.len()].copy_from_slice(values);
+
+        let mut code = 0_i32;
+        let mut k = 0;
+        for len in 1..=16 {
+            let count = i32::from(counts[len - 1]);
+            self.value_offset[len] = k as i32 - code;
+            for _ in 0..count {
+                if len <= LOOKUP_BITS as usize {
+                    let shift = LOOKUP_BITS as usize - len;
+                    let first = (code as usize) << shift;
+                    let entry = ((len as u16) << 8) | u16::from(values[k]);
+                    for e in self.lookup[first..first + (1 << shift)].iter_mut() {
+                        *e = entry;
+                    }
+                }
+                code += 1;
+                k += 1;
+            }
+            if count != 0 {
+                self.max_code[len] = code - 1;
+            }
+            if code > (1 << len) {
+                return Err(Error::Syntax);
+            }
+            code <<= 1;
+        }
+
+        Ok(())
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u8, Error> {
+        let entry = self.lookup[bits.peek(LOOKUP_BITS) as usize];
+        if entry != 0 {
+            bits.consume(u32::from(entry >> 8));
+            return Ok(entry as u8);
+        }
+
+        for len in (LOOKUP_BITS + 1)..=16 {
+            let code = bits.peek(len) as i32;
+            if code <= self.max_code[len as usize] {
+                bits.consume(len);
+                return Ok(self.values[(self.value_offset[len as usize] + code) as usize]);
+            }
+        }
+
+        Err(Error::Syntax)
+    }
+}
+
+/// Entropy coded data reader, removes the stuffed zero bytes and reads
+/// zeros once a marker is reached
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// MSB aligned
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            pos: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let mut byte = 0;
+            if let Some(&b) = self.data.get(self.pos) {
+                if b != 0xFF {
+                    byte = b;
+                    self.pos += 1;
+                } else if self.data.get(self.pos + 1) == Some(&0) {
+                    byte = 0xFF;
+                    self.pos += 2;
+                }
+            }
+            self.bits |= u32::from(byte) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    /// Next `n` bits, 1 to 16
+    fn peek(&mut self, n: u32) -> u32 {
+        self.fill();
+        self.bits >> (32 - n)
+    }
+
+    fn consume(&mut self, n: u32) {
+        self.bits <<= n;
+        self.count -= n;
+    }
+
+    /// Reads a `size` bit coefficient, section F.2.2.1
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        if size == 0 {
+            return 0;
+        }
+        let size = u32::from(size);
+        let value = self.peek(size) as i32;
+        self.consume(size);
+        if value < (1 << (size - 1)) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    /// Skips the padding bits and the RSTn marker at the end of a restart
+    /// interval
+    fn restart(&mut self) -> Result<(), Error> {
+        // Reading stops at markers, only the padding of the last byte
+        // remains buffered
+        self.bits = 0;
+        self.count = 0;
+        while self.data.get(self.pos) == Some(&0xFF) && self.data.get(self.pos + 1) == Some(&0xFF) {
+            self.pos += 1;
+        }
+        match self.data.get(self.pos..self.pos + 2) {
+            Some(&[0xFF, m]) if (RST0..=RST7).contains(&m) => {
+                self.pos += 2;
+                Ok(())
+            }
+            _ => Err(Error::Syntax),
+        }
+    }
+}
+
+/// Separable integer IDCT of a natural order block, writes the level
+/// shifted samples to `out`, rows are `stride` bytes apart
+fn idct(block: &[i32; 64], out: &mut [u8], stride: usize) {
+    // Rows keep 2 extra bits of precision
+    let mut tmp = [0_i32; 64];
+    for (row, out_row) in block.chunks(8).zip(tmp.chunks_mut(8)) {
+        for (x, t) in out_row.iter_mut().enumerate() {
+            let sum: i32 = row
+                .iter()
+                .zip(&IDCT_TABLE[x * 8..(x + 1) * 8])
+                .map(|(f, c)| f * c)
+                .sum();
+            *t = (sum + (1 << 10)) >> 11;
+        }
+    }
+
+    for x in 0..8 {
+        for y in 0..8 {
+            let sum: i32 = (0..8)
+                .map(|v| tmp[(v * 8) + x] * IDCT_TABLE[(y * 8) + v])
+                .sum();
+            let sample = ((sum + (1 << 14)) >> 15) + 128;
+            out[(y * stride) + x] = clamp(sample, 0, 255) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::convert::Source;
+    use crate::{
+        STD_CHROMA_AC_CODE_LENGTHS, STD_CHROMA_AC_VALUES, STD_CHROMA_DC_CODE_LENGTHS,
+        STD_CHROMA_DC_VALUES, STD_LUMA_AC_CODE_LENGTHS, STD_LUMA_AC_VALUES,
+        STD_LUMA_DC_CODE_LENGTHS, STD_LUMA_DC_VALUES,
+    };
+
+    /// Entropy coded data writer, stuffs zeros after 0xFF
+    pub(crate) struct BitWriter<'a> {
+        out: &'a mut [u8],
+        len: usize,
+        acc: u8,
+        count: u32,
+    }
+
+    impl<'a> BitWriter<'a> {
+        pub(crate) fn new(out: &'a mut [u8]) -> Self {
+            BitWriter {
+                out,
+                len: 0,
+                acc: 0,
+                count: 0,
+            }
+        }
+
+        fn put(&mut self, bits: u32, n: u32) {
+            for i in (0..n).rev() {
+                self.acc = (self.acc << 1) | ((bits >> i) & 1) as u8;
+                self.count += 1;
+                if self.count == 8 {
+                    self.byte(self.acc);
+                    self.acc = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        fn byte(&mut self, b: u8) {
+            self.out[self.len] = b;
+            self.len += 1;
+            if b == 0xFF {
+                self.out[self.len] = 0;
+                self.len += 1;
+            }
+        }
+
+        /// Pads with ones to a byte boundary
+        pub(crate) fn finish(&mut self) -> usize {
+            while self.count != 0 {
+                self.put(1, 1);
+            }
+            self.len
+        }
+
+        fn restart(&mut self, n: u8) {
+            self.finish();
+            self.out[self.len..self.len + 2].copy_from_slice(&[0xFF, RST0 + n]);
+            self.len += 2;
+        }
+
+        fn code(&mut self, counts: &[u8; 16], values: &[u8], value: u8) {
+            let mut code = 0;
+            let mut k = 0;
+            for len in 1..=16 {
+                for _ in 0..counts[len - 1] {
+                    if values[k] == value {
+                        self.put(code, len as u32);
+                        return;
+                    }
+                    code += 1;
+                    k += 1;
+                }
+                code <<= 1;
+            }
+            panic!("No code for {}", value);
+        }
+
+        /// Bits needed for a coefficient, its size category
+        fn size(value: i32) -> u8 {
+            (32 - value.abs().leading_zeros()) as u8
+        }
+
+        /// Encodes a block of quantized coefficients, `ac` holds the
+        /// nonzero ones as (zigzag index, value)
+        pub(crate) fn block(&mut self, luma: bool, dc_diff: i32, ac: &[(usize, i32)]) {
+            let (dc_counts, dc_values, ac_counts, ac_values): (_, &[u8], _, &[u8]) = if luma {
+                (
+                    &STD_LUMA_DC_CODE_LENGTHS,
+                    &STD_LUMA_DC_VALUES,
+                    &STD_LUMA_AC_CODE_LENGTHS,
+                    &STD_LUMA_AC_VALUES,
+                )
+            } else {
+                (
+                    &STD_CHROMA_DC_CODE_LENGTHS,
+                    &STD_CHROMA_DC_VALUES,
+                    &STD_CHROMA_AC_CODE_LENGTHS,
+                    &STD_CHROMA_AC_VALUES,
+                )
+            };
+
+            let size = Self::size(dc_diff);
+            self.code(dc_counts, dc_values, size);
+            self.value(dc_diff, size);
+
+            let mut k = 1;
+            for &(index, value) in ac {
+                let mut run = index - k;
+                while run >= 16 {
+                    self.code(ac_counts, ac_values, 0xF0);
+                    run -= 16;
+                }
+                let size = Self::size(value);
+                self.code(ac_counts, ac_values, ((run as u8) << 4) | size);
+                self.value(value, size);
+                k = index + 1;
+            }
+            if k < 64 {
+                self.code(ac_counts, ac_values, 0x00);
+            }
+        }
+
+        fn value(&mut self, value: i32, size: u8) {
+            let bits = if value < 0 {
+                value + (1 << size) - 1
+            } else {
+                value
+            };
+            self.put(bits as u32, u32::from(size));
+        }
+    }
+
+    /// Builds a JFIF image with all ones quantization tables, so
+    /// coefficients are their quantized values
+    fn jfif(
+        out: &mut [u8],
+        width: u16,
+        height: u16,
+        luma_sampling: u8,
+        restart_interval: u16,
+        scan: &[u8],
+    ) -> usize {
+        let mut len = 0;
+        let mut put = |data: &[u8]| {
+            out[len..len + data.len()].copy_from_slice(data);
+            len += data.len();
+        };
+
+        put(&[0xFF, SOI]);
+        for id in 0..2 {
+            put(&[0xFF, DQT, 0, 67, id]);
+            put(&[1; 64]);
+        }
+        let (w, h) = (width.to_be_bytes(), height.to_be_bytes());
+        put(&[0xFF, SOF, 0, 17, 8, h[0], h[1], w[0], w[1], 3]);
+        put(&[0, luma_sampling, 0, 1, 0x11, 1, 2, 0x11, 1]);
+        let tables: [(u8, &[u8; 16], &[u8]); 4] = [
+            (0x00, &STD_LUMA_DC_CODE_LENGTHS, &STD_LUMA_DC_VALUES),
+            (0x10, &STD_LUMA_AC_CODE_LENGTHS, &STD_LUMA_AC_VALUES),
+            (0x01, &STD_CHROMA_DC_CODE_LENGTHS, &STD_CHROMA_DC_VALUES),
+            (0x11, &STD_CHROMA_AC_CODE_LENGTHS, &STD_CHROMA_AC_VALUES),
+        ];
+        for (class_id, counts, values) in tables.iter() {
+            let seg_len = (2 + 1 + 16 + values.len()) as u16;
+            put(&[0xFF, DHT]);
+            put(&seg_len.to_be_bytes());
+            put(&[*class_id]);
+            put(*counts);
+            put(values);
+        }
+        if restart_interval != 0 {
+            let ri = restart_interval.to_be_bytes();
+            put(&[0xFF, DRI, 0, 4, ri[0], ri[1]]);
+        }
+        put(&[0xFF, SOS, 0, 12, 3, 0, 0x00, 1, 0x11, 2, 0x11, 0, 63, 0]);
+        put(scan);
+        put(&[0xFF, EOI]);
+        len
+    }
+
+    /// Level shifted sample of a flat block with DC coefficient `dc`
+    fn flat(dc: i32) -> u8 {
+        (128 + (dc / 8)) as u8
+    }
+
+    #[test]
+    fn flat_blocks_420() {
+        // 32x16, two MCUs of four Y blocks and one Cb and Cr block
+        let luma = [[-512, 0, 256, 512], [800, 800, 800, 800]];
+        let cb = [80, -160];
+        let cr = [-80, 160];
+
+        let mut scan = [0; 64];
+        let mut w = BitWriter::new(&mut scan);
+        let mut pred = [0; 3];
+        for mcu in 0..2 {
+            for &dc in luma[mcu].iter() {
+                w.block(true, dc - pred[0], &[]);
+                pred[0] = dc;
+            }
+            w.block(false, cb[mcu] - pred[1], &[]);
+            pred[1] = cb[mcu];
+            w.block(false, cr[mcu] - pred[2], &[]);
+            pred[2] = cr[mcu];
+        }
+        let scan_len = w.finish();
+
+        let mut data = [0; 1024];
+        let len = jfif(&mut data, 32, 16, 0x22, 0, &scan[..scan_len]);
+
+        let size = storage_size(32, 16, Subsampling::Yuv420);
+        assert_eq!(size, (32 * 16) + (2 * 16 * 8));
+        let mut storage = [0; 1024];
+        let planes = decode(&data[..len], &mut storage[..size]).unwrap();
+        assert_eq!(planes.width(), 32);
+        assert_eq!(planes.height(), 16);
+        assert_eq!(planes.subsampling(), Subsampling::Yuv420);
+
+        for y in 0..16 {
+            for x in 0..32 {
+                let (mcu, block) = (x / 16, ((y / 8) * 2) + ((x % 16) / 8));
+                assert_eq!(
+                    planes.components(x, y),
+                    [flat(luma[mcu][block]), flat(cb[mcu]), flat(cr[mcu])],
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ac_coefficient() {
+        // 8x8 4:4:4, first horizontal AC coefficient only
+        let mut scan = [0; 64];
+        let mut w = BitWriter::new(&mut scan);
+        w.block(true, 0, &[(1, 100)]);
+        w.block(false, 0, &[]);
+        w.block(false, 0, &[]);
+        let scan_len = w.finish();
+
+        let mut data = [0; 1024];
+        let len = jfif(&mut data, 8, 8, 0x11, 0, &scan[..scan_len]);
+        let mut storage = [0; 3 * 64];
+        let planes = decode(&data[..len], &mut storage).unwrap();
+
+        // 128 + 100 / (4 * sqrt(2)) * cos((2x + 1) * PI / 16)
+        let expected = [
+            145.34, 142.70, 137.82, 131.45, 124.55, 118.18, 113.30, 110.66,
+        ];
+        for y in 0..8 {
+            for (x, e) in expected.iter().enumerate() {
+                let [luma, cb, cr] = planes.components(x, y);
+                assert!(
+                    (f64::from(luma) - e).abs() <= 1.0,
+                    "({}, {}) {}",
+                    x,
+                    y,
+                    luma
+                );
+                assert_eq!([cb, cr], [128, 128]);
+            }
+        }
+    }
+
+    #[test]
+    fn restart_interval() {
+        // 32x8 4:2:2, two MCUs with a restart marker between them
+        let luma = [[160, 320], [-160, -320]];
+        let chroma = [40, -40];
+
+        let mut scan = [0; 64];
+        let mut w = BitWriter::new(&mut scan);
+        for mcu in 0..2 {
+            if mcu != 0 {
+                w.restart(0);
+            }
+            // Predictions start over after the marker
+            w.block(true, luma[mcu][0], &[]);
+            w.block(true, luma[mcu][1] - luma[mcu][0], &[]);
+            w.block(false, chroma[mcu], &[]);
+            w.block(false, -chroma[mcu], &[]);
+        }
+        let scan_len = w.finish();
+
+        let mut data = [0; 1024];
+        let len = jfif(&mut data, 32, 8, 0x21, 1, &scan[..scan_len]);
+        let mut storage = [0; 1024];
+        let planes = decode(&data[..len], &mut storage).unwrap();
+        assert_eq!(planes.subsampling(), Subsampling::Yuv422);
+        for x in 0..32 {
+            let (mcu, block) = (x / 16, (x % 16) / 8);
+            assert_eq!(
+                planes.components(x, 7),
+                [
+                    flat(luma[mcu][block]),
+                    flat(chroma[mcu]),
+                    flat(-chroma[mcu])
+                ]
+            );
+        }
+
+        // Same data without the restart interval, the marker is where the
+        // second MCU should be
+        let len = jfif(&mut data, 32, 8, 0x21, 0, &scan[..scan_len]);
+        let planes = decode(&data[..len], &mut storage).unwrap();
+        assert_ne!(planes.components(16, 0)[0], flat(luma[1][0]));
+
+        // Restart interval without the marker
+        let mut scan = [0; 64];
+        let mut w = BitWriter::new(&mut scan);
+        for _ in 0..2 {
+            for _ in 0..4 {
+                w.block(true, 0, &[]);
+            }
+        }
+        let scan_len = w.finish();
+        let len = jfif(&mut data, 32, 8, 0x21, 1, &scan[..scan_len]);
+        assert_eq!(
+            decode(&data[..len], &mut storage).err(),
+            Some(Error::Syntax)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut scan = [0; 64];
+        let mut w = BitWriter::new(&mut scan);
+        for _ in 0..3 {
+            w.block(true, 0, &[]);
+        }
+        let scan_len = w.finish();
+
+        let mut data = [0; 1024];
+        let len = jfif(&mut data, 8, 8, 0x11, 0, &scan[..scan_len]);
+        let mut storage = [0; 3 * 64];
+        assert_eq!(
+            decode(&data[..len], &mut storage[..(3 * 64) - 1]).err(),
+            Some(Error::StorageOverflow)
+        );
+
+        // Scan header cut off
+        assert_eq!(
+            decode(&data[..len - scan_len - 4], &mut storage).err(),
+            Some(Error::Truncated)
+        );
+
+        // Luma sampled 1x2
+        let len = jfif(&mut data, 8, 16, 0x12, 0, &scan[..scan_len]);
+        assert_eq!(
+            decode(&data[..len], &mut storage).err(),
+            Some(Error::Unsupported)
+        );
+
+        // Progressive
+        let len = jfif(&mut data, 8, 8, 0x11, 0, &scan[..scan_len]);
+        let sof = data[..len]
+            .windows(2)
+            .position(|m| m == [0xFF, SOF])
+            .unwrap();
+        data[sof + 1] = 0xC2;
+        assert_eq!(
+            decode(&data[..len], &mut storage).err(),
+            Some(Error::Unsupported)
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Codec-agnostic payload depacketization
+
+use crate::Packet;
+use core::fmt;
+use core::ops::RangeInclusive;
+
+/// Payload types available for dynamic assignment, typically negotiated
+/// with SDP `a=rtpmap` attributes
+///
+/// [RFC1890](https://tools.ietf.org/html/rfc1890#section-3)
+pub const DYNAMIC_PAYLOAD_TYPES: RangeInclusive<u8> = 96..=127;
+
+/// Reassembles RTP payloads into complete frames
+pub trait Depacketizer {
+    type Error: fmt::Debug;
+
+    /// The RTP payload type packets are expected to carry
+    fn payload_type(&self) -> u8;
+
+    /// Changes the expected RTP payload type, e.g. once it's known from
+    /// the session description
+    fn set_payload_type(&mut self, payload_type: u8);
+
+    /// Pushes the next received packet.
+    ///
+    /// Returns the complete frame once the last of its packets has been
+    /// received.
+    /// The frame data is only valid until the next packet is pushed.
+    fn push(&mut self, packet: &Packet<&[u8]>) -> Result<Option<Frame<'_>>, Self::Error>;
+
+    /// Discards any partial frame and clears the statistics
+    fn reset(&mut self);
+
+    fn statistics(&self) -> Statistics;
+}
+
+/// A complete, reassembled frame
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Frame<'a> {
+    /// RTP timestamp shared by all of the frame's packets
+    pub timestamp: u32,
+    /// Codec specific byte stream, i.e. JFIF for JPEG or Annex B for H.264
+    pub data: &'a [u8],
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Statistics {
+    /// Packets pushed, including ones that were ignored
+    pub packets: usize,
+    /// Complete frames emitted
+    pub frames: usize,
+    /// Incomplete or corrupt frames that were discarded
+    pub dropped_frames: usize,
+}
+
+impl Statistics {
+    pub const fn new() -> Self {
+        Statistics {
+            packets: 0,
+            frames: 0,
+            dropped_frames: 0,
+        }
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Statistics {{packets={}, frames={}, dropped_frames={}}}",
+            self.packets, self.frames, self.dropped_frames,
+        )
+    }
+}
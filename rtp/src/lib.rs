@@ -2,9 +2,12 @@
 
 #![no_std]
 
+pub use crate::depacketizer::{Depacketizer, Frame, Statistics, DYNAMIC_PAYLOAD_TYPES};
 use byteorder::{BigEndian, ByteOrder};
 use core::fmt;
 
+pub mod depacketizer;
+
 /// Minimum of 12 bytes
 pub const HEADER_SIZE: usize = 12;
 